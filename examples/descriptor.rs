@@ -7,13 +7,13 @@ use std::{fs::File, io::BufWriter};
 use nexg::{
     Buffer, BufferDescriptor, BufferUsage, CommandPoolDescriptor, CommandRecorderDescriptor,
     DataFormat, Extent3d, FrameBuffer, FrameBufferDescriptor, Image, ImageDescriptor, ImageFormat,
-    ImageViewDescriptor, InstanceBuilder, InstanceFeature, LoadOp, Pipeline, PipelineDescriptor,
-    PipelineLayout, PipelineLayoutDescriptor, PipelineVertexInputDescriptor, QueueSubmitDescriptor,
-    RenderPass, RenderPassBeginDescriptor, RenderPassDescriptor, RequestConnecterDescriptor,
-    Resource, ResourceBufferDescriptor, ResourceLayout, ResourceLayoutBinding, ResourcePool,
-    ResourcePoolDescriptor, ResourcePoolSize, ResourceType, ResourceUpdateDescriptor, Shader,
-    ShaderStage, ShaderStageDescriptor, Spirv, StoreOp, SubPass, SubPassDescriptor,
-    VertexInputAttributeDescriptor, VertexInputBindingDescriptor,
+    ImageTiling, ImageViewDescriptor, InstanceBuilder, InstanceFeature, LoadOp, Pipeline,
+    PipelineDescriptor, PipelineLayout, PipelineLayoutDescriptor, PipelineVertexInputDescriptor,
+    QueueSubmitDescriptor, RenderPass, RenderPassBeginDescriptor, RenderPassDescriptor,
+    RequestConnecterDescriptor, Resource, ResourceBufferDescriptor, ResourceLayout,
+    ResourceLayoutBinding, ResourcePool, ResourcePoolDescriptor, ResourcePoolSize, ResourceType,
+    ResourceUpdateDescriptor, Shader, ShaderStage, ShaderStageDescriptor, Spirv, StoreOp, SubPass,
+    SubPassDescriptor, VertexInputAttributeDescriptor, VertexInputBindingDescriptor,
 };
 use png::text_metadata::ZTXtChunk;
 use simple_logger::SimpleLogger;
@@ -85,7 +85,10 @@ fn main() {
     let pool = device.create_command_pool(&desc).unwrap();
     let desc = CommandRecorderDescriptor::empty();
     let recorders = device.allocate_command_recorder(pool, &desc).unwrap();
-    let desc = ImageDescriptor::new().extent(Extent3d::new(WIDTH, HEIGHT, 1));
+    // Linear tiling in host visible memory lets the image be read back with `as_raw_data`.
+    let desc = ImageDescriptor::new()
+        .extent(Extent3d::new(WIDTH, HEIGHT, 1))
+        .tiling(ImageTiling::Linear);
     let image = Image::create(&instance, &device, connecter, &desc).unwrap();
     let desc = ImageViewDescriptor::empty().format(ImageFormat::R8G8B8A8Unorm);
     let image_view = image.create_image_view(&device, &desc);
@@ -122,13 +125,13 @@ fn main() {
         .resource_type(ResourceType::UniformBuffer)
         .count(1)
         .shader_stage(ShaderStage::Vertex)];
-    let resource_layout = ResourceLayout::new(&device, &resource_layout_bindings);
+    let resource_layout = ResourceLayout::new(&device, &resource_layout_bindings).unwrap();
     let pool_sizes = vec![ResourcePoolSize::empty()];
     let pool_desc = ResourcePoolDescriptor::empty()
         .pool_sizes(&pool_sizes)
         .max_sets(1);
     let resource_pool = ResourcePool::new(&device, &pool_desc);
-    let resource = Resource::allocate(&device, &resource_pool, &resource_layout);
+    let resource = Resource::allocate(&device, &resource_pool, &resource_layout).unwrap();
 
     let buffer_desc = ResourceBufferDescriptor::new::<SceneData>(&uniform_buffer);
     let desc = vec![buffer_desc];
@@ -180,7 +183,7 @@ fn main() {
         .input_descriptor(&vertex_input_desc)
         .width(WIDTH)
         .height(HEIGHT);
-    let pipeline = Pipeline::new(&device, pipeline_layout, &render_pass, &desc).unwrap();
+    let pipeline = Pipeline::new(&device, &pipeline_layout, &render_pass, &desc).unwrap();
 
     let desc = FrameBufferDescriptor::empty()
         .render_pass(&render_pass)
@@ -3,8 +3,8 @@ use std::mem::offset_of;
 use std::{env, fs::File, io::BufWriter};
 
 use nexg::{
-    Buffer, BufferDescriptor, BufferUsage, CommandPoolDescriptor, CommandRecorderDescriptor,
-    DataFormat, Extent3d, FrameBuffer, FrameBufferDescriptor, Image, ImageDescriptor, ImageFormat,
+    Buffer, BufferDescriptor, CommandPoolDescriptor, CommandRecorderDescriptor, DataFormat,
+    Extent3d, FrameBuffer, FrameBufferDescriptor, Image, ImageDescriptor, ImageFormat, ImageTiling,
     ImageViewDescriptor, InstanceBuilder, InstanceFeature, LoadOp, Pipeline, PipelineDescriptor,
     PipelineLayout, PipelineLayoutDescriptor, PipelineVertexInputDescriptor, QueueSubmitDescriptor,
     RenderPass, RenderPassBeginDescriptor, RenderPassDescriptor, RequestConnecterDescriptor,
@@ -67,7 +67,10 @@ fn main() {
     let pool = device.create_command_pool(&desc).unwrap();
     let desc = CommandRecorderDescriptor::empty();
     let recorders = device.allocate_command_recorder(pool, &desc).unwrap();
-    let desc = ImageDescriptor::new().extent(Extent3d::new(WIDTH, HEIGHT, 1));
+    // Linear tiling in host visible memory lets the image be read back with `as_raw_data`.
+    let desc = ImageDescriptor::new()
+        .extent(Extent3d::new(WIDTH, HEIGHT, 1))
+        .tiling(ImageTiling::Linear);
     let image = Image::create(&instance, &device, connecter, &desc).unwrap();
     let desc = ImageViewDescriptor::empty().format(ImageFormat::R8G8B8A8Unorm);
     let image_view = image.create_image_view(&device, &desc);
@@ -94,12 +97,7 @@ fn main() {
     let vertex_buffer = Buffer::new(&instance, connecter, &device, &desc).unwrap();
     vertex_buffer.write(&device, VERTEX.as_ptr() as *const c_void);
     vertex_buffer.lock(&device);
-    let desc = BufferDescriptor::empty()
-        .size(std::mem::size_of::<u16>() * INDICES.len())
-        .usage(BufferUsage::Index);
-    let index_buffer = Buffer::new(&instance, connecter, &device, &desc).unwrap();
-    index_buffer.write(&device, INDICES.as_ptr() as *const c_void);
-    index_buffer.lock(&device);
+    let index_buffer = Buffer::index_from_slice(&instance, connecter, &device, &INDICES).unwrap();
 
     let desc = SubPassDescriptor::empty();
     let subpass = SubPass::new(connecter, &desc);
@@ -144,7 +142,7 @@ fn main() {
         .input_descriptor(&vertex_input_desc)
         .width(WIDTH)
         .height(HEIGHT);
-    let pipeline = Pipeline::new(&device, pipeline_layout, &render_pass, &desc).unwrap();
+    let pipeline = Pipeline::new(&device, &pipeline_layout, &render_pass, &desc).unwrap();
 
     let desc = FrameBufferDescriptor::empty()
         .render_pass(&render_pass)
@@ -0,0 +1,167 @@
+use nexg::{
+    CommandPoolDescriptor, CommandRecorderDescriptor, DeviceFeatures, Fence, FenceDescriptor,
+    FrameBuffer, FrameBufferDescriptor, InstanceBuilder, InstanceFeature, LoadOp,
+    QueuePresentDescriptor, QueueSelection, QueueSubmitDescriptor, RenderPass,
+    RenderPassBeginDescriptor, RenderPassDescriptor, RequestConnecterDescriptor, Semaphore,
+    SemaphoreDescriptor, StoreOp, SubPass, SubPassDescriptor, Surface, Swapchain,
+};
+use simple_logger::SimpleLogger;
+use winit::{
+    event::{Event, WindowEvent},
+    event_loop::EventLoop,
+    window::WindowBuilder,
+};
+
+/// Clear color of each window.
+const COLORS: [(f32, f32, f32); 2] = [(0.8, 0.2, 0.2), (0.2, 0.2, 0.8)];
+
+fn main() {
+    SimpleLogger::new().init().unwrap();
+
+    let event_loop = EventLoop::new();
+    let windows = COLORS
+        .iter()
+        .enumerate()
+        .map(|(i, _)| {
+            WindowBuilder::new()
+                .with_title(format!("Window {}", i))
+                .with_inner_size(winit::dpi::LogicalSize::new(480, 360))
+                .build(&event_loop)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut feature = InstanceFeature::empty();
+    feature.use_surface(&windows[0]).unwrap();
+    let instance = InstanceBuilder::new().feature(feature).build().unwrap();
+
+    // Each window has its own surface, swapchain, frame buffers and synchronization.
+    let surfaces = windows
+        .iter()
+        .map(|x| Surface::new(&instance, x).unwrap())
+        .collect::<Vec<_>>();
+
+    // One device is shared by every window, so it must present to all of their surfaces.
+    let mut desc = RequestConnecterDescriptor::new().graphic_support(true);
+    for surface in &surfaces {
+        desc = desc.present_support(surface);
+    }
+    let connecter = instance.request_connecters(&[desc]).unwrap()[0];
+    let index = connecter.get_queue_family_index();
+    let device = connecter
+        .create_device_for_surfaces(
+            &instance,
+            index,
+            &surfaces.iter().collect::<Vec<_>>(),
+            &DeviceFeatures::empty(),
+        )
+        .unwrap();
+    let queue = device.get_queue(index);
+    let present_queue = device.queue(QueueSelection::Present);
+
+    let subpasses = &[SubPass::new(connecter, &SubPassDescriptor::empty())];
+    let desc = RenderPassDescriptor::empty()
+        .subpasses(subpasses)
+        .load_op(LoadOp::Clear)
+        .store_op(StoreOp::Store);
+    let render_pass = RenderPass::new(&device, &desc).unwrap();
+
+    let swapchains = surfaces
+        .iter()
+        .map(|x| Swapchain::new(x, &instance, &device, connecter).unwrap())
+        .collect::<Vec<_>>();
+    let frame_buffers = swapchains
+        .iter()
+        .map(|swapchain| {
+            let extent = swapchain.extent();
+            swapchain
+                .images()
+                .map(|(_, view)| {
+                    let desc = FrameBufferDescriptor::empty()
+                        .render_pass(&render_pass)
+                        .image_view(view)
+                        .width(extent.width())
+                        .height(extent.height());
+                    FrameBuffer::new(&device, &desc).unwrap()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let desc = CommandPoolDescriptor::empty().queue_family_index(index);
+    let pool = device.create_command_pool(&desc).unwrap();
+    let desc = CommandRecorderDescriptor::empty().recorder_count(windows.len() as u32);
+    let recorders = device.allocate_command_recorder(pool, &desc).unwrap();
+    let fences = windows
+        .iter()
+        .map(|_| Fence::new(&device, &FenceDescriptor::empty().signaled(true)).unwrap())
+        .collect::<Vec<_>>();
+    let semaphores = windows
+        .iter()
+        .map(|_| {
+            (
+                Semaphore::new(&device, &SemaphoreDescriptor::empty()).unwrap(),
+                Semaphore::new(&device, &SemaphoreDescriptor::empty()).unwrap(),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    event_loop.run(move |event, _, control_flow| {
+        control_flow.set_poll();
+        match event {
+            Event::RedrawEventsCleared => {
+                for window in &windows {
+                    window.request_redraw();
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => control_flow.set_exit(),
+            Event::RedrawRequested(id) => {
+                let Some(i) = windows.iter().position(|x| x.id() == id) else {
+                    return;
+                };
+                let (image_available, render_finished) = &semaphores[i];
+                let Some(img) = swapchains[i]
+                    .acquire_next_image(Some(image_available))
+                    .unwrap()
+                    .index()
+                else {
+                    return;
+                };
+
+                fences[i].wait(&device, u64::MAX).unwrap();
+                fences[i].reset(&device).unwrap();
+
+                let extent = swapchains[i].extent();
+                let (r, g, b) = COLORS[i];
+                let begin_desc = RenderPassBeginDescriptor::empty()
+                    .width(extent.width())
+                    .height(extent.height())
+                    .clear(r, g, b, 1.0)
+                    .render_pass(&render_pass)
+                    .frame_buffer(&frame_buffers[i][img]);
+                recorders[i].reset(&device).unwrap();
+                recorders[i].begin(&device, begin_desc).unwrap();
+                recorders[i].end(&device).unwrap();
+
+                let w_semaphores = &[*image_available];
+                let s_semaphores = &[*render_finished];
+                let desc = QueueSubmitDescriptor::empty()
+                    .wait_semaphores(w_semaphores)
+                    .signal_semaphores(s_semaphores)
+                    .fence(&fences[i]);
+                queue
+                    .submit(&device, &desc, std::slice::from_ref(&recorders[i]))
+                    .unwrap();
+
+                let desc = QueuePresentDescriptor::empty().wait_semaphores(s_semaphores);
+                swapchains[i]
+                    .present(&present_queue, &desc, img as u32)
+                    .unwrap();
+            }
+            _ => (),
+        }
+    });
+}
@@ -4,7 +4,7 @@ use std::{env, fs::File, io::BufWriter};
 
 use nexg::{
     Buffer, BufferDescriptor, CommandPoolDescriptor, CommandRecorderDescriptor, DataFormat,
-    Extent3d, FrameBuffer, FrameBufferDescriptor, Image, ImageDescriptor, ImageFormat,
+    Extent3d, FrameBuffer, FrameBufferDescriptor, Image, ImageDescriptor, ImageFormat, ImageTiling,
     ImageViewDescriptor, InstanceBuilder, InstanceFeature, LoadOp, Pipeline, PipelineDescriptor,
     PipelineLayout, PipelineLayoutDescriptor, PipelineVertexInputDescriptor, QueueSubmitDescriptor,
     RenderPass, RenderPassBeginDescriptor, RenderPassDescriptor, RequestConnecterDescriptor,
@@ -61,7 +61,10 @@ fn main() {
     let pool = device.create_command_pool(&desc).unwrap();
     let desc = CommandRecorderDescriptor::empty();
     let recorders = device.allocate_command_recorder(pool, &desc).unwrap();
-    let desc = ImageDescriptor::new().extent(Extent3d::new(WIDTH, HEIGHT, 1));
+    // Linear tiling in host visible memory lets the image be read back with `as_raw_data`.
+    let desc = ImageDescriptor::new()
+        .extent(Extent3d::new(WIDTH, HEIGHT, 1))
+        .tiling(ImageTiling::Linear);
     let image = Image::create(&instance, &device, connecter, &desc).unwrap();
     let desc = ImageViewDescriptor::empty().format(ImageFormat::R8G8B8A8Unorm);
     let image_view = image.create_image_view(&device, &desc);
@@ -132,7 +135,7 @@ fn main() {
         .input_descriptor(&vertex_input_desc)
         .width(WIDTH)
         .height(HEIGHT);
-    let pipeline = Pipeline::new(&device, pipeline_layout, &render_pass, &desc).unwrap();
+    let pipeline = Pipeline::new(&device, &pipeline_layout, &render_pass, &desc).unwrap();
 
     let desc = FrameBufferDescriptor::empty()
         .render_pass(&render_pass)
@@ -1,12 +1,12 @@
 use nexg::{
     Buffer, BufferDescriptor, BufferUsage, CommandPoolDescriptor, CommandRecorderDescriptor,
-    DataFormat, Fence, FenceDescriptor, FrameBuffer, FrameBufferDescriptor, ImageViewDescriptor,
+    DataFormat, DeviceFeatures, Fence, FenceDescriptor, FrameBuffer, FrameBufferDescriptor,
     InstanceBuilder, InstanceFeature, LoadOp, Pipeline, PipelineDescriptor, PipelineLayout,
     PipelineLayoutDescriptor, PipelineVertexInputDescriptor, QueuePresentDescriptor,
-    QueueSubmitDescriptor, RenderPass, RenderPassBeginDescriptor, RenderPassDescriptor,
-    RequestConnecterDescriptor, Resource, ResourceBufferDescriptor, ResourceLayout,
-    ResourceLayoutBinding, ResourcePool, ResourcePoolDescriptor, ResourcePoolSize, ResourceType,
-    ResourceUpdateDescriptor, Semaphore, SemaphoreDescriptor, Shader, ShaderStage,
+    QueueSelection, QueueSubmitDescriptor, RenderPass, RenderPassBeginDescriptor,
+    RenderPassDescriptor, RequestConnecterDescriptor, Resource, ResourceBufferDescriptor,
+    ResourceLayout, ResourceLayoutBinding, ResourcePool, ResourcePoolDescriptor, ResourcePoolSize,
+    ResourceType, ResourceUpdateDescriptor, Semaphore, SemaphoreDescriptor, Shader, ShaderStage,
     ShaderStageDescriptor, Spirv, StoreOp, SubPass, SubPassDescriptor, Surface, Swapchain,
     VertexInputAttributeDescriptor, VertexInputBindingDescriptor,
 };
@@ -84,31 +84,27 @@ fn main() {
     let mut feature = InstanceFeature::empty();
     feature.use_surface(&window).unwrap();
     let instance = InstanceBuilder::new().feature(feature).build().unwrap();
+    let surface = Surface::new(&instance, &window).unwrap();
     let desc = RequestConnecterDescriptor::new()
         .graphic_support(true)
         .compute_support(true)
-        .transfer_support(true);
+        .transfer_support(true)
+        .present_support(&surface);
     let connecters = instance.request_connecters(&[desc]).unwrap();
     let connecter = connecters[0];
     let index = connecter.get_queue_family_index();
 
-    let device = connecter.create_device(&instance, index).unwrap();
-
-    let surface = Surface::new(&instance, &window).unwrap();
+    let device = connecter
+        .create_device_for_surface(&instance, index, &surface, &DeviceFeatures::empty())
+        .unwrap();
     let swapchain = Swapchain::new(&surface, &instance, &device, connecter).unwrap();
 
     let queue = device.get_queue(index);
+    let present_queue = device.queue(QueueSelection::Present);
     let desc = CommandPoolDescriptor::empty().queue_family_index(index);
     let pool = device.create_command_pool(&desc).unwrap();
     let desc = CommandRecorderDescriptor::empty();
     let recorders = device.allocate_command_recorder(pool, &desc).unwrap();
-    let images = swapchain.images().unwrap();
-    let mut swapchain_images = vec![];
-    let desc = ImageViewDescriptor::empty().format(swapchain.format());
-    for i in &images {
-        swapchain_images.push(i.create_image_view(&device, &desc));
-    }
-
     let vertex = Shader::new(&device, &Spirv::from_raw(VERTEX_S).unwrap());
 
     let fragment = Shader::new(&device, &Spirv::from_raw(FRAGMENT_S).unwrap());
@@ -139,13 +135,13 @@ fn main() {
         .resource_type(ResourceType::UniformBuffer)
         .count(1)
         .shader_stage(ShaderStage::Vertex)];
-    let resource_layout = ResourceLayout::new(&device, &resource_layout_bindings);
+    let resource_layout = ResourceLayout::new(&device, &resource_layout_bindings).unwrap();
     let pool_sizes = vec![ResourcePoolSize::empty()];
     let pool_desc = ResourcePoolDescriptor::empty()
         .pool_sizes(&pool_sizes)
         .max_sets(1);
     let resource_pool = ResourcePool::new(&device, &pool_desc);
-    let resource = Resource::allocate(&device, &resource_pool, &resource_layout);
+    let resource = Resource::allocate(&device, &resource_pool, &resource_layout).unwrap();
 
     let buffer_desc = ResourceBufferDescriptor::new::<SceneData>(&uniform_buffer);
     let desc = vec![buffer_desc];
@@ -197,14 +193,14 @@ fn main() {
         .input_descriptor(&vertex_input_desc)
         .width(size.width)
         .height(size.height);
-    let pipeline = Pipeline::new(&device, pipeline_layout, &render_pass, &desc).unwrap();
+    let pipeline = Pipeline::new(&device, &pipeline_layout, &render_pass, &desc).unwrap();
 
     let mut frame_buffers = vec![];
-    for i in &swapchain_images {
+    for (_, view) in swapchain.images() {
         let desc = FrameBufferDescriptor::empty()
             .render_pass(&render_pass)
             .width(size.width)
-            .image_view(i)
+            .image_view(view)
             .height(size.height);
         frame_buffers.push(FrameBuffer::new(&device, &desc).unwrap());
     }
@@ -230,9 +226,13 @@ fn main() {
                 window_id,
             } if window_id == window.id() => control_flow.set_exit(),
             Event::RedrawRequested(id) => {
-                let (img, state) = swapchain
+                let Some(img) = swapchain
                     .acquire_next_image(Some(&swapchain_image_semaphore))
-                    .unwrap();
+                    .unwrap()
+                    .index()
+                else {
+                    return;
+                };
 
                 scene_data.rect_center = Vec4(
                     (0.3 * f64::cos(time)) as f32,
@@ -274,11 +274,9 @@ fn main() {
                 queue.submit(&device, &desc, &recorders).unwrap();
 
                 let w_semaphores = &[image_rendered_semaphore];
-                let desc = QueuePresentDescriptor::empty()
-                    .wait_semaphores(w_semaphores)
-                    .queue(&queue);
+                let desc = QueuePresentDescriptor::empty().wait_semaphores(w_semaphores);
 
-                swapchain.present(&desc, img as u32);
+                swapchain.present(&present_queue, &desc, img as u32);
             }
             _ => (),
         }
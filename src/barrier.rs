@@ -0,0 +1,441 @@
+use crate::{Buffer, CommandRecorder, Device, Image, ImageLayout, NxError, NxResult};
+use ash::vk::{
+    AccessFlags, BufferMemoryBarrier, DependencyFlags, ImageAspectFlags, ImageMemoryBarrier,
+    ImageSubresourceRange, PipelineStageFlags,
+};
+
+/// Represents a stage of the pipeline.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PipelineStage {
+    TopOfPipe,
+    DrawIndirect,
+    VertexInput,
+    VertexShader,
+    FragmentShader,
+    EarlyFragmentTests,
+    LateFragmentTests,
+    ColorAttachmentOutput,
+    ComputeShader,
+    Transfer,
+    BottomOfPipe,
+    Host,
+    AllGraphics,
+    AllCommands,
+}
+
+impl From<PipelineStage> for PipelineStageFlags {
+    fn from(value: PipelineStage) -> Self {
+        match value {
+            PipelineStage::TopOfPipe => PipelineStageFlags::TOP_OF_PIPE,
+            PipelineStage::DrawIndirect => PipelineStageFlags::DRAW_INDIRECT,
+            PipelineStage::VertexInput => PipelineStageFlags::VERTEX_INPUT,
+            PipelineStage::VertexShader => PipelineStageFlags::VERTEX_SHADER,
+            PipelineStage::FragmentShader => PipelineStageFlags::FRAGMENT_SHADER,
+            PipelineStage::EarlyFragmentTests => PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            PipelineStage::LateFragmentTests => PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            PipelineStage::ColorAttachmentOutput => PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            PipelineStage::ComputeShader => PipelineStageFlags::COMPUTE_SHADER,
+            PipelineStage::Transfer => PipelineStageFlags::TRANSFER,
+            PipelineStage::BottomOfPipe => PipelineStageFlags::BOTTOM_OF_PIPE,
+            PipelineStage::Host => PipelineStageFlags::HOST,
+            PipelineStage::AllGraphics => PipelineStageFlags::ALL_GRAPHICS,
+            PipelineStage::AllCommands => PipelineStageFlags::ALL_COMMANDS,
+        }
+    }
+}
+
+/// Represents a kind of memory access.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    IndirectCommandRead,
+    IndexRead,
+    VertexAttributeRead,
+    UniformRead,
+    ShaderRead,
+    ShaderWrite,
+    ColorAttachmentRead,
+    ColorAttachmentWrite,
+    DepthStencilAttachmentRead,
+    DepthStencilAttachmentWrite,
+    TransferRead,
+    TransferWrite,
+    HostRead,
+    HostWrite,
+    MemoryRead,
+    MemoryWrite,
+}
+
+impl From<Access> for AccessFlags {
+    fn from(value: Access) -> Self {
+        match value {
+            Access::IndirectCommandRead => AccessFlags::INDIRECT_COMMAND_READ,
+            Access::IndexRead => AccessFlags::INDEX_READ,
+            Access::VertexAttributeRead => AccessFlags::VERTEX_ATTRIBUTE_READ,
+            Access::UniformRead => AccessFlags::UNIFORM_READ,
+            Access::ShaderRead => AccessFlags::SHADER_READ,
+            Access::ShaderWrite => AccessFlags::SHADER_WRITE,
+            Access::ColorAttachmentRead => AccessFlags::COLOR_ATTACHMENT_READ,
+            Access::ColorAttachmentWrite => AccessFlags::COLOR_ATTACHMENT_WRITE,
+            Access::DepthStencilAttachmentRead => AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ,
+            Access::DepthStencilAttachmentWrite => AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            Access::TransferRead => AccessFlags::TRANSFER_READ,
+            Access::TransferWrite => AccessFlags::TRANSFER_WRITE,
+            Access::HostRead => AccessFlags::HOST_READ,
+            Access::HostWrite => AccessFlags::HOST_WRITE,
+            Access::MemoryRead => AccessFlags::MEMORY_READ,
+            Access::MemoryWrite => AccessFlags::MEMORY_WRITE,
+        }
+    }
+}
+
+const WRITE_ACCESS: AccessFlags = AccessFlags::from_raw(
+    AccessFlags::SHADER_WRITE.as_raw()
+        | AccessFlags::COLOR_ATTACHMENT_WRITE.as_raw()
+        | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw()
+        | AccessFlags::TRANSFER_WRITE.as_raw()
+        | AccessFlags::HOST_WRITE.as_raw()
+        | AccessFlags::MEMORY_WRITE.as_raw(),
+);
+
+/// The last known use of a Buffer or Image.
+/// Used by `transition_to` to emit the barriers needed between two uses.
+/// The layout is ignored for buffers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResourceState {
+    pub(crate) layout: ImageLayout,
+    /// Layout of the stencil aspect, if it differs from `layout`.
+    pub(crate) stencil_layout: Option<ImageLayout>,
+    pub(crate) stages: PipelineStageFlags,
+    pub(crate) access: AccessFlags,
+}
+
+impl ResourceState {
+    /// A resource whose contents are not needed and that has not been used yet.
+    pub const UNDEFINED: Self = Self {
+        layout: ImageLayout::Undefined,
+        stencil_layout: None,
+        stages: PipelineStageFlags::TOP_OF_PIPE,
+        access: AccessFlags::empty(),
+    };
+
+    /// Initializes a state with the given layout and no stages or accesses.
+    #[inline]
+    pub const fn new(layout: ImageLayout) -> Self {
+        Self {
+            layout,
+            stencil_layout: None,
+            stages: PipelineStageFlags::empty(),
+            access: AccessFlags::empty(),
+        }
+    }
+
+    /// Rendered to as a color attachment.
+    pub fn color_attachment() -> Self {
+        Self::new(ImageLayout::ColorAttachment)
+            .add_stage(PipelineStage::ColorAttachmentOutput)
+            .add_access(Access::ColorAttachmentRead)
+            .add_access(Access::ColorAttachmentWrite)
+    }
+
+    /// Sampled or read in a fragment shader.
+    pub fn shader_read() -> Self {
+        Self::new(ImageLayout::ShaderReadOnly)
+            .add_stage(PipelineStage::FragmentShader)
+            .add_access(Access::ShaderRead)
+    }
+
+    /// Source of a copy.
+    pub fn transfer_src() -> Self {
+        Self::new(ImageLayout::TransferSrc)
+            .add_stage(PipelineStage::Transfer)
+            .add_access(Access::TransferRead)
+    }
+
+    /// Destination of a copy.
+    pub fn transfer_dst() -> Self {
+        Self::new(ImageLayout::TransferDst)
+            .add_stage(PipelineStage::Transfer)
+            .add_access(Access::TransferWrite)
+    }
+
+    /// Read and written as a storage image in a compute shader.
+    pub fn compute_storage() -> Self {
+        Self::new(ImageLayout::General)
+            .add_stage(PipelineStage::ComputeShader)
+            .add_access(Access::ShaderRead)
+            .add_access(Access::ShaderWrite)
+    }
+
+    /// Presented to a swapchain.
+    pub fn present() -> Self {
+        Self::new(ImageLayout::PresentSrc).add_stage(PipelineStage::BottomOfPipe)
+    }
+
+    /// Adds a stage in which the resource is used.
+    #[inline]
+    pub fn add_stage(mut self, stage: PipelineStage) -> Self {
+        self.stages |= stage.into();
+        self
+    }
+
+    /// Adds a kind of access to the resource.
+    #[inline]
+    pub fn add_access(mut self, access: Access) -> Self {
+        self.access |= access.into();
+        self
+    }
+
+    /// Uses a different layout for the stencil aspect of a depth/stencil image.
+    /// `layout` then only applies to the depth aspect.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    #[inline]
+    pub fn stencil_layout(mut self, layout: ImageLayout) -> Self {
+        self.stencil_layout = Some(layout);
+        self
+    }
+
+    pub fn layout(&self) -> ImageLayout {
+        self.layout
+    }
+
+    /// Get the layout of the stencil aspect.
+    pub fn get_stencil_layout(&self) -> ImageLayout {
+        self.stencil_layout.unwrap_or(self.layout)
+    }
+
+    /// Whether any of the layouts requires the `separate_depth_stencil_layouts` device feature.
+    pub(crate) fn uses_separate_depth_stencil(&self) -> bool {
+        self.stencil_layout.is_some() || self.layout.is_separate_depth_stencil()
+    }
+
+    pub(crate) fn is_read_only(&self) -> bool {
+        !self.access.intersects(WRITE_ACCESS)
+    }
+
+    /// Stages to wait for when leaving this state. Never empty.
+    pub(crate) fn src_stages(&self) -> PipelineStageFlags {
+        if self.stages.is_empty() {
+            PipelineStageFlags::TOP_OF_PIPE
+        } else {
+            self.stages
+        }
+    }
+
+    /// Stages that wait when entering this state. Never empty.
+    pub(crate) fn dst_stages(&self) -> PipelineStageFlags {
+        if self.stages.is_empty() {
+            PipelineStageFlags::BOTTOM_OF_PIPE
+        } else {
+            self.stages
+        }
+    }
+}
+
+/// A range of mip levels and array layers of an Image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SubresourceRange {
+    base_mip_level: u32,
+    level_count: u32,
+    base_array_layer: u32,
+    layer_count: u32,
+}
+
+impl SubresourceRange {
+    /// All mip levels and array layers of the image.
+    #[inline]
+    pub const fn all() -> Self {
+        Self {
+            base_mip_level: 0,
+            level_count: ash::vk::REMAINING_MIP_LEVELS,
+            base_array_layer: 0,
+            layer_count: ash::vk::REMAINING_ARRAY_LAYERS,
+        }
+    }
+
+    /// Restricts the range to `count` mip levels starting at `base`.
+    #[inline]
+    pub const fn mip_levels(mut self, base: u32, count: u32) -> Self {
+        self.base_mip_level = base;
+        self.level_count = count;
+        self
+    }
+
+    /// Restricts the range to `count` array layers starting at `base`.
+    #[inline]
+    pub const fn array_layers(mut self, base: u32, count: u32) -> Self {
+        self.base_array_layer = base;
+        self.layer_count = count;
+        self
+    }
+
+    /// Whether the range covers every mip level and array layer.
+    pub(crate) fn is_all(&self) -> bool {
+        *self == Self::all()
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn to_vk(self, aspect_mask: ImageAspectFlags) -> ImageSubresourceRange {
+        ImageSubresourceRange::builder()
+            .aspect_mask(aspect_mask)
+            .base_mip_level(self.base_mip_level)
+            .level_count(self.level_count)
+            .base_array_layer(self.base_array_layer)
+            .layer_count(self.layer_count)
+            .build()
+    }
+}
+
+/// Collects buffer and image barriers and records them with a single `vkCmdPipelineBarrier`,
+/// which is cheaper than one call per resource.
+/// The call waits for the union of the source stages of every barrier
+/// and blocks the union of their destination stages.
+///
+/// Only add barriers that can happen at the same time: a resource must not appear twice.
+#[derive(Default)]
+pub struct BarrierBatch {
+    src_stages: PipelineStageFlags,
+    dst_stages: PipelineStageFlags,
+    buffer_barriers: Vec<BufferMemoryBarrier>,
+    image_barriers: Vec<ImageMemoryBarrier>,
+}
+
+impl BarrierBatch {
+    /// Initializes an empty batch.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a barrier moving the whole buffer from `old` to `new`.
+    /// Use `Buffer::batch_transition` for buffers whose state is tracked.
+    pub fn buffer(&mut self, buffer: &Buffer, old: &ResourceState, new: &ResourceState) {
+        let ignored = ash::vk::QUEUE_FAMILY_IGNORED;
+        self.push_buffer(buffer.buffer, old, new, (ignored, ignored));
+    }
+
+    /// Adds a barrier moving `range` of the image from `old` to `new`.
+    /// Use `Image::batch_transition` for images whose state is tracked.
+    pub fn image(
+        &mut self,
+        device: &Device,
+        image: &Image,
+        range: SubresourceRange,
+        old: &ResourceState,
+        new: &ResourceState,
+    ) -> NxResult<()> {
+        if (old.uses_separate_depth_stencil() || new.uses_separate_depth_stencil())
+            && !device.features.separate_depth_stencil_layouts
+        {
+            return Err(NxError::UnsupportedFeature(
+                "separateDepthStencilLayouts is not enabled on the device".to_string(),
+            ));
+        }
+        let ignored = ash::vk::QUEUE_FAMILY_IGNORED;
+        self.push_image(
+            image.image,
+            range.to_vk(image.format.aspect()),
+            old,
+            new,
+            (ignored, ignored),
+        );
+        Ok(())
+    }
+
+    /// Whether no barrier has been added since the last `flush`.
+    pub fn is_empty(&self) -> bool {
+        self.buffer_barriers.is_empty() && self.image_barriers.is_empty()
+    }
+
+    /// Records every barrier added so far and empties the batch.
+    /// Nothing is recorded if the batch is empty.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `recorder` - CommandRecorder to record the barriers to, outside a render pass.
+    pub fn flush(&mut self, device: &Device, recorder: &CommandRecorder) {
+        if self.is_empty() {
+            return;
+        }
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                recorder.buffer,
+                self.src_stages,
+                self.dst_stages,
+                DependencyFlags::empty(),
+                &[],
+                &self.buffer_barriers,
+                &self.image_barriers,
+            );
+        }
+        *self = Self::default();
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn push_buffer(
+        &mut self,
+        buffer: ash::vk::Buffer,
+        old: &ResourceState,
+        new: &ResourceState,
+        queue_families: (u32, u32),
+    ) {
+        self.src_stages |= old.src_stages();
+        self.dst_stages |= new.dst_stages();
+        self.buffer_barriers.push(
+            BufferMemoryBarrier::builder()
+                .src_access_mask(old.access)
+                .dst_access_mask(new.access)
+                .src_queue_family_index(queue_families.0)
+                .dst_queue_family_index(queue_families.1)
+                .buffer(buffer)
+                .offset(0)
+                .size(ash::vk::WHOLE_SIZE)
+                .build(),
+        );
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn push_image(
+        &mut self,
+        image: ash::vk::Image,
+        range: ImageSubresourceRange,
+        old: &ResourceState,
+        new: &ResourceState,
+        queue_families: (u32, u32),
+    ) {
+        let depth_stencil = ImageAspectFlags::DEPTH | ImageAspectFlags::STENCIL;
+        // The stencil aspect gets its own barrier when its layout differs from the depth aspect.
+        let aspects = if range.aspect_mask.contains(depth_stencil)
+            && (old.stencil_layout.is_some() || new.stencil_layout.is_some())
+        {
+            vec![
+                (ImageAspectFlags::DEPTH, old.layout, new.layout),
+                (
+                    ImageAspectFlags::STENCIL,
+                    old.get_stencil_layout(),
+                    new.get_stencil_layout(),
+                ),
+            ]
+        } else {
+            vec![(range.aspect_mask, old.layout, new.layout)]
+        };
+        self.src_stages |= old.src_stages();
+        self.dst_stages |= new.dst_stages();
+        self.image_barriers.extend(aspects.into_iter().map(
+            |(aspect_mask, old_layout, new_layout)| {
+                ImageMemoryBarrier::builder()
+                    .src_access_mask(old.access)
+                    .dst_access_mask(new.access)
+                    .old_layout(old_layout.into())
+                    .new_layout(new_layout.into())
+                    .src_queue_family_index(queue_families.0)
+                    .dst_queue_family_index(queue_families.1)
+                    .image(image)
+                    .subresource_range(ImageSubresourceRange {
+                        aspect_mask,
+                        ..range
+                    })
+                    .build()
+            },
+        ));
+    }
+}
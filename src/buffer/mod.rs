@@ -1,13 +1,33 @@
-use crate::mem::DeviceMemory;
-use crate::{Destroy, Device, DeviceConnecter, Instance, NxError, NxResult};
-use ash::vk::{BufferCreateInfo, BufferUsageFlags, MappedMemoryRange, MemoryMapFlags, SharingMode};
+use crate::mem::{DeviceMemory, MappedMemory, ResourceMemoryRequirements};
+use crate::{
+    BarrierBatch, CommandRecorder, DedicatedAllocation, Destroy, Device, DeviceConnecter,
+    ExternalMemoryHandleType, Instance, NxError, NxResult, QueueSelection, ResourceState,
+};
+use ash::vk::{
+    BufferCopy, BufferCreateFlags, BufferCreateInfo, BufferDeviceAddressInfo, BufferUsageFlags,
+    ExternalMemoryBufferCreateInfo, IndexType, MemoryAllocateFlags, MemoryMapFlags,
+    MemoryRequirements, SharingMode,
+};
 use std::ffi::c_void;
+use std::sync::Mutex;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum BufferUsage {
     Vertex,
     Index,
     Uniform,
+    Storage,
+    /// Buffer holding the predicate of conditional rendering.
+    ConditionalRendering,
+    /// Shader binding table of a ray tracing pipeline.
+    /// Requires the `ray_tracing_pipeline` device feature.
+    ShaderBindingTable,
+    /// Source of copy commands.
+    TransferSrc,
+    /// Destination of copy commands.
+    TransferDst,
+    /// Parameters and counts of indirect draws.
+    Indirect,
 }
 
 impl From<BufferUsage> for BufferUsageFlags {
@@ -16,13 +36,57 @@ impl From<BufferUsage> for BufferUsageFlags {
             BufferUsage::Vertex => BufferUsageFlags::VERTEX_BUFFER,
             BufferUsage::Index => BufferUsageFlags::INDEX_BUFFER,
             BufferUsage::Uniform => BufferUsageFlags::UNIFORM_BUFFER,
+            BufferUsage::Storage => BufferUsageFlags::STORAGE_BUFFER,
+            BufferUsage::ConditionalRendering => BufferUsageFlags::CONDITIONAL_RENDERING_EXT,
+            BufferUsage::ShaderBindingTable => {
+                BufferUsageFlags::SHADER_BINDING_TABLE_KHR | BufferUsageFlags::SHADER_DEVICE_ADDRESS
+            }
+            BufferUsage::TransferSrc => BufferUsageFlags::TRANSFER_SRC,
+            BufferUsage::TransferDst => BufferUsageFlags::TRANSFER_DST,
+            BufferUsage::Indirect => BufferUsageFlags::INDIRECT_BUFFER,
         }
     }
 }
 
+/// Indicates the type of the elements of an index buffer.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IndexFormat {
+    U16,
+    U32,
+}
+
+impl From<IndexFormat> for IndexType {
+    fn from(value: IndexFormat) -> Self {
+        match value {
+            IndexFormat::U16 => IndexType::UINT16,
+            IndexFormat::U32 => IndexType::UINT32,
+        }
+    }
+}
+
+/// Types that can be stored in an index buffer.
+pub trait IndexElement: Copy {
+    const FORMAT: IndexFormat;
+}
+
+impl IndexElement for u16 {
+    const FORMAT: IndexFormat = IndexFormat::U16;
+}
+
+impl IndexElement for u32 {
+    const FORMAT: IndexFormat = IndexFormat::U32;
+}
+
 pub struct BufferDescriptor {
     size: usize,
     usage: BufferUsage,
+    additional_usage: BufferUsageFlags,
+    element_count: usize,
+    element_alignment: Option<usize>,
+    external_memory: Option<ExternalMemoryHandleType>,
+    dedicated_allocation: DedicatedAllocation,
+    zero_initialized: bool,
+    sparse: bool,
 }
 
 impl BufferDescriptor {
@@ -30,24 +94,156 @@ impl BufferDescriptor {
         Self {
             size: 0,
             usage: BufferUsage::Vertex,
+            additional_usage: BufferUsageFlags::empty(),
+            element_count: 1,
+            element_alignment: None,
+            external_memory: None,
+            dedicated_allocation: DedicatedAllocation::Auto,
+            zero_initialized: false,
+            sparse: false,
         }
     }
 
+    /// Specifies the size of one element.
+    /// Unless `element_count` is used, this is the size of the buffer.
     pub fn size(mut self, size: usize) -> Self {
         self.size = size;
         self
     }
 
+    /// Specifies the number of elements in the buffer.
+    pub fn element_count(mut self, element_count: usize) -> Self {
+        self.element_count = element_count;
+        self
+    }
+
+    /// Pads each element so that it can be bound at a dynamic offset.
+    /// The stride is rounded up to the larger of `alignment` and
+    /// `Device::min_buffer_offset_alignment` of the usage, which for a buffer used as both
+    /// a uniform and a storage buffer is the stricter of the two alignments.
+    pub fn element_alignment(mut self, alignment: usize) -> Self {
+        self.element_alignment = Some(alignment);
+        self
+    }
+
     pub fn usage(mut self, usage: BufferUsage) -> Self {
         self.usage = usage;
         self
     }
+
+    /// Adds a usage to the buffer, for example `TransferSrc` to copy from it,
+    /// or `Storage` to bind a uniform buffer as a storage buffer as well.
+    pub fn add_usage(mut self, usage: BufferUsage) -> Self {
+        self.additional_usage |= usage.into();
+        self
+    }
+
+    /// Allocates memory that can be exported as a handle of `handle_type`.
+    /// Requires the `external_memory` device feature.
+    pub fn external_memory(mut self, handle_type: ExternalMemoryHandleType) -> Self {
+        self.external_memory = Some(handle_type);
+        self
+    }
+
+    /// Specifies whether the buffer gets a dedicated allocation.
+    /// The driver decides by default.
+    pub fn dedicated_allocation(mut self, dedicated_allocation: DedicatedAllocation) -> Self {
+        self.dedicated_allocation = dedicated_allocation;
+        self
+    }
+
+    /// Fills the buffer with zeros when it is created.
+    /// The memory is mapped and cleared, which costs time proportional to the size.
+    pub fn zero_initialized(mut self, zero_initialized: bool) -> Self {
+        self.zero_initialized = zero_initialized;
+        self
+    }
+
+    /// Creates the buffer without memory, so that memory can be bound to parts of it
+    /// with `Queue::bind_sparse`. Requires the `sparse_buffer` device feature.
+    /// Sparse buffers cannot be mapped, exported or zero-initialized.
+    pub fn sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+}
+
+/// A range copied between two buffers by `CommandRecorder::copy_buffer_regions`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BufferCopyRegion {
+    pub(crate) src_offset: u64,
+    pub(crate) dst_offset: u64,
+    pub(crate) size: u64,
+}
+
+impl BufferCopyRegion {
+    /// Initializes a new region.
+    /// # Arguments
+    ///
+    /// * `src_offset` - Offset in bytes of the range in the source buffer.
+    /// * `dst_offset` - Offset in bytes of the range in the destination buffer.
+    /// * `size` - Number of bytes copied.
+    #[inline]
+    pub fn new(src_offset: u64, dst_offset: u64, size: u64) -> Self {
+        Self {
+            src_offset,
+            dst_offset,
+            size,
+        }
+    }
+}
+
+impl From<BufferCopyRegion> for BufferCopy {
+    fn from(value: BufferCopyRegion) -> Self {
+        BufferCopy::builder()
+            .src_offset(value.src_offset)
+            .dst_offset(value.dst_offset)
+            .size(value.size)
+            .build()
+    }
+}
+
+/// Binds memory to, or unbinds memory from, a range of a sparse buffer.
+/// Used with `Queue::bind_sparse`.
+#[derive(Clone, Copy)]
+pub struct SparseBufferBind<'a> {
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    pub(crate) memory: Option<(&'a DeviceMemory, u64)>,
+}
+
+impl<'a> SparseBufferBind<'a> {
+    /// Back `size` bytes of the buffer starting at `offset` with `memory` from `memory_offset`.
+    /// The offsets and the size must be multiples of `Buffer::sparse_alignment`.
+    /// The memory should be allocated with `DeviceMemory::for_sparse_buffer`.
+    pub fn bind(offset: u64, size: u64, memory: &'a DeviceMemory, memory_offset: u64) -> Self {
+        Self {
+            offset,
+            size,
+            memory: Some((memory, memory_offset)),
+        }
+    }
+
+    /// Remove the memory backing `size` bytes of the buffer starting at `offset`.
+    pub fn unbind(offset: u64, size: u64) -> Self {
+        Self {
+            offset,
+            size,
+            memory: None,
+        }
+    }
 }
 
 pub struct Buffer {
     pub(crate) buffer: ash::vk::Buffer,
-    memory: DeviceMemory,
-    size: usize,
+    memory: Option<DeviceMemory>,
+    /// Requirements of the memory bound to a sparse buffer.
+    pub(crate) sparse_requirements: Option<MemoryRequirements>,
+    pub(crate) size: usize,
+    stride: usize,
+    pub(crate) usage: BufferUsageFlags,
+    pub(crate) index_format: Option<IndexFormat>,
+    state: Mutex<Option<ResourceState>>,
 }
 
 impl Buffer {
@@ -57,39 +253,271 @@ impl Buffer {
         device: &Device,
         descriptor: &BufferDescriptor,
     ) -> NxResult<Self> {
-        let create_info = BufferCreateInfo::builder()
-            .size(descriptor.size as u64)
-            .usage(descriptor.usage.into())
-            .sharing_mode(SharingMode::EXCLUSIVE)
-            .build();
-        let buffer = unsafe { device.device.create_buffer(&create_info, None) }.unwrap();
+        let (buffer, size, stride, usage) = Self::create_raw(device, descriptor)?;
+        if descriptor.sparse {
+            return Ok(Self {
+                buffer,
+                memory: None,
+                sparse_requirements: Some(
+                    ResourceMemoryRequirements::of_buffer(device, buffer).to_vk(),
+                ),
+                size,
+                stride,
+                usage,
+                index_format: None,
+                state: Mutex::new(None),
+            });
+        }
         let mem_props = connecter.get_memory_properties(instance);
-        let mem_req = unsafe { device.device.get_buffer_memory_requirements(buffer) };
-        let memory =
-            match DeviceMemory::alloc_buffer_memory(&device.device, buffer, mem_props, mem_req) {
-                Ok(x) => x,
-                Err(e) => return Err(e),
-            };
-
-        Ok(Self {
+        let non_coherent_atom_size = instance
+            .get_properties(connecter.0)
+            .limits
+            .non_coherent_atom_size;
+        let memory = match DeviceMemory::alloc_buffer_memory(
+            device,
             buffer,
-            memory,
-            size: descriptor.size,
-        })
+            mem_props,
+            non_coherent_atom_size,
+            if usage.contains(BufferUsageFlags::SHADER_DEVICE_ADDRESS) {
+                MemoryAllocateFlags::DEVICE_ADDRESS
+            } else {
+                MemoryAllocateFlags::empty()
+            },
+            descriptor.external_memory,
+            descriptor.dedicated_allocation,
+        ) {
+            Ok(x) => x,
+            Err(e) => return Err(e),
+        };
+
+        let buffer = Self {
+            buffer,
+            memory: Some(memory),
+            sparse_requirements: None,
+            size,
+            stride,
+            usage,
+            index_format: None,
+            state: Mutex::new(None),
+        };
+        if descriptor.zero_initialized {
+            if let Err(e) = buffer.clear(device) {
+                device.destroy(&buffer);
+                return Err(e);
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// Get the memory a buffer created with `descriptor` needs, without allocating any.
+    /// Lets an allocator decide where to place the buffer before creating it.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `descriptor` - Appropriate BufferDescriptor.
+    pub fn memory_requirements(
+        device: &Device,
+        descriptor: &BufferDescriptor,
+    ) -> NxResult<ResourceMemoryRequirements> {
+        let (buffer, ..) = Self::create_raw(device, descriptor)?;
+        let requirements = ResourceMemoryRequirements::of_buffer(device, buffer);
+        unsafe {
+            device.device.destroy_buffer(buffer, None);
+        }
+        Ok(requirements)
+    }
+
+    /// Validates `descriptor` and creates a buffer without memory.
+    /// Returns the buffer with its size, stride and usage.
+    fn create_raw(
+        device: &Device,
+        descriptor: &BufferDescriptor,
+    ) -> NxResult<(ash::vk::Buffer, usize, usize, BufferUsageFlags)> {
+        if descriptor.usage == BufferUsage::ShaderBindingTable
+            && !device.features.ray_tracing_pipeline
+        {
+            return Err(NxError::UnsupportedFeature(
+                "ray_tracing_pipeline is not enabled on the device".to_string(),
+            ));
+        }
+        if descriptor.external_memory.is_some() && device.external_memory.is_none() {
+            return Err(NxError::UnsupportedFeature(
+                "external_memory is not enabled on the device".to_string(),
+            ));
+        }
+        if descriptor.sparse {
+            if !device.features.sparse_buffer {
+                return Err(NxError::UnsupportedFeature(
+                    "sparse_buffer is not enabled on the device".to_string(),
+                ));
+            }
+            if descriptor.external_memory.is_some() || descriptor.zero_initialized {
+                return Err(NxError::InvalidDescriptor(
+                    "sparse buffers cannot be exported or zero-initialized".to_string(),
+                ));
+            }
+        }
+        let usage = BufferUsageFlags::from(descriptor.usage) | descriptor.additional_usage;
+        let stride = match descriptor.element_alignment {
+            None => descriptor.size,
+            Some(alignment) => {
+                // Buffers added both usages with `add_usage` need the stricter alignment.
+                let min_alignment = device.buffer_offset_alignment(usage);
+                let alignment = alignment.max(min_alignment as usize).max(1);
+                descriptor.size.next_multiple_of(alignment)
+            }
+        };
+        let size = stride * descriptor.element_count;
+        let mut external_info = ExternalMemoryBufferCreateInfo::builder().handle_types(
+            descriptor
+                .external_memory
+                .map(|x| x.into())
+                .unwrap_or_default(),
+        );
+        let flags = if descriptor.sparse {
+            BufferCreateFlags::SPARSE_BINDING | BufferCreateFlags::SPARSE_RESIDENCY
+        } else {
+            BufferCreateFlags::empty()
+        };
+        let mut create_info = BufferCreateInfo::builder()
+            .flags(flags)
+            .size(size as u64)
+            .usage(usage)
+            .sharing_mode(SharingMode::EXCLUSIVE);
+        if descriptor.external_memory.is_some() {
+            create_info = create_info.push_next(&mut external_info);
+        }
+        let create_info = create_info.build();
+        let buffer = unsafe { device.device.create_buffer(&create_info, None) }?;
+        Ok((buffer, size, stride, usage))
+    }
+
+    /// Fills the whole buffer with zeros. Buffer memory is always host visible.
+    fn clear(&self, device: &Device) -> NxResult<()> {
+        let mut mapped = self.bound_memory()?.map_guard(device)?;
+        mapped.as_mut_slice().fill(0);
+        mapped.flush(0, mapped.len())
+    }
+
+    /// Create an index buffer holding `indices`.
+    /// The index format is recorded so that `bind_index_buffer` can use it.
+    /// Returns `NxError::InvalidDescriptor` if `indices` is empty.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `indices` - Indices to upload.
+    pub fn index_from_slice<T: IndexElement>(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        indices: &[T],
+    ) -> NxResult<Self> {
+        if indices.is_empty() {
+            return Err(NxError::InvalidDescriptor(
+                "an index buffer needs at least one index".to_string(),
+            ));
+        }
+        let desc = BufferDescriptor::empty()
+            .size(std::mem::size_of_val(indices))
+            .usage(BufferUsage::Index);
+        let mut buffer = Self::new(instance, connecter, device, &desc)?;
+        buffer.write(device, indices.as_ptr() as *const c_void)?;
+        buffer.lock(device);
+        buffer.index_format = Some(T::FORMAT);
+        Ok(buffer)
+    }
+
+    /// Get the format of the indices, if the buffer was created as an index buffer.
+    pub fn index_format(&self) -> Option<IndexFormat> {
+        self.index_format
     }
 
     pub fn size(&self, device: &Device) -> u64 {
-        self.memory.size(device)
+        self.memory.as_ref().map_or(0, |x| x.size(device))
+    }
+
+    /// Get the memory bound to the buffer.
+    /// Returns `None` for sparse buffers, whose memory is bound with `Queue::bind_sparse`.
+    pub fn memory(&self) -> Option<&DeviceMemory> {
+        self.memory.as_ref()
+    }
+
+    /// Get the alignment of the offsets and sizes passed to `Queue::bind_sparse`.
+    /// Returns `None` if the buffer is not sparse.
+    pub fn sparse_alignment(&self) -> Option<u64> {
+        self.sparse_requirements.map(|x| x.alignment)
+    }
+
+    fn bound_memory(&self) -> NxResult<&DeviceMemory> {
+        self.memory.as_ref().ok_or_else(|| {
+            NxError::InvalidDescriptor("sparse buffers cannot be accessed by the host".to_string())
+        })
+    }
+
+    /// Get the device address of the buffer.
+    /// Only available for buffers created with `BufferUsage::ShaderBindingTable`.
+    pub fn device_address(&self, device: &Device) -> NxResult<u64> {
+        if !self.usage.contains(BufferUsageFlags::SHADER_DEVICE_ADDRESS) {
+            return Err(NxError::InvalidDescriptor(
+                "the buffer was not created with a device address usage".to_string(),
+            ));
+        }
+        let info = BufferDeviceAddressInfo::builder()
+            .buffer(self.buffer)
+            .build();
+        Ok(unsafe { device.device.get_buffer_device_address(&info) })
+    }
+
+    /// Create a buffer with the same size and usage and copy the contents of this one into it.
+    /// Blocks until the copy finishes.
+    /// The buffer must have been created with `BufferUsage::TransferSrc`.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `queue` - Queue to copy on. The copy must be acquired before use on another family.
+    pub fn clone_contents(
+        &self,
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        queue: QueueSelection,
+    ) -> NxResult<Self> {
+        if !self.usage.contains(BufferUsageFlags::TRANSFER_SRC) {
+            return Err(NxError::InvalidDescriptor(
+                "the buffer was not created with TransferSrc usage".to_string(),
+            ));
+        }
+        let mut descriptor = BufferDescriptor::empty()
+            .size(self.size)
+            .usage(BufferUsage::TransferDst);
+        descriptor.additional_usage = self.usage;
+        let mut buffer = Self::new(instance, connecter, device, &descriptor)?;
+        buffer.stride = self.stride;
+        buffer.index_format = self.index_format;
+        if let Err(e) = device.immediate_submit(queue, |recorder| {
+            recorder.copy_buffer(device, self, &buffer)
+        }) {
+            device.destroy(&buffer);
+            return Err(e);
+        }
+        Ok(buffer)
+    }
+
+    /// Get the distance in bytes between elements, including padding from `element_alignment`.
+    pub fn stride(&self) -> usize {
+        self.stride
     }
 
     pub fn write(&self, device: &Device, data: *const c_void) -> NxResult<()> {
+        let memory = self.bound_memory()?.memory;
         let mapped_memory = match unsafe {
-            device.device.map_memory(
-                self.memory.memory,
-                0,
-                self.size as u64,
-                MemoryMapFlags::empty(),
-            )
+            device
+                .device
+                .map_memory(memory, 0, self.size as u64, MemoryMapFlags::empty())
         } {
             Ok(x) => x,
             Err(e) => match e {
@@ -101,24 +529,133 @@ impl Buffer {
         };
 
         mem_copy(mapped_memory, data, self.size);
-        let flush_memory_range = MappedMemoryRange::builder()
-            .memory(self.memory.memory)
-            .offset(0)
-            .size(self.size as u64)
-            .build();
-        unsafe {
-            device
-                .device
-                .flush_mapped_memory_ranges(&[flush_memory_range])
-                .unwrap();
-        }
+        self.bound_memory()?
+            .flush_range(device, 0, self.size as u64)
+    }
 
+    /// Whether the memory of the buffer is host coherent.
+    /// Writes through `map` to memory that is not must be flushed with `MappedMemory::flush`,
+    /// and reads invalidated with `MappedMemory::invalidate`. `write` and `read_range`
+    /// do this themselves. Sparse buffers have no memory of their own and return false.
+    pub fn is_coherent(&self) -> bool {
+        self.memory.as_ref().is_some_and(|x| x.is_coherent())
+    }
+
+    /// Map the memory of the buffer.
+    /// Unlike `write`, nothing is flushed implicitly; use `MappedMemory::flush` and
+    /// `MappedMemory::invalidate` to control when that happens.
+    /// The memory must not be mapped already.
+    pub fn map<'a>(&'a self, device: &'a Device) -> NxResult<MappedMemory<'a>> {
+        self.bound_memory()?.map_guard(device)
+    }
+
+    /// Read `size` bytes starting at `offset` from the buffer.
+    /// Only the part of the memory containing the range is mapped and invalidated.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `offset` - Offset in bytes from the beginning of the buffer.
+    /// * `size` - Number of bytes to read.
+    pub fn read_range(&self, device: &Device, offset: u64, size: u64) -> NxResult<Vec<u8>> {
+        check_range(offset, size, self.size as u64, "buffer")?;
+        let mapped = self.bound_memory()?.map_range_guard(device, offset, size)?;
+        mapped.invalidate(0, mapped.len())?;
+        let start = (offset - mapped.offset()) as usize;
+        Ok(mapped.as_slice()[start..start + size as usize].to_vec())
+    }
+
+    /// Write `data` to the buffer starting at `offset`.
+    /// Only the part of the memory containing the range is mapped and flushed.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `offset` - Offset in bytes from the beginning of the buffer.
+    /// * `data` - Bytes to write.
+    pub fn write_range(&self, device: &Device, offset: u64, data: &[u8]) -> NxResult<()> {
+        let size = data.len() as u64;
+        check_range(offset, size, self.size as u64, "buffer")?;
+        let mut mapped = self.bound_memory()?.map_range_guard(device, offset, size)?;
+        let start = (offset - mapped.offset()) as usize;
+        mapped.as_mut_slice()[start..start + data.len()].copy_from_slice(data);
+        mapped.flush(start as u64, size)
+    }
+
+    /// Get a view of `size` bytes of the buffer starting at `offset`.
+    /// # Arguments
+    ///
+    /// * `offset` - Offset in bytes from the beginning of the buffer.
+    /// * `size` - Size of the view in bytes.
+    pub fn slice(&self, offset: u64, size: u64) -> NxResult<BufferSlice<'_>> {
+        check_range(offset, size, self.size as u64, "buffer")?;
+        Ok(BufferSlice {
+            buffer: self,
+            offset,
+            size,
+        })
+    }
+
+    /// Starts tracking the state of the buffer so that `transition_to` can be used.
+    /// Tracking is optional; buffers that are not tracked are not affected.
+    pub fn track_state(&self, state: ResourceState) {
+        *self.state.lock().unwrap() = Some(state);
+    }
+
+    /// Get the last known state of the buffer, if it is tracked.
+    pub fn state(&self) -> Option<ResourceState> {
+        *self.state.lock().unwrap()
+    }
+
+    /// Records the barrier needed to use the buffer in `state` and remembers the new state.
+    /// No barrier is recorded when both the old and new uses only read the buffer.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `recorder` - CommandRecorder to record the barrier to, outside a render pass.
+    /// * `state` - How the buffer will be used next.
+    pub fn transition_to(
+        &self,
+        device: &Device,
+        recorder: &CommandRecorder,
+        state: ResourceState,
+    ) -> NxResult<()> {
+        let mut batch = BarrierBatch::new();
+        self.batch_transition(&mut batch, state)?;
+        batch.flush(device, recorder);
+        Ok(())
+    }
+
+    /// Like `transition_to`, but adds the barrier to `batch` instead of recording it.
+    /// The tracked state is updated immediately,
+    /// so the batch must be flushed before the buffer is used.
+    pub fn batch_transition(&self, batch: &mut BarrierBatch, state: ResourceState) -> NxResult<()> {
+        let mut current = self.state.lock().unwrap();
+        let old = match *current {
+            Some(x) => x,
+            None => {
+                return Err(NxError::InvalidDescriptor(
+                    "the state of the buffer is not tracked".to_string(),
+                ))
+            }
+        };
+        if old.is_read_only() && state.is_read_only() {
+            *current = Some(ResourceState {
+                layout: state.layout,
+                stencil_layout: state.stencil_layout,
+                stages: old.stages | state.stages,
+                access: old.access | state.access,
+            });
+            return Ok(());
+        }
+        batch.buffer(self, &old, &state);
+        *current = Some(state);
         Ok(())
     }
 
     pub fn lock(&self, device: &Device) {
-        unsafe {
-            device.device.unmap_memory(self.memory.memory);
+        if let Some(memory) = &self.memory {
+            unsafe {
+                device.device.unmap_memory(memory.memory);
+            }
         }
     }
 }
@@ -129,9 +666,72 @@ impl Destroy for Buffer {
     fn device(&self, device: &Device) {
         unsafe {
             device.device.destroy_buffer(self.buffer, None);
-            device.destroy(&self.memory);
         }
+        if let Some(memory) = &self.memory {
+            device.destroy(memory);
+        }
+    }
+}
+
+/// View of a range of a Buffer, so that several logical buffers can share one allocation.
+/// Obtained with `Buffer::slice`.
+#[derive(Clone, Copy)]
+pub struct BufferSlice<'a> {
+    pub(crate) buffer: &'a Buffer,
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+}
+
+impl<'a> BufferSlice<'a> {
+    /// Get the buffer the slice is a view of.
+    pub fn buffer(&self) -> &'a Buffer {
+        self.buffer
+    }
+
+    /// Get the offset in bytes of the slice in the buffer.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Get the size of the slice in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Write `data` to the slice starting at `offset`, relative to the start of the slice.
+    pub fn write_range(&self, device: &Device, offset: u64, data: &[u8]) -> NxResult<()> {
+        check_range(offset, data.len() as u64, self.size, "slice")?;
+        self.buffer.write_range(device, self.offset + offset, data)
+    }
+
+    /// Read `size` bytes from the slice starting at `offset`, relative to the start of the slice.
+    pub fn read_range(&self, device: &Device, offset: u64, size: u64) -> NxResult<Vec<u8>> {
+        check_range(offset, size, self.size, "slice")?;
+        self.buffer.read_range(device, self.offset + offset, size)
+    }
+}
+
+impl<'a> From<&'a Buffer> for BufferSlice<'a> {
+    fn from(value: &'a Buffer) -> Self {
+        Self {
+            buffer: value,
+            offset: 0,
+            size: value.size as u64,
+        }
+    }
+}
+
+fn check_range(offset: u64, size: u64, len: u64, name: &str) -> NxResult<()> {
+    if offset.checked_add(size).map_or(true, |end| end > len) {
+        return Err(NxError::InvalidDescriptor(format!(
+            "range {}..{} is outside of the {} of {} bytes",
+            offset,
+            offset.saturating_add(size),
+            name,
+            len
+        )));
     }
+    Ok(())
 }
 
 pub(crate) fn mem_copy<T>(dst: *mut T, src: *const T, count: usize) {
@@ -0,0 +1,310 @@
+use crate::{DeviceConnecter, DeviceFeature, Instance};
+use ash::vk::{
+    ExtMeshShaderFn, PhysicalDeviceAccelerationStructureFeaturesKHR,
+    PhysicalDeviceConditionalRenderingFeaturesEXT, PhysicalDeviceFeatures2,
+    PhysicalDeviceMeshShaderFeaturesEXT, PhysicalDeviceRayTracingPipelineFeaturesKHR,
+    PhysicalDeviceRobustness2FeaturesEXT, PhysicalDeviceVulkan11Features,
+    PhysicalDeviceVulkan12Features, PhysicalDeviceVulkan13Features, API_VERSION_1_1,
+    API_VERSION_1_2, API_VERSION_1_3,
+};
+use std::ffi::CStr;
+
+/// Optional features and extensions supported by a device.
+/// Obtained with `DeviceConnecter::capabilities`.
+///
+/// Unlike `DeviceFeatures`, this also reports capabilities that nexg cannot enable yet.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DeviceCapabilities {
+    pub(crate) geometry_shader: bool,
+    pub(crate) tessellation_shader: bool,
+    pub(crate) wide_lines: bool,
+    pub(crate) sampler_anisotropy: bool,
+    pub(crate) occlusion_query_precise: bool,
+    pub(crate) pipeline_statistics_query: bool,
+    pub(crate) sparse_binding: bool,
+    pub(crate) sparse_residency_buffer: bool,
+    pub(crate) multiview: bool,
+    pub(crate) timeline_semaphore: bool,
+    pub(crate) buffer_device_address: bool,
+    pub(crate) descriptor_indexing: bool,
+    pub(crate) separate_depth_stencil_layouts: bool,
+    pub(crate) host_query_reset: bool,
+    pub(crate) dynamic_rendering: bool,
+    pub(crate) synchronization2: bool,
+    pub(crate) conditional_rendering: bool,
+    pub(crate) external_memory: bool,
+    pub(crate) external_semaphore: bool,
+    pub(crate) ray_tracing_pipeline: bool,
+    pub(crate) acceleration_structure: bool,
+    pub(crate) mesh_shader: bool,
+    pub(crate) robust_buffer_access: bool,
+    pub(crate) descriptor_binding_partially_bound: bool,
+    pub(crate) descriptor_update_after_bind: bool,
+    pub(crate) null_descriptor: bool,
+    pub(crate) draw_indirect_count: bool,
+    pub(crate) extended_dynamic_state: bool,
+    pub(crate) hdr_metadata: bool,
+    pub(crate) push_descriptor: bool,
+}
+
+impl DeviceCapabilities {
+    #[doc(hidden)]
+    pub(crate) fn query(instance: &Instance, connecter: DeviceConnecter) -> Self {
+        let api_version = instance.device_api_version(connecter.0);
+        let extensions = unsafe {
+            instance
+                .instance
+                .enumerate_device_extension_properties(connecter.0)
+        }
+        .unwrap_or_default();
+        let has_extension = |name: &CStr| {
+            extensions
+                .iter()
+                .any(|x| unsafe { CStr::from_ptr(x.extension_name.as_ptr()) } == name)
+        };
+        let has_feature = |feature: DeviceFeature| has_extension(feature.name());
+
+        let ray_tracing_extensions = has_feature(DeviceFeature::RayTracingPipeline)
+            && has_feature(DeviceFeature::AccelerationStructure)
+            && has_feature(DeviceFeature::DeferredHostOperations);
+        let acceleration_structure_extension = has_feature(DeviceFeature::AccelerationStructure);
+        let mesh_shader_extension = has_extension(ExtMeshShaderFn::name());
+        let robustness2_extension = has_feature(DeviceFeature::Robustness2);
+        let conditional_rendering_extension = has_feature(DeviceFeature::ConditionalRendering);
+
+        // Only chain the structures the device knows about.
+        let mut vulkan11 = PhysicalDeviceVulkan11Features::default();
+        let mut vulkan12 = PhysicalDeviceVulkan12Features::default();
+        let mut vulkan13 = PhysicalDeviceVulkan13Features::default();
+        let mut ray_tracing_pipeline = PhysicalDeviceRayTracingPipelineFeaturesKHR::default();
+        let mut acceleration_structure = PhysicalDeviceAccelerationStructureFeaturesKHR::default();
+        let mut mesh_shader = PhysicalDeviceMeshShaderFeaturesEXT::default();
+        let mut robustness2 = PhysicalDeviceRobustness2FeaturesEXT::default();
+        let mut conditional_rendering = PhysicalDeviceConditionalRenderingFeaturesEXT::default();
+        let mut features2 = PhysicalDeviceFeatures2::builder();
+        if api_version >= API_VERSION_1_2 {
+            features2 = features2.push_next(&mut vulkan11).push_next(&mut vulkan12);
+        }
+        if api_version >= API_VERSION_1_3 {
+            features2 = features2.push_next(&mut vulkan13);
+        }
+        if ray_tracing_extensions {
+            features2 = features2.push_next(&mut ray_tracing_pipeline);
+        }
+        if acceleration_structure_extension {
+            features2 = features2.push_next(&mut acceleration_structure);
+        }
+        if mesh_shader_extension {
+            features2 = features2.push_next(&mut mesh_shader);
+        }
+        if robustness2_extension {
+            features2 = features2.push_next(&mut robustness2);
+        }
+        if conditional_rendering_extension {
+            features2 = features2.push_next(&mut conditional_rendering);
+        }
+        // vkGetPhysicalDeviceFeatures2 is core since Vulkan 1.1,
+        // extension features cannot be queried without it.
+        let core = if api_version >= API_VERSION_1_1 {
+            unsafe {
+                instance
+                    .instance
+                    .get_physical_device_features2(connecter.0, &mut features2);
+            }
+            features2.features
+        } else {
+            unsafe { instance.instance.get_physical_device_features(connecter.0) }
+        };
+
+        // VK_KHR_external_memory and VK_KHR_external_semaphore are core since Vulkan 1.1.
+        let vulkan11_core = api_version >= API_VERSION_1_1;
+        Self {
+            geometry_shader: core.geometry_shader != 0,
+            tessellation_shader: core.tessellation_shader != 0,
+            wide_lines: core.wide_lines != 0,
+            sampler_anisotropy: core.sampler_anisotropy != 0,
+            occlusion_query_precise: core.occlusion_query_precise != 0,
+            pipeline_statistics_query: core.pipeline_statistics_query != 0,
+            sparse_binding: core.sparse_binding != 0,
+            sparse_residency_buffer: core.sparse_residency_buffer != 0,
+            multiview: vulkan11.multiview != 0,
+            timeline_semaphore: vulkan12.timeline_semaphore != 0,
+            buffer_device_address: vulkan12.buffer_device_address != 0,
+            descriptor_indexing: vulkan12.descriptor_indexing != 0,
+            separate_depth_stencil_layouts: vulkan12.separate_depth_stencil_layouts != 0,
+            host_query_reset: vulkan12.host_query_reset != 0,
+            dynamic_rendering: vulkan13.dynamic_rendering != 0,
+            synchronization2: vulkan13.synchronization2 != 0,
+            conditional_rendering: conditional_rendering.conditional_rendering != 0,
+            external_memory: vulkan11_core && has_feature(DeviceFeature::ExternalMemory),
+            external_semaphore: vulkan11_core && has_feature(DeviceFeature::ExternalSemaphore),
+            ray_tracing_pipeline: ray_tracing_pipeline.ray_tracing_pipeline != 0
+                && vulkan12.buffer_device_address != 0,
+            acceleration_structure: acceleration_structure.acceleration_structure != 0,
+            mesh_shader: mesh_shader.mesh_shader != 0,
+            robust_buffer_access: core.robust_buffer_access != 0,
+            descriptor_binding_partially_bound: vulkan12.descriptor_binding_partially_bound != 0,
+            descriptor_update_after_bind: vulkan12
+                .descriptor_binding_sampled_image_update_after_bind
+                != 0
+                && vulkan12.descriptor_binding_storage_image_update_after_bind != 0
+                && vulkan12.descriptor_binding_storage_buffer_update_after_bind != 0,
+            null_descriptor: robustness2.null_descriptor != 0,
+            draw_indirect_count: vulkan12.draw_indirect_count != 0,
+            // The extended dynamic state commands are always available in Vulkan 1.3.
+            extended_dynamic_state: api_version >= API_VERSION_1_3,
+            hdr_metadata: has_feature(DeviceFeature::HdrMetadata),
+            push_descriptor: has_feature(DeviceFeature::PushDescriptor),
+        }
+    }
+
+    /// Geometry shaders.
+    pub const fn geometry_shader(&self) -> bool {
+        self.geometry_shader
+    }
+
+    /// Tessellation control and evaluation shaders.
+    pub const fn tessellation_shader(&self) -> bool {
+        self.tessellation_shader
+    }
+
+    /// Lines with a width other than 1.0.
+    pub const fn wide_lines(&self) -> bool {
+        self.wide_lines
+    }
+
+    /// Anisotropic filtering in samplers.
+    pub const fn sampler_anisotropy(&self) -> bool {
+        self.sampler_anisotropy
+    }
+
+    /// Precise occlusion queries.
+    pub const fn occlusion_query_precise(&self) -> bool {
+        self.occlusion_query_precise
+    }
+
+    /// Pipeline statistics queries.
+    pub const fn pipeline_statistics_query(&self) -> bool {
+        self.pipeline_statistics_query
+    }
+
+    /// Binding memory to resources after they are created.
+    pub const fn sparse_binding(&self) -> bool {
+        self.sparse_binding
+    }
+
+    /// Buffers that are only partially backed by memory.
+    pub const fn sparse_residency_buffer(&self) -> bool {
+        self.sparse_residency_buffer
+    }
+
+    /// Rendering to several views in one render pass. Requires Vulkan 1.2 to be reported.
+    pub const fn multiview(&self) -> bool {
+        self.multiview
+    }
+
+    /// Timeline semaphores.
+    pub const fn timeline_semaphore(&self) -> bool {
+        self.timeline_semaphore
+    }
+
+    /// Buffer device addresses.
+    pub const fn buffer_device_address(&self) -> bool {
+        self.buffer_device_address
+    }
+
+    /// Descriptor indexing.
+    pub const fn descriptor_indexing(&self) -> bool {
+        self.descriptor_indexing
+    }
+
+    /// Separate layouts for the depth and stencil aspects of an image.
+    pub const fn separate_depth_stencil_layouts(&self) -> bool {
+        self.separate_depth_stencil_layouts
+    }
+
+    /// Resetting queries from the host.
+    pub const fn host_query_reset(&self) -> bool {
+        self.host_query_reset
+    }
+
+    /// Rendering without render pass objects.
+    pub const fn dynamic_rendering(&self) -> bool {
+        self.dynamic_rendering
+    }
+
+    /// The `synchronization2` barrier and submit commands.
+    pub const fn synchronization2(&self) -> bool {
+        self.synchronization2
+    }
+
+    /// `VK_EXT_conditional_rendering`.
+    pub const fn conditional_rendering(&self) -> bool {
+        self.conditional_rendering
+    }
+
+    /// Exporting and importing memory as OS handles of this platform.
+    pub const fn external_memory(&self) -> bool {
+        self.external_memory
+    }
+
+    /// Exporting and importing semaphores as OS handles of this platform.
+    pub const fn external_semaphore(&self) -> bool {
+        self.external_semaphore
+    }
+
+    /// Ray tracing pipelines, with the extensions and buffer device addresses they need.
+    pub const fn ray_tracing_pipeline(&self) -> bool {
+        self.ray_tracing_pipeline
+    }
+
+    /// Acceleration structures.
+    pub const fn acceleration_structure(&self) -> bool {
+        self.acceleration_structure
+    }
+
+    /// Mesh shaders from `VK_EXT_mesh_shader`.
+    pub const fn mesh_shader(&self) -> bool {
+        self.mesh_shader
+    }
+
+    /// Bounds-checked buffer accesses in shaders.
+    pub const fn robust_buffer_access(&self) -> bool {
+        self.robust_buffer_access
+    }
+
+    /// Resource bindings whose array elements need not all be written.
+    pub const fn descriptor_binding_partially_bound(&self) -> bool {
+        self.descriptor_binding_partially_bound
+    }
+
+    /// Writing sampler, image and storage buffer resources while they are in use.
+    pub const fn descriptor_update_after_bind(&self) -> bool {
+        self.descriptor_update_after_bind
+    }
+
+    /// Null buffers and images in resources, from `VK_EXT_robustness2`.
+    pub const fn null_descriptor(&self) -> bool {
+        self.null_descriptor
+    }
+
+    /// Indirect draws that read the draw count from a buffer.
+    pub const fn draw_indirect_count(&self) -> bool {
+        self.draw_indirect_count
+    }
+
+    /// Setting cull mode, front face, topology and depth state while recording.
+    pub const fn extended_dynamic_state(&self) -> bool {
+        self.extended_dynamic_state
+    }
+
+    /// Describing the mastering display of HDR content, from `VK_EXT_hdr_metadata`.
+    pub const fn hdr_metadata(&self) -> bool {
+        self.hdr_metadata
+    }
+
+    /// Writing resources directly into command buffers, from `VK_KHR_push_descriptor`.
+    pub const fn push_descriptor(&self) -> bool {
+        self.push_descriptor
+    }
+}
@@ -0,0 +1,101 @@
+use crate::DeviceFeatures;
+
+/// Extensions and layers an Instance was created with, obtained with `Instance::config`.
+/// Serializable with the "serde" feature, for example to attach to bug reports.
+/// An instance with the same configuration is created with `InstanceFeature::from_config`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InstanceConfig {
+    pub(crate) api_version: u32,
+    pub(crate) extensions: Vec<String>,
+    pub(crate) layers: Vec<String>,
+}
+
+impl InstanceConfig {
+    /// Vulkan version requested by the instance, encoded like `VK_MAKE_API_VERSION`.
+    pub fn api_version(&self) -> u32 {
+        self.api_version
+    }
+
+    /// Names of the enabled instance extensions.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Names of the enabled layers.
+    pub fn layers(&self) -> &[String] {
+        &self.layers
+    }
+}
+
+/// Physical device, extensions, features and queue families a Device was created with,
+/// obtained with `Device::config`.
+/// Serializable with the "serde" feature, for example to attach to bug reports.
+/// A device with the same configuration is created with
+/// `DeviceConnecter::create_device_from_config`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceConfig {
+    pub(crate) device_name: String,
+    pub(crate) vendor_id: u32,
+    pub(crate) device_id: u32,
+    pub(crate) api_version: u32,
+    pub(crate) extensions: Vec<String>,
+    pub(crate) features: DeviceFeatures,
+    pub(crate) graphics_queue_family: usize,
+    pub(crate) transfer_queue_family: Option<usize>,
+    pub(crate) compute_queue_family: Option<usize>,
+    pub(crate) present_queue_family: Option<usize>,
+}
+
+impl DeviceConfig {
+    /// Name of the physical device.
+    pub fn device_name(&self) -> &str {
+        &self.device_name
+    }
+
+    /// PCI vendor ID of the physical device.
+    pub fn vendor_id(&self) -> u32 {
+        self.vendor_id
+    }
+
+    /// Vendor-specific ID of the physical device.
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// Vulkan version supported by the physical device.
+    pub fn api_version(&self) -> u32 {
+        self.api_version
+    }
+
+    /// Names of the enabled device extensions.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Features enabled on the device.
+    pub fn features(&self) -> DeviceFeatures {
+        self.features
+    }
+
+    /// Index of the queue family used by `QueueSelection::Graphics`.
+    pub fn graphics_queue_family(&self) -> usize {
+        self.graphics_queue_family
+    }
+
+    /// Index of the dedicated transfer queue family, if the device has one.
+    pub fn transfer_queue_family(&self) -> Option<usize> {
+        self.transfer_queue_family
+    }
+
+    /// Index of the dedicated compute queue family, if the device has one.
+    pub fn compute_queue_family(&self) -> Option<usize> {
+        self.compute_queue_family
+    }
+
+    /// Index of the separate present queue family, if the graphics family cannot present.
+    pub fn present_queue_family(&self) -> Option<usize> {
+        self.present_queue_family
+    }
+}
@@ -0,0 +1,147 @@
+use crate::{
+    Buffer, CommandRecorder, DeviceMemory, Fence, FrameBuffer, Image, ImageView, Pipeline,
+    PipelineLayout, QueryPool, RenderPass, Sampler, Semaphore, Shader,
+};
+#[allow(deprecated)]
+use ash::extensions::ext::{DebugMarker, DebugUtils};
+use ash::vk::{
+    CommandBuffer, DebugMarkerMarkerInfoEXT, DebugMarkerObjectNameInfoEXT,
+    DebugReportObjectTypeEXT, DebugUtilsLabelEXT, DebugUtilsObjectNameInfoEXT, Handle, ObjectType,
+};
+use std::ffi::CString;
+
+/// Objects that can be given a name shown by debugging tools.
+pub trait DebugObject {
+    #[doc(hidden)]
+    fn debug_handle(&self) -> (ObjectType, u64);
+}
+
+macro_rules! debug_object {
+    ($ty:ty, $field:ident, $object_type:ident) => {
+        impl DebugObject for $ty {
+            fn debug_handle(&self) -> (ObjectType, u64) {
+                (ObjectType::$object_type, self.$field.as_raw())
+            }
+        }
+    };
+}
+
+debug_object!(Buffer, buffer, BUFFER);
+debug_object!(Image, image, IMAGE);
+debug_object!(ImageView, image_view, IMAGE_VIEW);
+debug_object!(DeviceMemory, memory, DEVICE_MEMORY);
+debug_object!(Semaphore, semaphore, SEMAPHORE);
+debug_object!(Fence, fence, FENCE);
+debug_object!(Sampler, sampler, SAMPLER);
+debug_object!(RenderPass, render_pass, RENDER_PASS);
+debug_object!(FrameBuffer, frame_buffer, FRAMEBUFFER);
+debug_object!(Pipeline, pipeline, PIPELINE);
+debug_object!(PipelineLayout, layout, PIPELINE_LAYOUT);
+debug_object!(QueryPool, pool, QUERY_POOL);
+debug_object!(Shader, inner, SHADER_MODULE);
+debug_object!(CommandRecorder, buffer, COMMAND_BUFFER);
+
+#[doc(hidden)]
+/// Backend used for object names and command labels.
+/// `VK_EXT_debug_utils` is preferred, `VK_EXT_debug_marker` is used on older drivers,
+/// and nothing is recorded if neither is available.
+#[allow(deprecated)]
+#[derive(Clone)]
+pub(crate) enum DebugLoader {
+    Utils(DebugUtils),
+    Marker(DebugMarker),
+    None,
+}
+
+#[allow(deprecated)]
+impl DebugLoader {
+    pub(crate) fn set_name(
+        &self,
+        device: &ash::Device,
+        (object_type, handle): (ObjectType, u64),
+        name: &str,
+    ) {
+        let name = CString::new(name).unwrap_or_default();
+        // Names only help debugging, so failing to set one is not an error.
+        let _ = match self {
+            DebugLoader::Utils(loader) => {
+                let info = DebugUtilsObjectNameInfoEXT::builder()
+                    .object_type(object_type)
+                    .object_handle(handle)
+                    .object_name(&name)
+                    .build();
+                unsafe { loader.set_debug_utils_object_name(device.handle(), &info) }
+            }
+            DebugLoader::Marker(loader) => {
+                let info = DebugMarkerObjectNameInfoEXT::builder()
+                    .object_type(Self::report_object_type(object_type))
+                    .object(handle)
+                    .object_name(&name)
+                    .build();
+                unsafe { loader.debug_marker_set_object_name(&info) }
+            }
+            DebugLoader::None => Ok(()),
+        };
+    }
+
+    pub(crate) fn begin_label(&self, command_buffer: CommandBuffer, name: &str, color: [f32; 4]) {
+        let name = CString::new(name).unwrap_or_default();
+        match self {
+            DebugLoader::Utils(loader) => {
+                let label = DebugUtilsLabelEXT::builder()
+                    .label_name(&name)
+                    .color(color)
+                    .build();
+                unsafe { loader.cmd_begin_debug_utils_label(command_buffer, &label) }
+            }
+            DebugLoader::Marker(loader) => {
+                let marker = DebugMarkerMarkerInfoEXT::builder()
+                    .marker_name(&name)
+                    .color(color)
+                    .build();
+                unsafe { loader.cmd_debug_marker_begin(command_buffer, &marker) }
+            }
+            DebugLoader::None => {}
+        }
+    }
+
+    pub(crate) fn end_label(&self, command_buffer: CommandBuffer) {
+        match self {
+            DebugLoader::Utils(loader) => unsafe {
+                loader.cmd_end_debug_utils_label(command_buffer)
+            },
+            DebugLoader::Marker(loader) => unsafe { loader.cmd_debug_marker_end(command_buffer) },
+            DebugLoader::None => {}
+        }
+    }
+
+    pub(crate) fn insert_label(&self, command_buffer: CommandBuffer, name: &str, color: [f32; 4]) {
+        let name = CString::new(name).unwrap_or_default();
+        match self {
+            DebugLoader::Utils(loader) => {
+                let label = DebugUtilsLabelEXT::builder()
+                    .label_name(&name)
+                    .color(color)
+                    .build();
+                unsafe { loader.cmd_insert_debug_utils_label(command_buffer, &label) }
+            }
+            DebugLoader::Marker(loader) => {
+                let marker = DebugMarkerMarkerInfoEXT::builder()
+                    .marker_name(&name)
+                    .color(color)
+                    .build();
+                unsafe { loader.cmd_debug_marker_insert(command_buffer, &marker) }
+            }
+            DebugLoader::None => {}
+        }
+    }
+
+    /// The core object types share their values with `VkDebugReportObjectTypeEXT`.
+    fn report_object_type(object_type: ObjectType) -> DebugReportObjectTypeEXT {
+        if object_type.as_raw() <= ObjectType::COMMAND_POOL.as_raw() {
+            DebugReportObjectTypeEXT::from_raw(object_type.as_raw())
+        } else {
+            DebugReportObjectTypeEXT::UNKNOWN
+        }
+    }
+}
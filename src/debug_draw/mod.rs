@@ -0,0 +1,387 @@
+mod font;
+
+use std::mem::offset_of;
+
+use crate::{
+    AddressMode, Buffer, BufferDescriptor, BufferUsage, CommandRecorder, DataFormat, Destroy,
+    Device, DeviceConnecter, Extent2d, Extent3d, Filter, Image, ImageDescriptor, ImageFormat,
+    ImageLayout, ImageUsage, ImageView, ImageViewDescriptor, Instance, NxError, NxResult, Pipeline,
+    PipelineDescriptor, PipelineLayout, PipelineLayoutDescriptor, PipelineVertexInputDescriptor,
+    PixelFormat, RenderPass, Resource, ResourceImageDescriptor, ResourceLayout,
+    ResourceLayoutBinding, ResourcePool, ResourcePoolSize, ResourceType, ResourceUpdateDescriptor,
+    Sampler, SamplerDescriptor, Shader, ShaderStage, ShaderStageDescriptor, Spirv,
+    VertexInputAttributeDescriptor, VertexInputBindingDescriptor,
+};
+use font::GLYPHS;
+
+const VERTEX_SHADER: &[u8] = include_bytes!("shader/debug_draw.vert.spv");
+const FRAGMENT_SHADER: &[u8] = include_bytes!("shader/debug_draw.frag.spv");
+
+/// Size of a glyph in pixels, both in the atlas and on screen.
+const GLYPH_SIZE: u32 = 8;
+/// The atlas holds the glyphs in rows of 16, followed by a solid block used by rectangles.
+const ATLAS_COLUMNS: u32 = 16;
+const ATLAS_ROWS: u32 = 6;
+const SOLID_SLOT: u32 = GLYPHS.len() as u32;
+/// Maximum number of characters and rectangles drawn by one `record`.
+const MAX_QUADS: usize = 4096;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct DebugVertex {
+    position: [f32; 2],
+    uv: [f32; 2],
+    color: [f32; 4],
+}
+
+/// A rectangle in pixels, with the origin at the top left of the render area.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Rect {
+    #[inline]
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+/// Draws text and colored rectangles on top of a render, for debugging.
+///
+/// Shapes are queued with `draw_text` and `draw_rect` and recorded by `record`
+/// inside a render pass compatible with the one given to `new`, usually the swapchain
+/// render pass. Text uses a built-in 8x8 pixel font covering printable ASCII.
+///
+/// The vertices are rewritten by every `record`, so the previous frame using them must
+/// have finished, as when waiting for the frame fence before recording.
+/// Recreate the helper when the render area is resized.
+pub struct DebugDraw {
+    extent: Extent2d,
+    quads: Vec<[DebugVertex; 6]>,
+    vertex_buffer: Buffer,
+    atlas: Image,
+    atlas_view: ImageView,
+    sampler: Sampler,
+    resource_layout: ResourceLayout,
+    resource_pool: ResourcePool,
+    resource: Resource,
+    pipeline_layout: PipelineLayout,
+    pipeline: Pipeline,
+}
+
+impl DebugDraw {
+    /// Create the pipeline, font atlas and vertex buffer of the helper.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `device` - Reference to the appropriate device.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `render_pass` - Render pass the shapes are recorded in. Its first subpass is used.
+    /// * `extent` - Size of the render area in pixels.
+    pub fn new(
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        render_pass: &RenderPass,
+        extent: Extent2d,
+    ) -> NxResult<Self> {
+        let vertex_buffer = Buffer::new(
+            instance,
+            connecter,
+            device,
+            &BufferDescriptor::empty()
+                .size(MAX_QUADS * std::mem::size_of::<[DebugVertex; 6]>())
+                .usage(BufferUsage::Vertex),
+        )?;
+        let atlas = Image::create(
+            instance,
+            device,
+            connecter,
+            &ImageDescriptor::new()
+                .extent(Extent3d::new(
+                    ATLAS_COLUMNS * GLYPH_SIZE,
+                    ATLAS_ROWS * GLYPH_SIZE,
+                    1,
+                ))
+                .format(ImageFormat::R8G8B8A8Unorm)
+                .usage(ImageUsage::Sampled),
+        )?;
+        atlas.upload_pixels(
+            instance,
+            connecter,
+            device,
+            PixelFormat::Rgba8,
+            &atlas_pixels(),
+        )?;
+        let atlas_view = atlas.create_image_view(
+            device,
+            &ImageViewDescriptor::empty().format(ImageFormat::R8G8B8A8Unorm),
+        );
+        let sampler = Sampler::new(
+            device,
+            &SamplerDescriptor::empty()
+                .mag_filter(Filter::Nearest)
+                .min_filter(Filter::Nearest)
+                .mipmap_filter(Filter::Nearest)
+                .address_mode(
+                    AddressMode::ClampToEdge,
+                    AddressMode::ClampToEdge,
+                    AddressMode::ClampToEdge,
+                ),
+        )?;
+
+        let resource_layout = ResourceLayout::new(
+            device,
+            &[ResourceLayoutBinding::empty()
+                .binding(0)
+                .resource_type(ResourceType::CombinedImageSampler)
+                .count(1)
+                .shader_stage(ShaderStage::Fragment)],
+        )?;
+        let pool_sizes = [ResourcePoolSize::empty()
+            .resource_type(ResourceType::CombinedImageSampler)
+            .count(1)];
+        let resource_pool = ResourcePool::create(device, &pool_sizes, 1, false)?;
+        let resource = Resource::allocate(device, &resource_pool, &resource_layout)?.remove(0);
+        let image_desc = [
+            ResourceImageDescriptor::new(&atlas_view, ImageLayout::ShaderReadOnly)
+                .sampler(&sampler),
+        ];
+        device.update_resource(
+            &ResourceUpdateDescriptor::new(&resource)
+                .binding(0)
+                .resource_type(ResourceType::CombinedImageSampler)
+                .image_desc(&image_desc),
+        );
+
+        let pipeline_layout = PipelineLayout::new(
+            device,
+            &PipelineLayoutDescriptor::empty()
+                .render_pass(render_pass)
+                .resource(&resource_layout),
+        )?;
+        let vertex = Shader::new(device, &Spirv::from_raw(VERTEX_SHADER)?);
+        let fragment = Shader::new(device, &Spirv::from_raw(FRAGMENT_SHADER)?);
+        let shader_stages = [
+            ShaderStageDescriptor::empty()
+                .entry_point("main")
+                .stage(ShaderStage::Vertex)
+                .shaders(&vertex),
+            ShaderStageDescriptor::empty()
+                .entry_point("main")
+                .stage(ShaderStage::Fragment)
+                .shaders(&fragment),
+        ];
+        let binding_desc = [VertexInputBindingDescriptor::empty()
+            .binding(0)
+            .stride(std::mem::size_of::<DebugVertex>())];
+        let attribute_desc = [
+            VertexInputAttributeDescriptor::empty()
+                .binding(0)
+                .location(0)
+                .format(DataFormat::R32G32SFloat)
+                .offset(offset_of!(DebugVertex, position)),
+            VertexInputAttributeDescriptor::empty()
+                .binding(0)
+                .location(1)
+                .format(DataFormat::R32G32SFloat)
+                .offset(offset_of!(DebugVertex, uv)),
+            VertexInputAttributeDescriptor::empty()
+                .binding(0)
+                .location(2)
+                .format(DataFormat::R32G32B32A32SFloat)
+                .offset(offset_of!(DebugVertex, color)),
+        ];
+        let input_desc = PipelineVertexInputDescriptor::empty()
+            .binding_desc(&binding_desc)
+            .attribute_desc(&attribute_desc);
+        let pipeline = Pipeline::new(
+            device,
+            &pipeline_layout,
+            render_pass,
+            &PipelineDescriptor::empty()
+                .shader_stages(&shader_stages)
+                .input_descriptor(&input_desc)
+                .width(extent.width())
+                .height(extent.height())
+                .alpha_blend(true),
+        );
+        // The modules are no longer needed once the pipeline is created.
+        device.destroy(&vertex);
+        device.destroy(&fragment);
+        let pipeline = pipeline?.into_iter().next().ok_or(NxError::Unknown)?;
+
+        Ok(Self {
+            extent,
+            quads: vec![],
+            vertex_buffer,
+            atlas,
+            atlas_view,
+            sampler,
+            resource_layout,
+            resource_pool,
+            resource,
+            pipeline_layout,
+            pipeline,
+        })
+    }
+
+    /// Queues `text` in white with its top left corner at `position`, in pixels.
+    /// Each character is 8x8 pixels; `'\n'` starts a new line.
+    /// Characters outside of printable ASCII are drawn as `'?'`.
+    pub fn draw_text(&mut self, position: [f32; 2], text: &str) {
+        self.draw_text_colored(position, text, [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    /// Queues `text` like `draw_text`, in `color`.
+    pub fn draw_text_colored(&mut self, position: [f32; 2], text: &str, color: [f32; 4]) {
+        let size = GLYPH_SIZE as f32;
+        let (mut x, mut y) = (position[0], position[1]);
+        for c in text.chars() {
+            match c {
+                '\n' => {
+                    x = position[0];
+                    y += size;
+                }
+                ' ' => x += size,
+                _ => {
+                    let slot = match c {
+                        ' '..='~' => c as u32 - ' ' as u32,
+                        _ => '?' as u32 - ' ' as u32,
+                    };
+                    self.push_quad(Rect::new(x, y, size, size), slot, color);
+                    x += size;
+                }
+            }
+        }
+    }
+
+    /// Queues a rectangle filled with `color`.
+    pub fn draw_rect(&mut self, rect: Rect, color: [f32; 4]) {
+        self.push_quad(rect, SOLID_SLOT, color);
+    }
+
+    /// Records the queued shapes into `recorder` and clears the queue.
+    /// Must be called inside the render pass; the bound pipeline and resources change.
+    pub fn record(&mut self, device: &Device, recorder: &CommandRecorder) -> NxResult<()> {
+        if self.quads.is_empty() {
+            return Ok(());
+        }
+        let vertices = self.quads.as_flattened();
+        // SAFETY: DebugVertex is plain old data without padding.
+        let bytes = unsafe {
+            std::slice::from_raw_parts(
+                vertices.as_ptr() as *const u8,
+                std::mem::size_of_val(vertices),
+            )
+        };
+        let mut mapped = self.vertex_buffer.map(device)?;
+        mapped.as_mut_slice()[..bytes.len()].copy_from_slice(bytes);
+        mapped.flush(0, mapped.len())?;
+        drop(mapped);
+
+        recorder.bind_pipeline(device, &self.pipeline);
+        recorder.bind_vertex_buffer(device, &self.vertex_buffer);
+        recorder.bind_resource(device, &self.resource, &self.pipeline_layout);
+        recorder.draw(device, vertices.len() as u32, 1, 0, 0);
+        self.quads.clear();
+        Ok(())
+    }
+
+    /// Size of the render area given to `new`.
+    pub fn extent(&self) -> Extent2d {
+        self.extent
+    }
+
+    /// Queues a quad covering `rect` that shows atlas slot `slot`.
+    /// Shapes beyond `MAX_QUADS` are dropped.
+    fn push_quad(&mut self, rect: Rect, slot: u32, color: [f32; 4]) {
+        if self.quads.len() >= MAX_QUADS {
+            return;
+        }
+        let atlas_width = (ATLAS_COLUMNS * GLYPH_SIZE) as f32;
+        let atlas_height = (ATLAS_ROWS * GLYPH_SIZE) as f32;
+        let u0 = ((slot % ATLAS_COLUMNS) * GLYPH_SIZE) as f32 / atlas_width;
+        let v0 = ((slot / ATLAS_COLUMNS) * GLYPH_SIZE) as f32 / atlas_height;
+        let size = GLYPH_SIZE as f32;
+        let (min, max) = if slot == SOLID_SLOT {
+            // Every texel of the block is opaque, so its center is sampled everywhere.
+            let center = [
+                u0 + size / 2.0 / atlas_width,
+                v0 + size / 2.0 / atlas_height,
+            ];
+            (center, center)
+        } else {
+            (
+                [u0, v0],
+                [u0 + size / atlas_width, v0 + size / atlas_height],
+            )
+        };
+
+        let (width, height) = (self.extent.width() as f32, self.extent.height() as f32);
+        let vertex = |x: f32, y: f32, u: f32, v: f32| DebugVertex {
+            position: [x / width * 2.0 - 1.0, y / height * 2.0 - 1.0],
+            uv: [u, v],
+            color,
+        };
+        let (left, top) = (rect.x, rect.y);
+        let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+        let top_left = vertex(left, top, min[0], min[1]);
+        let bottom_right = vertex(right, bottom, max[0], max[1]);
+        // Both triangles wind clockwise on screen so that they face the front.
+        self.quads.push([
+            top_left,
+            vertex(right, top, max[0], min[1]),
+            bottom_right,
+            top_left,
+            bottom_right,
+            vertex(left, bottom, min[0], max[1]),
+        ]);
+    }
+}
+
+impl Destroy for DebugDraw {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        device.destroy(&self.pipeline);
+        device.destroy(&self.pipeline_layout);
+        // Destroying the pool frees the resource.
+        device.destroy(&self.resource_pool);
+        device.destroy(&self.resource_layout);
+        device.destroy(&self.sampler);
+        device.destroy(&self.atlas_view);
+        device.destroy(&self.atlas);
+        device.destroy(&self.vertex_buffer);
+    }
+}
+
+/// White RGBA pixels of the atlas, with the glyph coverage in alpha.
+fn atlas_pixels() -> Vec<u8> {
+    let width = ATLAS_COLUMNS * GLYPH_SIZE;
+    let height = ATLAS_ROWS * GLYPH_SIZE;
+    let mut pixels = vec![0; (width * height * 4) as usize];
+    let solid = [0xFF; GLYPH_SIZE as usize];
+    for (slot, glyph) in GLYPHS.iter().chain([&solid]).enumerate() {
+        let slot = slot as u32;
+        for (row, bits) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_SIZE {
+                let x = (slot % ATLAS_COLUMNS) * GLYPH_SIZE + column;
+                let y = (slot / ATLAS_COLUMNS) * GLYPH_SIZE + row as u32;
+                let i = ((y * width + x) * 4) as usize;
+                let alpha = if bits >> column & 1 == 1 { 0xFF } else { 0 };
+                pixels[i..i + 4].copy_from_slice(&[0xFF, 0xFF, 0xFF, alpha]);
+            }
+        }
+    }
+    pixels
+}
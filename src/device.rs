@@ -1,24 +1,771 @@
+use crate::debug::DebugLoader;
+use crate::external::{ExternalMemoryLoader, ExternalSemaphoreLoader};
 use crate::{
-    CommandPool, CommandPoolDescriptor, CommandRecorder, CommandRecorderDescriptor, Destroy,
-    NxResult, Queue, ResourceUpdateDescriptor,
+    BufferUsage, CommandPool, CommandPoolDescriptor, CommandRecorder, CommandRecorderDescriptor,
+    DebugObject, DescriptorWriteBatch, Destroy, DeviceConfig, Fence, FenceDescriptor, NxError,
+    NxResult, Queue, QueueSelection, QueueSubmitDescriptor, ResourceUpdateDescriptor,
+};
+use ash::vk::{
+    BufferUsageFlags, CommandBufferAllocateInfo, CommandBufferLevel, CommandPoolCreateFlags,
+    CommandPoolCreateInfo, ExtConditionalRenderingFn, ExtHdrMetadataFn, ExtRobustness2Fn,
+    PhysicalDevice, PhysicalDeviceFeatures, PhysicalDeviceLimits,
+    PhysicalDeviceMultiviewProperties, PhysicalDeviceProperties2,
+    PhysicalDevicePushDescriptorPropertiesKHR, PhysicalDeviceRayTracingPipelinePropertiesKHR,
+    API_VERSION_1_1,
+};
+use std::ffi::CStr;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU32},
+    Arc, Mutex,
 };
-use ash::vk::{DescriptorBufferInfo, WriteDescriptorSet};
 
 #[doc(hidden)]
 pub(crate) enum DeviceFeature {
     Swapchain,
+    ConditionalRendering,
+    RayTracingPipeline,
+    AccelerationStructure,
+    DeferredHostOperations,
+    ExternalMemory,
+    ExternalSemaphore,
+    DebugMarker,
+    Robustness2,
+    HdrMetadata,
+    PushDescriptor,
+}
+
+impl DeviceFeature {
+    #[doc(hidden)]
+    pub(crate) fn name(&self) -> &'static CStr {
+        match self {
+            DeviceFeature::Swapchain => ash::extensions::khr::Swapchain::name(),
+            DeviceFeature::ConditionalRendering => ExtConditionalRenderingFn::name(),
+            DeviceFeature::RayTracingPipeline => ash::extensions::khr::RayTracingPipeline::name(),
+            DeviceFeature::AccelerationStructure => {
+                ash::extensions::khr::AccelerationStructure::name()
+            }
+            DeviceFeature::DeferredHostOperations => {
+                ash::extensions::khr::DeferredHostOperations::name()
+            }
+            DeviceFeature::ExternalMemory => ExternalMemoryLoader::name(),
+            DeviceFeature::ExternalSemaphore => ExternalSemaphoreLoader::name(),
+            #[allow(deprecated)]
+            DeviceFeature::DebugMarker => ash::extensions::ext::DebugMarker::name(),
+            DeviceFeature::Robustness2 => ExtRobustness2Fn::name(),
+            DeviceFeature::HdrMetadata => ExtHdrMetadataFn::name(),
+            DeviceFeature::PushDescriptor => ash::extensions::khr::PushDescriptor::name(),
+        }
+    }
+}
+
+/// Optional features of the device.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DeviceFeatures {
+    pub(crate) occlusion_query_precise: bool,
+    pub(crate) pipeline_statistics_query: bool,
+    pub(crate) conditional_rendering: bool,
+    pub(crate) wide_lines: bool,
+    pub(crate) sampler_anisotropy: bool,
+    pub(crate) ray_tracing_pipeline: bool,
+    pub(crate) separate_depth_stencil_layouts: bool,
+    pub(crate) external_memory: bool,
+    pub(crate) external_semaphore: bool,
+    pub(crate) sparse_buffer: bool,
+    pub(crate) multiview: bool,
+    pub(crate) robust_buffer_access: bool,
+    pub(crate) descriptor_binding_partially_bound: bool,
+    pub(crate) descriptor_update_after_bind: bool,
+    pub(crate) null_descriptor: bool,
+    pub(crate) dynamic_rendering: bool,
+    pub(crate) draw_indirect_count: bool,
+    pub(crate) host_query_reset: bool,
+    pub(crate) extended_dynamic_state: bool,
+    pub(crate) hdr_metadata: bool,
+    pub(crate) push_descriptor: bool,
+}
+
+impl DeviceFeatures {
+    /// Initializes with all features disabled.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            occlusion_query_precise: false,
+            pipeline_statistics_query: false,
+            conditional_rendering: false,
+            wide_lines: false,
+            sampler_anisotropy: false,
+            ray_tracing_pipeline: false,
+            separate_depth_stencil_layouts: false,
+            external_memory: false,
+            external_semaphore: false,
+            sparse_buffer: false,
+            multiview: false,
+            robust_buffer_access: false,
+            descriptor_binding_partially_bound: false,
+            descriptor_update_after_bind: false,
+            null_descriptor: false,
+            dynamic_rendering: false,
+            draw_indirect_count: false,
+            host_query_reset: false,
+            extended_dynamic_state: false,
+            hdr_metadata: false,
+            push_descriptor: false,
+        }
+    }
+
+    /// Precise occlusion queries, which return the exact number of samples.
+    #[inline]
+    pub const fn occlusion_query_precise(mut self, enable: bool) -> Self {
+        self.occlusion_query_precise = enable;
+        self
+    }
+
+    pub const fn is_occlusion_query_precise(&self) -> bool {
+        self.occlusion_query_precise
+    }
+
+    /// Pipeline statistics queries.
+    #[inline]
+    pub const fn pipeline_statistics_query(mut self, enable: bool) -> Self {
+        self.pipeline_statistics_query = enable;
+        self
+    }
+
+    pub const fn is_pipeline_statistics_query(&self) -> bool {
+        self.pipeline_statistics_query
+    }
+
+    /// Conditional rendering. Enables the `VK_EXT_conditional_rendering` extension.
+    #[inline]
+    pub const fn conditional_rendering(mut self, enable: bool) -> Self {
+        self.conditional_rendering = enable;
+        self
+    }
+
+    pub const fn is_conditional_rendering(&self) -> bool {
+        self.conditional_rendering
+    }
+
+    /// Lines with a width other than 1.0.
+    #[inline]
+    pub const fn wide_lines(mut self, enable: bool) -> Self {
+        self.wide_lines = enable;
+        self
+    }
+
+    pub const fn is_wide_lines(&self) -> bool {
+        self.wide_lines
+    }
+
+    /// Anisotropic filtering in samplers.
+    #[inline]
+    pub const fn sampler_anisotropy(mut self, enable: bool) -> Self {
+        self.sampler_anisotropy = enable;
+        self
+    }
+
+    pub const fn is_sampler_anisotropy(&self) -> bool {
+        self.sampler_anisotropy
+    }
+
+    /// Ray tracing pipelines and buffer device addresses.
+    /// Enables `VK_KHR_ray_tracing_pipeline` and the extensions it depends on.
+    #[inline]
+    pub const fn ray_tracing_pipeline(mut self, enable: bool) -> Self {
+        self.ray_tracing_pipeline = enable;
+        self
+    }
+
+    pub const fn is_ray_tracing_pipeline(&self) -> bool {
+        self.ray_tracing_pipeline
+    }
+
+    /// Separate layouts for the depth and stencil aspects of an image. Requires Vulkan 1.2.
+    #[inline]
+    pub const fn separate_depth_stencil_layouts(mut self, enable: bool) -> Self {
+        self.separate_depth_stencil_layouts = enable;
+        self
+    }
+
+    pub const fn is_separate_depth_stencil_layouts(&self) -> bool {
+        self.separate_depth_stencil_layouts
+    }
+
+    /// Exporting and importing memory as OS handles.
+    /// Enables `VK_KHR_external_memory_fd`, or `VK_KHR_external_memory_win32` on Windows.
+    #[inline]
+    pub const fn external_memory(mut self, enable: bool) -> Self {
+        self.external_memory = enable;
+        self
+    }
+
+    pub const fn is_external_memory(&self) -> bool {
+        self.external_memory
+    }
+
+    /// Exporting and importing semaphores as OS handles.
+    /// Enables `VK_KHR_external_semaphore_fd`, or `VK_KHR_external_semaphore_win32` on Windows.
+    #[inline]
+    pub const fn external_semaphore(mut self, enable: bool) -> Self {
+        self.external_semaphore = enable;
+        self
+    }
+
+    pub const fn is_external_semaphore(&self) -> bool {
+        self.external_semaphore
+    }
+
+    /// Sparse buffers, which are backed by memory only where it is bound.
+    /// Enables `sparseBinding` and `sparseResidencyBuffer`.
+    #[inline]
+    pub const fn sparse_buffer(mut self, enable: bool) -> Self {
+        self.sparse_buffer = enable;
+        self
+    }
+
+    pub const fn is_sparse_buffer(&self) -> bool {
+        self.sparse_buffer
+    }
+
+    /// Rendering to several views, such as both eyes of a headset, in one render pass.
+    /// Requires Vulkan 1.2.
+    #[inline]
+    pub const fn multiview(mut self, enable: bool) -> Self {
+        self.multiview = enable;
+        self
+    }
+
+    pub const fn is_multiview(&self) -> bool {
+        self.multiview
+    }
+
+    /// Bounds-checked buffer accesses in shaders.
+    /// Out-of-bounds reads return zero or other defined values instead of undefined results.
+    #[inline]
+    pub const fn robust_buffer_access(mut self, enable: bool) -> Self {
+        self.robust_buffer_access = enable;
+        self
+    }
+
+    pub const fn is_robust_buffer_access(&self) -> bool {
+        self.robust_buffer_access
+    }
+
+    /// Resource bindings whose array elements need not all be written,
+    /// see `ResourceLayoutBinding::partially_bound`. Requires Vulkan 1.2.
+    #[inline]
+    pub const fn descriptor_binding_partially_bound(mut self, enable: bool) -> Self {
+        self.descriptor_binding_partially_bound = enable;
+        self
+    }
+
+    pub const fn is_descriptor_binding_partially_bound(&self) -> bool {
+        self.descriptor_binding_partially_bound
+    }
+
+    /// Writing sampler, image and storage buffer resources while they are in use,
+    /// see `ResourceLayoutBinding::update_after_bind`. Requires Vulkan 1.2.
+    #[inline]
+    pub const fn descriptor_update_after_bind(mut self, enable: bool) -> Self {
+        self.descriptor_update_after_bind = enable;
+        self
+    }
+
+    pub const fn is_descriptor_update_after_bind(&self) -> bool {
+        self.descriptor_update_after_bind
+    }
+
+    /// Writing null buffers and images to resources.
+    /// Enables the `VK_EXT_robustness2` extension.
+    #[inline]
+    pub const fn null_descriptor(mut self, enable: bool) -> Self {
+        self.null_descriptor = enable;
+        self
+    }
+
+    pub const fn is_null_descriptor(&self) -> bool {
+        self.null_descriptor
+    }
+
+    /// Rendering without render pass and frame buffer objects,
+    /// see `CommandRecorder::begin_rendering`. Requires Vulkan 1.3.
+    #[inline]
+    pub const fn dynamic_rendering(mut self, enable: bool) -> Self {
+        self.dynamic_rendering = enable;
+        self
+    }
+
+    pub const fn is_dynamic_rendering(&self) -> bool {
+        self.dynamic_rendering
+    }
+
+    /// Indirect draws that read the draw count from a buffer,
+    /// see `CommandRecorder::draw_indexed_indirect_count`. Requires Vulkan 1.2.
+    #[inline]
+    pub const fn draw_indirect_count(mut self, enable: bool) -> Self {
+        self.draw_indirect_count = enable;
+        self
+    }
+
+    pub const fn is_draw_indirect_count(&self) -> bool {
+        self.draw_indirect_count
+    }
+
+    /// Resetting queries from the host, see `QueryPool::reset_host`. Requires Vulkan 1.2.
+    #[inline]
+    pub const fn host_query_reset(mut self, enable: bool) -> Self {
+        self.host_query_reset = enable;
+        self
+    }
+
+    pub const fn is_host_query_reset(&self) -> bool {
+        self.host_query_reset
+    }
+
+    /// Pipeline state set while recording, see `PipelineDescriptor::dynamic_states`.
+    /// Requires Vulkan 1.3.
+    #[inline]
+    pub const fn extended_dynamic_state(mut self, enable: bool) -> Self {
+        self.extended_dynamic_state = enable;
+        self
+    }
+
+    pub const fn is_extended_dynamic_state(&self) -> bool {
+        self.extended_dynamic_state
+    }
+
+    /// Describing the mastering display of HDR content, see `Swapchain::set_hdr_metadata`.
+    /// Enables the `VK_EXT_hdr_metadata` extension.
+    #[inline]
+    pub const fn hdr_metadata(mut self, enable: bool) -> Self {
+        self.hdr_metadata = enable;
+        self
+    }
+
+    pub const fn is_hdr_metadata(&self) -> bool {
+        self.hdr_metadata
+    }
+
+    /// Writing resources directly into command buffers, see `CommandRecorder::push_resources`.
+    /// Enables the `VK_KHR_push_descriptor` extension.
+    #[inline]
+    pub const fn push_descriptor(mut self, enable: bool) -> Self {
+        self.push_descriptor = enable;
+        self
+    }
+
+    pub const fn is_push_descriptor(&self) -> bool {
+        self.push_descriptor
+    }
+
+    #[doc(hidden)]
+    /// Get the name of the first feature in self that is not in `supported`.
+    pub(crate) fn first_missing(&self, supported: &DeviceFeatures) -> Option<&'static str> {
+        if self.occlusion_query_precise && !supported.occlusion_query_precise {
+            return Some("occlusionQueryPrecise");
+        }
+        if self.pipeline_statistics_query && !supported.pipeline_statistics_query {
+            return Some("pipelineStatisticsQuery");
+        }
+        if self.conditional_rendering && !supported.conditional_rendering {
+            return Some("VK_EXT_conditional_rendering");
+        }
+        if self.wide_lines && !supported.wide_lines {
+            return Some("wideLines");
+        }
+        if self.sampler_anisotropy && !supported.sampler_anisotropy {
+            return Some("samplerAnisotropy");
+        }
+        if self.ray_tracing_pipeline && !supported.ray_tracing_pipeline {
+            return Some("VK_KHR_ray_tracing_pipeline");
+        }
+        if self.separate_depth_stencil_layouts && !supported.separate_depth_stencil_layouts {
+            return Some("separateDepthStencilLayouts");
+        }
+        if self.external_memory && !supported.external_memory {
+            return Some("VK_KHR_external_memory");
+        }
+        if self.external_semaphore && !supported.external_semaphore {
+            return Some("VK_KHR_external_semaphore");
+        }
+        if self.sparse_buffer && !supported.sparse_buffer {
+            return Some("sparseResidencyBuffer");
+        }
+        if self.multiview && !supported.multiview {
+            return Some("multiview");
+        }
+        if self.robust_buffer_access && !supported.robust_buffer_access {
+            return Some("robustBufferAccess");
+        }
+        if self.descriptor_binding_partially_bound && !supported.descriptor_binding_partially_bound
+        {
+            return Some("descriptorBindingPartiallyBound");
+        }
+        if self.descriptor_update_after_bind && !supported.descriptor_update_after_bind {
+            return Some("descriptorBindingSampledImageUpdateAfterBind");
+        }
+        if self.null_descriptor && !supported.null_descriptor {
+            return Some("nullDescriptor");
+        }
+        if self.dynamic_rendering && !supported.dynamic_rendering {
+            return Some("dynamicRendering");
+        }
+        if self.draw_indirect_count && !supported.draw_indirect_count {
+            return Some("drawIndirectCount");
+        }
+        if self.host_query_reset && !supported.host_query_reset {
+            return Some("hostQueryReset");
+        }
+        if self.extended_dynamic_state && !supported.extended_dynamic_state {
+            return Some("extendedDynamicState");
+        }
+        if self.hdr_metadata && !supported.hdr_metadata {
+            return Some("VK_EXT_hdr_metadata");
+        }
+        if self.push_descriptor && !supported.push_descriptor {
+            return Some("VK_KHR_push_descriptor");
+        }
+        None
+    }
+
+    #[doc(hidden)]
+    /// Get the device extensions required by the features.
+    pub(crate) fn extensions(&self) -> Vec<DeviceFeature> {
+        let mut extensions = vec![];
+        if self.conditional_rendering {
+            extensions.push(DeviceFeature::ConditionalRendering);
+        }
+        if self.ray_tracing_pipeline {
+            extensions.push(DeviceFeature::RayTracingPipeline);
+            extensions.push(DeviceFeature::AccelerationStructure);
+            extensions.push(DeviceFeature::DeferredHostOperations);
+        }
+        if self.external_memory {
+            extensions.push(DeviceFeature::ExternalMemory);
+        }
+        if self.external_semaphore {
+            extensions.push(DeviceFeature::ExternalSemaphore);
+        }
+        if self.null_descriptor {
+            extensions.push(DeviceFeature::Robustness2);
+        }
+        if self.hdr_metadata {
+            extensions.push(DeviceFeature::HdrMetadata);
+        }
+        if self.push_descriptor {
+            extensions.push(DeviceFeature::PushDescriptor);
+        }
+        extensions
+    }
+}
+
+impl From<PhysicalDeviceFeatures> for DeviceFeatures {
+    fn from(value: PhysicalDeviceFeatures) -> Self {
+        Self {
+            occlusion_query_precise: value.occlusion_query_precise != 0,
+            pipeline_statistics_query: value.pipeline_statistics_query != 0,
+            // Extension features are not part of PhysicalDeviceFeatures.
+            conditional_rendering: false,
+            wide_lines: value.wide_lines != 0,
+            sampler_anisotropy: value.sampler_anisotropy != 0,
+            ray_tracing_pipeline: false,
+            separate_depth_stencil_layouts: false,
+            external_memory: false,
+            external_semaphore: false,
+            sparse_buffer: value.sparse_binding != 0 && value.sparse_residency_buffer != 0,
+            multiview: false,
+            robust_buffer_access: value.robust_buffer_access != 0,
+            descriptor_binding_partially_bound: false,
+            descriptor_update_after_bind: false,
+            null_descriptor: false,
+            dynamic_rendering: false,
+            draw_indirect_count: false,
+            host_query_reset: false,
+            extended_dynamic_state: false,
+            hdr_metadata: false,
+            push_descriptor: false,
+        }
+    }
+}
+
+impl From<DeviceFeatures> for PhysicalDeviceFeatures {
+    fn from(value: DeviceFeatures) -> Self {
+        PhysicalDeviceFeatures::builder()
+            .occlusion_query_precise(value.occlusion_query_precise)
+            .pipeline_statistics_query(value.pipeline_statistics_query)
+            .wide_lines(value.wide_lines)
+            .sampler_anisotropy(value.sampler_anisotropy)
+            .sparse_binding(value.sparse_buffer)
+            .sparse_residency_buffer(value.sparse_buffer)
+            .robust_buffer_access(value.robust_buffer_access)
+            .build()
+    }
+}
+
+#[doc(hidden)]
+/// Loader and limits of `VK_KHR_ray_tracing_pipeline`.
+#[derive(Clone)]
+pub(crate) struct RayTracing {
+    pub(crate) loader: ash::extensions::khr::RayTracingPipeline,
+    pub(crate) shader_group_handle_size: u32,
+    pub(crate) shader_group_handle_alignment: u32,
+    pub(crate) shader_group_base_alignment: u32,
+}
+
+impl RayTracing {
+    fn new(
+        instance: &ash::Instance,
+        physical_device: PhysicalDevice,
+        device: &ash::Device,
+    ) -> Self {
+        let mut properties = PhysicalDeviceRayTracingPipelinePropertiesKHR::default();
+        let mut properties2 = PhysicalDeviceProperties2::builder().push_next(&mut properties);
+        unsafe {
+            instance.get_physical_device_properties2(physical_device, &mut properties2);
+        }
+        Self {
+            loader: ash::extensions::khr::RayTracingPipeline::new(instance, device),
+            shader_group_handle_size: properties.shader_group_handle_size,
+            shader_group_handle_alignment: properties.shader_group_handle_alignment,
+            shader_group_base_alignment: properties.shader_group_base_alignment,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct Device {
     #[doc(hidden)]
     pub(crate) device: ash::Device,
+    pub(crate) features: DeviceFeatures,
+    /// Vulkan version usable with the device, the lower of the device and instance versions.
+    pub(crate) api_version: u32,
+    pub(crate) limits: PhysicalDeviceLimits,
+    pub(crate) max_multiview_view_count: u32,
+    pub(crate) max_push_descriptors: u32,
+    pub(crate) conditional_rendering: Option<ExtConditionalRenderingFn>,
+    /// Used by `Swapchain::set_hdr_metadata`.
+    #[cfg_attr(not(feature = "window"), allow(dead_code))]
+    pub(crate) hdr_metadata: Option<ExtHdrMetadataFn>,
+    pub(crate) push_descriptor: Option<ash::extensions::khr::PushDescriptor>,
+    pub(crate) ray_tracing: Option<RayTracing>,
+    pub(crate) external_memory: Option<ExternalMemoryLoader>,
+    pub(crate) external_semaphore: Option<ExternalSemaphoreLoader>,
+    pub(crate) debug: DebugLoader,
+    pub(crate) queue_family_index: usize,
+    pub(crate) transfer_queue_family_index: Option<usize>,
+    pub(crate) compute_queue_family_index: Option<usize>,
+    pub(crate) present_queue_family_index: Option<usize>,
+    /// Transient command pools used by `immediate_submit`, one per queue family.
+    immediate_pools: Arc<Mutex<Vec<(usize, ash::vk::CommandPool)>>>,
+    config: DeviceConfig,
+}
+
+#[doc(hidden)]
+/// Queue families a device is created with.
+#[derive(Clone, Copy)]
+pub(crate) struct QueueFamilyIndices {
+    pub(crate) graphics: usize,
+    pub(crate) transfer: Option<usize>,
+    pub(crate) compute: Option<usize>,
+    pub(crate) present: Option<usize>,
 }
 
 impl Device {
     #[doc(hidden)]
-    pub(crate) fn from(device: ash::Device) -> Self {
-        Self { device }
+    pub(crate) fn from(
+        instance: &ash::Instance,
+        physical_device: PhysicalDevice,
+        device: ash::Device,
+        api_version: u32,
+        queue_families: QueueFamilyIndices,
+        debug: DebugLoader,
+        config: DeviceConfig,
+    ) -> Self {
+        let features = config.features;
+        let limits = unsafe { instance.get_physical_device_properties(physical_device) }.limits;
+        let max_multiview_view_count = if features.multiview {
+            let mut properties = PhysicalDeviceMultiviewProperties::default();
+            let mut properties2 = PhysicalDeviceProperties2::builder().push_next(&mut properties);
+            unsafe {
+                instance.get_physical_device_properties2(physical_device, &mut properties2);
+            }
+            properties.max_multiview_view_count
+        } else {
+            0
+        };
+        // vkGetPhysicalDeviceProperties2 is core since Vulkan 1.1.
+        // Otherwise the limit is assumed to be the minimum the specification guarantees.
+        let max_push_descriptors = if features.push_descriptor && api_version >= API_VERSION_1_1 {
+            let mut properties = PhysicalDevicePushDescriptorPropertiesKHR::default();
+            let mut properties2 = PhysicalDeviceProperties2::builder().push_next(&mut properties);
+            unsafe {
+                instance.get_physical_device_properties2(physical_device, &mut properties2);
+            }
+            properties.max_push_descriptors
+        } else {
+            32
+        };
+        let conditional_rendering = if features.conditional_rendering {
+            Some(ExtConditionalRenderingFn::load(|name| unsafe {
+                std::mem::transmute(instance.get_device_proc_addr(device.handle(), name.as_ptr()))
+            }))
+        } else {
+            None
+        };
+        let hdr_metadata = if features.hdr_metadata {
+            Some(ExtHdrMetadataFn::load(|name| unsafe {
+                std::mem::transmute(instance.get_device_proc_addr(device.handle(), name.as_ptr()))
+            }))
+        } else {
+            None
+        };
+        let push_descriptor = if features.push_descriptor {
+            Some(ash::extensions::khr::PushDescriptor::new(instance, &device))
+        } else {
+            None
+        };
+        let ray_tracing = if features.ray_tracing_pipeline {
+            Some(RayTracing::new(instance, physical_device, &device))
+        } else {
+            None
+        };
+        let external_memory = if features.external_memory {
+            Some(ExternalMemoryLoader::new(instance, &device))
+        } else {
+            None
+        };
+        let external_semaphore = if features.external_semaphore {
+            Some(ExternalSemaphoreLoader::new(
+                instance,
+                physical_device,
+                &device,
+            ))
+        } else {
+            None
+        };
+        Self {
+            device,
+            features,
+            api_version,
+            limits,
+            max_multiview_view_count,
+            max_push_descriptors,
+            conditional_rendering,
+            hdr_metadata,
+            push_descriptor,
+            ray_tracing,
+            external_memory,
+            external_semaphore,
+            debug,
+            queue_family_index: queue_families.graphics,
+            transfer_queue_family_index: queue_families.transfer,
+            compute_queue_family_index: queue_families.compute,
+            present_queue_family_index: queue_families.present,
+            immediate_pools: Arc::new(Mutex::new(vec![])),
+            config,
+        }
+    }
+
+    /// Give `object` a name shown by debugging tools such as validation layers and RenderDoc.
+    /// Uses `VK_EXT_debug_utils`, or `VK_EXT_debug_marker` if it is not available.
+    /// Does nothing if neither is available.
+    pub fn set_debug_name<T: DebugObject>(&self, object: &T, name: &str) {
+        self.debug
+            .set_name(&self.device, object.debug_handle(), name);
+    }
+
+    /// Get the features enabled on the device.
+    pub fn features(&self) -> DeviceFeatures {
+        self.features
+    }
+
+    /// Get the physical device, extensions, features and queue families
+    /// the device was created with.
+    pub fn config(&self) -> DeviceConfig {
+        self.config.clone()
+    }
+
+    /// Get the maximum anisotropy that can be used in samplers.
+    /// Returns 1.0 if the `sampler_anisotropy` feature is not enabled.
+    pub fn max_sampler_anisotropy(&self) -> f32 {
+        if self.features.sampler_anisotropy {
+            self.limits.max_sampler_anisotropy
+        } else {
+            1.0
+        }
+    }
+
+    /// Get the alignment of the offsets a buffer with `usage` can be bound at as a resource,
+    /// including dynamic offsets. A buffer used as both a uniform and a storage buffer
+    /// needs the larger of `minUniformBufferOffsetAlignment` and
+    /// `minStorageBufferOffsetAlignment`. Other usages need no alignment and return 1.
+    pub fn min_buffer_offset_alignment(&self, usage: &[BufferUsage]) -> u64 {
+        let flags = usage
+            .iter()
+            .fold(BufferUsageFlags::empty(), |flags, x| flags | (*x).into());
+        self.buffer_offset_alignment(flags)
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn buffer_offset_alignment(&self, usage: BufferUsageFlags) -> u64 {
+        let mut alignment = 1;
+        if usage.contains(BufferUsageFlags::UNIFORM_BUFFER) {
+            alignment = alignment.max(self.limits.min_uniform_buffer_offset_alignment);
+        }
+        if usage.contains(BufferUsageFlags::STORAGE_BUFFER) {
+            alignment = alignment.max(self.limits.min_storage_buffer_offset_alignment);
+        }
+        alignment
+    }
+
+    /// Get the `bufferImageGranularity` limit.
+    /// Linear resources, which are buffers and linear images, must not share a page of this size
+    /// with optimal images in the same DeviceMemory, or their contents may be corrupted.
+    pub fn buffer_image_granularity(&self) -> u64 {
+        self.limits.buffer_image_granularity
+    }
+
+    /// Get the offset at which a resource can be placed after another one in the same
+    /// DeviceMemory, respecting `buffer_image_granularity`.
+    /// If one resource is linear and the other is not and they would share a page,
+    /// `offset` is moved to the start of the next page.
+    /// # Arguments
+    ///
+    /// * `previous_end` - Offset of the end of the previous resource.
+    /// * `previous_linear` - Whether the previous resource is a buffer or a linear image.
+    /// * `offset` - Offset of the resource, already aligned to its memory requirements.
+    /// * `linear` - Whether the resource is a buffer or a linear image.
+    pub fn granularity_offset(
+        &self,
+        previous_end: u64,
+        previous_linear: bool,
+        offset: u64,
+        linear: bool,
+    ) -> u64 {
+        let granularity = self.limits.buffer_image_granularity.max(1);
+        if previous_end == 0 || previous_linear == linear {
+            return offset;
+        }
+        let previous_page = (previous_end - 1) / granularity;
+        let page = offset / granularity;
+        if page > previous_page {
+            offset
+        } else {
+            (previous_page + 1) * granularity
+        }
+    }
+
+    #[doc(hidden)]
+    /// Checks that the line width can be used and clamps it to `lineWidthRange`.
+    pub(crate) fn validate_line_width(&self, line_width: f32) -> NxResult<f32> {
+        if line_width != 1.0 && !self.features.wide_lines {
+            return Err(NxError::UnsupportedFeature(
+                "wideLines is not enabled on the device".to_string(),
+            ));
+        }
+        let range = self.limits.line_width_range;
+        Ok(line_width.clamp(range[0], range[1]))
     }
 
     /// Get the queue corresponding to queue_family_index.
@@ -37,6 +784,97 @@ impl Device {
         Queue(unsafe { self.device.get_device_queue(queue_family_index as u32, 0) })
     }
 
+    /// Get the index of the queue family used for `queue`.
+    /// `QueueSelection::Transfer`, `QueueSelection::Compute` and `QueueSelection::Present`
+    /// fall back to the queue family the device was created with
+    /// when the device has no dedicated family for them.
+    pub fn queue_family_index(&self, queue: QueueSelection) -> usize {
+        match queue {
+            QueueSelection::Graphics => self.queue_family_index,
+            QueueSelection::Transfer => self
+                .transfer_queue_family_index
+                .unwrap_or(self.queue_family_index),
+            QueueSelection::Compute => self
+                .compute_queue_family_index
+                .unwrap_or(self.queue_family_index),
+            QueueSelection::Present => self
+                .present_queue_family_index
+                .unwrap_or(self.queue_family_index),
+        }
+    }
+
+    /// Whether `queue` runs on a queue family of its own, so that its work can overlap
+    /// with work on the graphics queue.
+    pub fn has_dedicated_queue(&self, queue: QueueSelection) -> bool {
+        self.queue_family_index(queue) != self.queue_family_index
+    }
+
+    /// Get the queue used for `queue`.
+    pub fn queue(&self, queue: QueueSelection) -> Queue {
+        self.get_queue(self.queue_family_index(queue))
+    }
+
+    /// Records commands with `record`, submits them to `queue` and waits for them to finish.
+    /// Intended for one-off work such as uploads.
+    ///
+    /// Resources written on the transfer queue and used on the graphics queue must be
+    /// released with `CommandRecorder::release_image`/`release_buffer` in `record`,
+    /// then acquired with `acquire_image`/`acquire_buffer` on the graphics queue.
+    /// # Arguments
+    ///
+    /// * `queue` - Queue to submit to.
+    /// * `record` - Records the commands. The recorder is already recording.
+    pub fn immediate_submit<F>(&self, queue: QueueSelection, record: F) -> NxResult<()>
+    where
+        F: FnOnce(&CommandRecorder) -> NxResult<()>,
+    {
+        let queue_family_index = self.queue_family_index(queue);
+        // The pool stays locked until the work finishes, as command pools are not thread safe.
+        let mut pools = self.immediate_pools.lock().unwrap();
+        let pool = match pools.iter().find(|(i, _)| *i == queue_family_index) {
+            Some((_, pool)) => *pool,
+            None => {
+                let create_info = CommandPoolCreateInfo::builder()
+                    .queue_family_index(queue_family_index as u32)
+                    .flags(CommandPoolCreateFlags::TRANSIENT)
+                    .build();
+                let pool = unsafe { self.device.create_command_pool(&create_info, None) }?;
+                pools.push((queue_family_index, pool));
+                pool
+            }
+        };
+        let allocate_info = CommandBufferAllocateInfo::builder()
+            .command_pool(pool)
+            .command_buffer_count(1)
+            .level(CommandBufferLevel::PRIMARY)
+            .build();
+        let buffer = unsafe { self.device.allocate_command_buffers(&allocate_info) }?[0];
+        let recorder = CommandRecorder {
+            buffer,
+            dynamic_states: AtomicU32::new(0),
+            failed: AtomicBool::new(false),
+        };
+        let fence = Fence::new(self, &FenceDescriptor::empty());
+        let result = fence.and_then(|fence| {
+            let result = recorder
+                .record(self, record)
+                .and_then(|_| {
+                    self.get_queue(queue_family_index).submit(
+                        self,
+                        &QueueSubmitDescriptor::empty().fence(&fence),
+                        std::slice::from_ref(&recorder),
+                    )
+                })
+                .and_then(|_| fence.wait(self, u64::MAX));
+            self.destroy(&fence);
+            result
+        });
+        unsafe {
+            self.device.free_command_buffers(pool, &[buffer]);
+        }
+        result
+    }
+
     /// Create a command pool.
     pub fn create_command_pool(&self, descriptor: &CommandPoolDescriptor) -> NxResult<CommandPool> {
         CommandPool::create(&self.device, descriptor)
@@ -51,28 +889,9 @@ impl Device {
         CommandRecorder::create(self, pool, descriptor)
     }
 
+    /// Writes to a Resource. Use `DescriptorWriteBatch` to update many at once.
     pub fn update_resource(&self, descriptor: &ResourceUpdateDescriptor) {
-        let buffer_info = descriptor
-            .buffer_desc
-            .iter()
-            .map(|x| {
-                DescriptorBufferInfo::builder()
-                    .buffer(x.buffer.buffer)
-                    .offset(x.offset)
-                    .range(x.range as u64)
-                    .build()
-            })
-            .collect::<Vec<DescriptorBufferInfo>>();
-        let desc = WriteDescriptorSet::builder()
-            .dst_set(descriptor.resource.descriptor_set)
-            .dst_binding(descriptor.binding)
-            .dst_array_element(descriptor.array_element)
-            .descriptor_type(descriptor.resource_type.into())
-            .buffer_info(&buffer_info)
-            .build();
-        unsafe {
-            self.device.update_descriptor_sets(&[desc], &[]);
-        }
+        DescriptorWriteBatch::new().write(descriptor).flush(self);
     }
 
     pub fn destroy<D>(&self, object: &D)
@@ -85,6 +904,11 @@ impl Device {
 
 impl Drop for Device {
     fn drop(&mut self) {
+        for (_, pool) in self.immediate_pools.lock().unwrap().drain(..) {
+            unsafe {
+                self.device.destroy_command_pool(pool, None);
+            }
+        }
         unsafe {
             self.device.destroy_device(None);
         }
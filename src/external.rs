@@ -0,0 +1,308 @@
+use crate::{NxError, NxResult};
+use ash::extensions::khr::{
+    ExternalMemoryFd, ExternalMemoryWin32, ExternalSemaphoreFd, ExternalSemaphoreWin32,
+};
+use ash::vk::{
+    ExternalMemoryHandleTypeFlags, ExternalSemaphoreFeatureFlags, ExternalSemaphoreHandleTypeFlags,
+    PhysicalDevice, SemaphoreImportFlags,
+};
+use std::ffi::CStr;
+
+/// Type of an OS handle used to share memory with other APIs or processes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExternalMemoryHandleType {
+    /// A POSIX file descriptor. Only available on Linux and Android.
+    OpaqueFd,
+    /// A Win32 `HANDLE`. Only available on Windows.
+    OpaqueWin32,
+}
+
+impl From<ExternalMemoryHandleType> for ExternalMemoryHandleTypeFlags {
+    fn from(value: ExternalMemoryHandleType) -> Self {
+        match value {
+            ExternalMemoryHandleType::OpaqueFd => ExternalMemoryHandleTypeFlags::OPAQUE_FD,
+            ExternalMemoryHandleType::OpaqueWin32 => ExternalMemoryHandleTypeFlags::OPAQUE_WIN32,
+        }
+    }
+}
+
+/// An OS handle referring to device memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExternalMemoryHandle {
+    /// A POSIX file descriptor.
+    /// Importing the descriptor transfers its ownership to the Vulkan implementation.
+    OpaqueFd(i32),
+    /// A Win32 `HANDLE`. Importing the handle does not transfer its ownership.
+    OpaqueWin32(ash::vk::HANDLE),
+}
+
+impl ExternalMemoryHandle {
+    /// Get the type of the handle.
+    pub fn handle_type(&self) -> ExternalMemoryHandleType {
+        match self {
+            ExternalMemoryHandle::OpaqueFd(_) => ExternalMemoryHandleType::OpaqueFd,
+            ExternalMemoryHandle::OpaqueWin32(_) => ExternalMemoryHandleType::OpaqueWin32,
+        }
+    }
+}
+
+#[doc(hidden)]
+/// Loader of the platform's external memory extension.
+#[derive(Clone)]
+pub(crate) enum ExternalMemoryLoader {
+    Fd(ExternalMemoryFd),
+    Win32(ExternalMemoryWin32),
+}
+
+impl ExternalMemoryLoader {
+    pub(crate) fn new(instance: &ash::Instance, device: &ash::Device) -> Self {
+        if cfg!(windows) {
+            Self::Win32(ExternalMemoryWin32::new(instance, device))
+        } else {
+            Self::Fd(ExternalMemoryFd::new(instance, device))
+        }
+    }
+
+    /// Name of the device extension used on this platform.
+    pub(crate) fn name() -> &'static CStr {
+        if cfg!(windows) {
+            ExternalMemoryWin32::name()
+        } else {
+            ExternalMemoryFd::name()
+        }
+    }
+
+    /// Export `memory` as a handle of `handle_type`.
+    pub(crate) fn export(
+        &self,
+        memory: ash::vk::DeviceMemory,
+        handle_type: ExternalMemoryHandleType,
+    ) -> NxResult<ExternalMemoryHandle> {
+        match (self, handle_type) {
+            (Self::Fd(loader), ExternalMemoryHandleType::OpaqueFd) => {
+                let info = ash::vk::MemoryGetFdInfoKHR::builder()
+                    .memory(memory)
+                    .handle_type(handle_type.into())
+                    .build();
+                let fd = unsafe { loader.get_memory_fd(&info) }?;
+                Ok(ExternalMemoryHandle::OpaqueFd(fd))
+            }
+            (Self::Win32(loader), ExternalMemoryHandleType::OpaqueWin32) => {
+                let info = ash::vk::MemoryGetWin32HandleInfoKHR::builder()
+                    .memory(memory)
+                    .handle_type(handle_type.into())
+                    .build();
+                let handle = unsafe { loader.get_memory_win32_handle(&info) }?;
+                Ok(ExternalMemoryHandle::OpaqueWin32(handle))
+            }
+            _ => Err(Self::unsupported(handle_type)),
+        }
+    }
+
+    /// Check that `handle` can be imported on this platform.
+    pub(crate) fn validate(&self, handle: ExternalMemoryHandle) -> NxResult<()> {
+        match (self, handle) {
+            (Self::Fd(_), ExternalMemoryHandle::OpaqueFd(_))
+            | (Self::Win32(_), ExternalMemoryHandle::OpaqueWin32(_)) => Ok(()),
+            _ => Err(Self::unsupported(handle.handle_type())),
+        }
+    }
+
+    fn unsupported(handle_type: ExternalMemoryHandleType) -> NxError {
+        NxError::UnsupportedFeature(format!(
+            "{:?} handles are not supported on this platform",
+            handle_type
+        ))
+    }
+}
+
+/// Type of an OS handle used to share a semaphore with other APIs or processes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExternalSemaphoreHandleType {
+    /// A POSIX file descriptor. Only available on Linux and Android.
+    OpaqueFd,
+    /// A Win32 `HANDLE`. Only available on Windows.
+    OpaqueWin32,
+}
+
+impl From<ExternalSemaphoreHandleType> for ExternalSemaphoreHandleTypeFlags {
+    fn from(value: ExternalSemaphoreHandleType) -> Self {
+        match value {
+            ExternalSemaphoreHandleType::OpaqueFd => ExternalSemaphoreHandleTypeFlags::OPAQUE_FD,
+            ExternalSemaphoreHandleType::OpaqueWin32 => {
+                ExternalSemaphoreHandleTypeFlags::OPAQUE_WIN32
+            }
+        }
+    }
+}
+
+/// An OS handle referring to a semaphore payload.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExternalSemaphoreHandle {
+    /// A POSIX file descriptor.
+    /// Importing the descriptor transfers its ownership to the Vulkan implementation.
+    OpaqueFd(i32),
+    /// A Win32 `HANDLE`. Importing the handle does not transfer its ownership.
+    OpaqueWin32(ash::vk::HANDLE),
+}
+
+impl ExternalSemaphoreHandle {
+    /// Get the type of the handle.
+    pub fn handle_type(&self) -> ExternalSemaphoreHandleType {
+        match self {
+            ExternalSemaphoreHandle::OpaqueFd(_) => ExternalSemaphoreHandleType::OpaqueFd,
+            ExternalSemaphoreHandle::OpaqueWin32(_) => ExternalSemaphoreHandleType::OpaqueWin32,
+        }
+    }
+}
+
+#[doc(hidden)]
+/// Loader of the platform's external semaphore extension,
+/// with the operations the device supports for its handle type.
+#[derive(Clone)]
+pub(crate) struct ExternalSemaphoreLoader {
+    loader: ExternalSemaphoreFns,
+    features: ExternalSemaphoreFeatureFlags,
+}
+
+#[derive(Clone)]
+enum ExternalSemaphoreFns {
+    Fd(ExternalSemaphoreFd),
+    Win32(ExternalSemaphoreWin32),
+}
+
+impl ExternalSemaphoreLoader {
+    pub(crate) fn new(
+        instance: &ash::Instance,
+        physical_device: PhysicalDevice,
+        device: &ash::Device,
+    ) -> Self {
+        let (loader, handle_type) = if cfg!(windows) {
+            (
+                ExternalSemaphoreFns::Win32(ExternalSemaphoreWin32::new(instance, device)),
+                ExternalSemaphoreHandleType::OpaqueWin32,
+            )
+        } else {
+            (
+                ExternalSemaphoreFns::Fd(ExternalSemaphoreFd::new(instance, device)),
+                ExternalSemaphoreHandleType::OpaqueFd,
+            )
+        };
+        let info = ash::vk::PhysicalDeviceExternalSemaphoreInfo::builder()
+            .handle_type(handle_type.into())
+            .build();
+        let mut properties = ash::vk::ExternalSemaphoreProperties::default();
+        unsafe {
+            instance.get_physical_device_external_semaphore_properties(
+                physical_device,
+                &info,
+                &mut properties,
+            );
+        }
+        Self {
+            loader,
+            features: properties.external_semaphore_features,
+        }
+    }
+
+    /// Name of the device extension used on this platform.
+    pub(crate) fn name() -> &'static CStr {
+        if cfg!(windows) {
+            ExternalSemaphoreWin32::name()
+        } else {
+            ExternalSemaphoreFd::name()
+        }
+    }
+
+    /// Check that semaphores can be exported as handles of `handle_type`.
+    pub(crate) fn validate_export(&self, handle_type: ExternalSemaphoreHandleType) -> NxResult<()> {
+        self.validate(handle_type, ExternalSemaphoreFeatureFlags::EXPORTABLE)
+    }
+
+    /// Export the payload of `semaphore` as a handle of `handle_type`.
+    pub(crate) fn export(
+        &self,
+        semaphore: ash::vk::Semaphore,
+        handle_type: ExternalSemaphoreHandleType,
+    ) -> NxResult<ExternalSemaphoreHandle> {
+        self.validate_export(handle_type)?;
+        match &self.loader {
+            ExternalSemaphoreFns::Fd(loader) => {
+                let info = ash::vk::SemaphoreGetFdInfoKHR::builder()
+                    .semaphore(semaphore)
+                    .handle_type(handle_type.into())
+                    .build();
+                let fd = unsafe { loader.get_semaphore_fd(&info) }?;
+                Ok(ExternalSemaphoreHandle::OpaqueFd(fd))
+            }
+            ExternalSemaphoreFns::Win32(loader) => {
+                let info = ash::vk::SemaphoreGetWin32HandleInfoKHR::builder()
+                    .semaphore(semaphore)
+                    .handle_type(handle_type.into())
+                    .build();
+                let handle = unsafe { loader.get_semaphore_win32_handle(&info) }?;
+                Ok(ExternalSemaphoreHandle::OpaqueWin32(handle))
+            }
+        }
+    }
+
+    /// Replace the payload of `semaphore` with the one referred to by `handle`.
+    pub(crate) fn import(
+        &self,
+        semaphore: ash::vk::Semaphore,
+        handle: ExternalSemaphoreHandle,
+        temporary: bool,
+    ) -> NxResult<()> {
+        let handle_type = handle.handle_type();
+        self.validate(handle_type, ExternalSemaphoreFeatureFlags::IMPORTABLE)?;
+        let flags = if temporary {
+            SemaphoreImportFlags::TEMPORARY
+        } else {
+            SemaphoreImportFlags::empty()
+        };
+        match (&self.loader, handle) {
+            (ExternalSemaphoreFns::Fd(loader), ExternalSemaphoreHandle::OpaqueFd(fd)) => {
+                let info = ash::vk::ImportSemaphoreFdInfoKHR::builder()
+                    .semaphore(semaphore)
+                    .flags(flags)
+                    .handle_type(handle_type.into())
+                    .fd(fd)
+                    .build();
+                unsafe { loader.import_semaphore_fd(&info) }?;
+            }
+            (ExternalSemaphoreFns::Win32(loader), ExternalSemaphoreHandle::OpaqueWin32(x)) => {
+                let info = ash::vk::ImportSemaphoreWin32HandleInfoKHR::builder()
+                    .semaphore(semaphore)
+                    .flags(flags)
+                    .handle_type(handle_type.into())
+                    .handle(x)
+                    .build();
+                unsafe { loader.import_semaphore_win32_handle(&info) }?;
+            }
+            _ => return Err(Self::unsupported(handle_type)),
+        }
+        Ok(())
+    }
+
+    fn validate(
+        &self,
+        handle_type: ExternalSemaphoreHandleType,
+        feature: ExternalSemaphoreFeatureFlags,
+    ) -> NxResult<()> {
+        let platform = match self.loader {
+            ExternalSemaphoreFns::Fd(_) => ExternalSemaphoreHandleType::OpaqueFd,
+            ExternalSemaphoreFns::Win32(_) => ExternalSemaphoreHandleType::OpaqueWin32,
+        };
+        if handle_type != platform || !self.features.contains(feature) {
+            return Err(Self::unsupported(handle_type));
+        }
+        Ok(())
+    }
+
+    fn unsupported(handle_type: ExternalSemaphoreHandleType) -> NxError {
+        NxError::UnsupportedFeature(format!(
+            "{:?} semaphore handles are not supported by the device",
+            handle_type
+        ))
+    }
+}
@@ -1,4 +1,19 @@
 use ash::vk::FenceCreateInfo;
+#[cfg(feature = "async")]
+use std::future::Future;
+#[cfg(feature = "async")]
+use std::marker::PhantomData;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "async")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
+#[cfg(feature = "async")]
+use std::thread::JoinHandle;
+use std::time::Duration;
 
 use crate::{Destroy, Device, Instance, NxError, NxResult};
 
@@ -51,17 +66,78 @@ impl Fence {
 
     /// Wait until the GPU finishes processing.
     /// The time until timeout must be specified.
+    /// Returns `NxError::Timeout` if the timeout expired
+    /// and `NxError::DeviceLost` if the device was lost.
     pub fn wait(&self, device: &Device, timeout: u64) -> NxResult<()> {
         match unsafe { device.device.wait_for_fences(&[self.fence], true, timeout) } {
             Ok(_) => Ok(()),
             Err(e) => match e {
+                ash::vk::Result::TIMEOUT => Err(NxError::Timeout),
                 ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
                 ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                ash::vk::Result::ERROR_DEVICE_LOST => Err(NxError::DeviceLost),
                 _ => Err(NxError::Unknown),
             }?,
         }
     }
 
+    /// Block the thread until the GPU finishes processing, without a timeout.
+    /// The driver can put the thread to sleep, which is the most power-efficient way to wait.
+    /// Returns `NxError::DeviceLost` if the device was lost.
+    pub fn wait_blocking(&self, device: &Device) -> NxResult<()> {
+        self.wait(device, u64::MAX)
+    }
+
+    /// Wait until the GPU finishes processing by polling the fence, calling `work` between polls.
+    /// The interval starts at `poll_interval` and doubles after each poll,
+    /// up to 16 times `poll_interval`.
+    /// Returns `NxError::DeviceLost` if the device was lost.
+    pub fn wait_polling<F: FnMut()>(
+        &self,
+        device: &Device,
+        poll_interval: Duration,
+        mut work: F,
+    ) -> NxResult<()> {
+        let mut interval = poll_interval;
+        while !self.is_signaled(device)? {
+            work();
+            std::thread::sleep(interval);
+            interval = (interval * 2).min(poll_interval * 16);
+        }
+        Ok(())
+    }
+
+    /// Check whether the fence is signaled without waiting.
+    /// Returns `NxError::DeviceLost` if the device was lost.
+    pub fn is_signaled(&self, device: &Device) -> NxResult<bool> {
+        match unsafe { device.device.get_fence_status(self.fence) } {
+            Ok(x) => Ok(x),
+            Err(e) => match e {
+                ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                ash::vk::Result::ERROR_DEVICE_LOST => Err(NxError::DeviceLost),
+                _ => Err(NxError::Unknown),
+            },
+        }
+    }
+
+    /// Check whether the fence is signaled without waiting.
+    /// Unlike `is_signaled`, errors such as a lost device are reported as ready,
+    /// so that waiters wake up and can inspect them with `is_signaled` or `wait`.
+    pub fn poll_ready(&self, device: &Device) -> bool {
+        self.is_signaled(device).unwrap_or(true)
+    }
+
+    /// Get a future that completes when the GPU finishes processing.
+    /// The fence is waited on by a background thread, so the future does not block the executor
+    /// and works with any async runtime.
+    /// Dropping the future before it completes stops the thread.
+    /// **"async" feature is required.**
+    #[cfg(feature = "async")]
+    pub fn wait_async<'a>(&'a self, device: &'a Device) -> FenceFuture<'a> {
+        FenceFuture::new(device.device.clone(), self.fence)
+    }
+
     /// Reset fence status.
     pub fn reset(&self, device: &Device) -> NxResult<()> {
         match unsafe { device.device.reset_fences(&[self.fence]) } {
@@ -84,3 +160,93 @@ impl Destroy for Fence {
         }
     }
 }
+
+/// How long the background thread of a FenceFuture waits before checking for cancellation.
+#[cfg(feature = "async")]
+const ASYNC_WAIT_SLICE: u64 = 10_000_000;
+
+#[cfg(feature = "async")]
+#[derive(Default)]
+struct FenceFutureState {
+    result: Option<NxResult<()>>,
+    waker: Option<Waker>,
+}
+
+/// Future returned by `Fence::wait_async`.
+/// **"async" feature is required.**
+#[cfg(feature = "async")]
+pub struct FenceFuture<'a> {
+    state: Arc<Mutex<FenceFutureState>>,
+    cancelled: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    _fence: PhantomData<&'a Fence>,
+}
+
+#[cfg(feature = "async")]
+impl FenceFuture<'_> {
+    fn new(device: ash::Device, fence: ash::vk::Fence) -> Self {
+        let state = Arc::new(Mutex::new(FenceFutureState::default()));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let state = state.clone();
+            let cancelled = cancelled.clone();
+            std::thread::spawn(move || {
+                let result = loop {
+                    if cancelled.load(Ordering::Acquire) {
+                        return;
+                    }
+                    match unsafe { device.wait_for_fences(&[fence], true, ASYNC_WAIT_SLICE) } {
+                        Ok(_) => break Ok(()),
+                        Err(ash::vk::Result::TIMEOUT) => continue,
+                        Err(ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => {
+                            break Err(NxError::OutOfDeviceMemory)
+                        }
+                        Err(ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY) => {
+                            break Err(NxError::OutOfHostMemory)
+                        }
+                        Err(ash::vk::Result::ERROR_DEVICE_LOST) => break Err(NxError::DeviceLost),
+                        Err(_) => break Err(NxError::Unknown),
+                    }
+                };
+                let mut state = state.lock().unwrap();
+                state.result = Some(result);
+                if let Some(waker) = state.waker.take() {
+                    waker.wake();
+                }
+            })
+        };
+        Self {
+            state,
+            cancelled,
+            thread: Some(thread),
+            _fence: PhantomData,
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Future for FenceFuture<'_> {
+    type Output = NxResult<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut state = self.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(result),
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for FenceFuture<'_> {
+    fn drop(&mut self) {
+        // The thread must stop using the fence before the borrow ends.
+        self.cancelled.store(true, Ordering::Release);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
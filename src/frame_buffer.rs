@@ -8,6 +8,7 @@ pub struct FrameBufferDescriptor<'a> {
     height: u32,
     render_pass: Option<&'a RenderPass>,
     image_view: Option<&'a ImageView>,
+    image_views: &'a [&'a ImageView],
 }
 
 impl<'a> FrameBufferDescriptor<'a> {
@@ -19,6 +20,7 @@ impl<'a> FrameBufferDescriptor<'a> {
             height: 100,
             render_pass: None,
             image_view: None,
+            image_views: &[],
         }
     }
 
@@ -44,6 +46,15 @@ impl<'a> FrameBufferDescriptor<'a> {
         self
     }
 
+    #[inline]
+    #[must_use]
+    /// ImageViews used to create the FrameBuffer, one for each attachment of the RenderPass.
+    /// Takes precedence over `image_view`.
+    pub fn image_views(mut self, image_views: &'a [&'a ImageView]) -> Self {
+        self.image_views = image_views;
+        self
+    }
+
     #[inline]
     #[must_use]
     /// RenderPass used to create the FrameBuffer.
@@ -66,13 +77,36 @@ impl FrameBuffer {
     /// * `descriptor` - Appropriate FenceDescriptor.
     pub fn new(device: &Device, descriptor: &FrameBufferDescriptor) -> NxResult<Self> {
         let render_pass = descriptor.render_pass.unwrap();
-        let image_view = descriptor.image_view.unwrap();
+        let views = if descriptor.image_views.is_empty() {
+            vec![descriptor.image_view.unwrap()]
+        } else {
+            descriptor.image_views.to_vec()
+        };
+        // Multiview render passes draw each view into its own layer of the attachments.
+        let view_count = u32::BITS - render_pass.view_mask.leading_zeros();
+        if let Some(view) = views.iter().find(|x| x.layer_count < view_count) {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the render pass renders {} views but an image view has {} layers",
+                view_count, view.layer_count
+            )));
+        }
+        let attachments = views
+            .iter()
+            .map(|x| x.image_view)
+            .collect::<Vec<ash::vk::ImageView>>();
+        if attachments.len() != render_pass.attachment_count {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the render pass has {} attachments but {} image views were given",
+                render_pass.attachment_count,
+                attachments.len()
+            )));
+        }
         let create_info = FramebufferCreateInfo::builder()
             .width(descriptor.width)
             .height(descriptor.height)
             .layers(1)
             .render_pass(render_pass.render_pass)
-            .attachments(&[image_view.image_view])
+            .attachments(&attachments)
             .build();
         let frame_buffer = match unsafe { device.device.create_framebuffer(&create_info, None) } {
             Ok(x) => x,
@@ -0,0 +1,433 @@
+use crate::{
+    BarrierBatch, CommandRecorder, Destroy, Device, DeviceConnecter, DeviceMemory, Image,
+    ImageDescriptor, Instance, NxError, NxResult, ResourceState,
+};
+use ash::vk::{MemoryPropertyFlags, MemoryRequirements};
+
+/// Handle to an image used by a RenderGraph.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct GraphImage(usize);
+
+enum GraphResource<'a> {
+    Imported(&'a Image),
+    Transient(ImageDescriptor),
+}
+
+type RecordFn<'a> = Box<dyn FnOnce(&CommandRecorder, &GraphImages) -> NxResult<()> + 'a>;
+
+/// Stores information needed to add a pass to a RenderGraph.
+pub struct PassDescriptor<'a> {
+    name: String,
+    reads: Vec<(GraphImage, ResourceState)>,
+    writes: Vec<(GraphImage, ResourceState)>,
+    record: Option<RecordFn<'a>>,
+}
+
+impl<'a> PassDescriptor<'a> {
+    /// Initializes a new pass that uses no images and records nothing.
+    /// # Arguments
+    ///
+    /// * `name` - Name of the pass, used in error messages.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            reads: vec![],
+            writes: vec![],
+            record: None,
+        }
+    }
+
+    /// Declares that the pass reads `image` in `state`.
+    #[inline]
+    pub fn read(mut self, image: GraphImage, state: ResourceState) -> Self {
+        self.reads.push((image, state));
+        self
+    }
+
+    /// Declares that the pass writes `image` in `state`.
+    #[inline]
+    pub fn write(mut self, image: GraphImage, state: ResourceState) -> Self {
+        self.writes.push((image, state));
+        self
+    }
+
+    /// Sets the closure that records the commands of the pass.
+    /// It is called outside a render pass, after the barriers for the pass have been recorded.
+    pub fn record<F>(mut self, record: F) -> Self
+    where
+        F: FnOnce(&CommandRecorder, &GraphImages) -> NxResult<()> + 'a,
+    {
+        self.record = Some(Box::new(record));
+        self
+    }
+
+    fn uses(&self) -> impl Iterator<Item = &(GraphImage, ResourceState)> {
+        self.reads.iter().chain(self.writes.iter())
+    }
+}
+
+/// The images of a RenderGraph, passed to the record closure of each pass.
+pub struct GraphImages<'a> {
+    /// `None` for transient images that are only used by skipped passes.
+    images: Vec<Option<&'a Image>>,
+}
+
+impl GraphImages<'_> {
+    /// Get the image behind a handle.
+    /// Returns `NxError::NoValue` for a transient image that is only used by skipped passes,
+    /// since such images are never created.
+    pub fn image(&self, image: GraphImage) -> NxResult<&Image> {
+        match self.images.get(image.0) {
+            Some(Some(x)) => Ok(x),
+            _ => Err(NxError::NoValue),
+        }
+    }
+}
+
+/// Transient images created by `RenderGraph::execute`.
+/// Must be destroyed once the recorded commands have finished executing.
+pub struct TransientImages {
+    images: Vec<Image>,
+    memory: Option<DeviceMemory>,
+}
+
+impl Destroy for TransientImages {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        for image in &self.images {
+            device.destroy(image);
+        }
+        if let Some(memory) = self.memory.as_ref() {
+            device.destroy(memory);
+        }
+    }
+}
+
+struct Lifetime {
+    first: usize,
+    last: usize,
+    alias_of: Option<usize>,
+    offset: u64,
+}
+
+/// A single-queue render graph.
+///
+/// Passes run in the order they are added. Passes whose writes are never used by a later
+/// pass or by an imported image are skipped. Barriers between passes are recorded
+/// automatically, and transient images whose lifetimes do not overlap share memory.
+pub struct RenderGraph<'a> {
+    resources: Vec<GraphResource<'a>>,
+    passes: Vec<PassDescriptor<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Initializes an empty graph.
+    pub fn new() -> Self {
+        Self {
+            resources: vec![],
+            passes: vec![],
+        }
+    }
+
+    /// Uses an existing image in the graph.
+    /// The state of the image must be tracked with `Image::track_state`.
+    /// Writes to imported images are considered to be the outputs of the graph.
+    pub fn import_image(&mut self, image: &'a Image) -> GraphImage {
+        self.resources.push(GraphResource::Imported(image));
+        GraphImage(self.resources.len() - 1)
+    }
+
+    /// Declares an image that only lives while the graph executes.
+    /// Its contents are undefined until a pass writes it.
+    pub fn create_image(&mut self, descriptor: &ImageDescriptor) -> GraphImage {
+        self.resources
+            .push(GraphResource::Transient(descriptor.clone()));
+        GraphImage(self.resources.len() - 1)
+    }
+
+    /// Adds a pass after the passes already added.
+    pub fn add_pass(&mut self, pass: PassDescriptor<'a>) {
+        self.passes.push(pass);
+    }
+
+    /// Creates the transient images and records every pass with its barriers to `recorder`.
+    /// The recorder must be recording and outside a render pass.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `device` - Reference to the appropriate device.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `recorder` - CommandRecorder to record to.
+    pub fn execute(
+        self,
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        recorder: &CommandRecorder,
+    ) -> NxResult<TransientImages> {
+        self.validate()?;
+        let mut transients = TransientImages {
+            images: vec![],
+            memory: None,
+        };
+        match self.record(instance, device, connecter, recorder, &mut transients) {
+            Ok(_) => Ok(transients),
+            Err(e) => {
+                device.destroy(&transients);
+                Err(e)
+            }
+        }
+    }
+
+    fn validate(&self) -> NxResult<()> {
+        let mut written = vec![false; self.resources.len()];
+        for pass in &self.passes {
+            for (image, _) in pass.uses() {
+                if image.0 >= self.resources.len() {
+                    return Err(NxError::InvalidDescriptor(format!(
+                        "pass \"{}\" uses an image from another graph",
+                        pass.name
+                    )));
+                }
+            }
+            for (image, _) in &pass.reads {
+                if let GraphResource::Transient(_) = self.resources[image.0] {
+                    if !written[image.0] {
+                        return Err(NxError::InvalidDescriptor(format!(
+                            "pass \"{}\" reads a transient image before it is written",
+                            pass.name
+                        )));
+                    }
+                }
+            }
+            for (image, _) in &pass.writes {
+                written[image.0] = true;
+            }
+        }
+        Ok(())
+    }
+
+    /// Walks the passes backwards and keeps those that contribute to an output.
+    /// Passes that write nothing are always kept.
+    fn kept_passes(&self) -> Vec<bool> {
+        let mut needed: Vec<bool> = self
+            .resources
+            .iter()
+            .map(|x| matches!(x, GraphResource::Imported(_)))
+            .collect();
+        let mut kept = vec![false; self.passes.len()];
+        for (i, pass) in self.passes.iter().enumerate().rev() {
+            if pass.writes.is_empty() || pass.writes.iter().any(|(x, _)| needed[x.0]) {
+                kept[i] = true;
+                for (image, _) in pass.uses() {
+                    needed[image.0] = true;
+                }
+            }
+        }
+        kept
+    }
+
+    /// First and last kept pass using each transient image.
+    fn lifetimes(&self, kept: &[bool]) -> Vec<Option<Lifetime>> {
+        let mut lifetimes: Vec<Option<Lifetime>> = self.resources.iter().map(|_| None).collect();
+        for (i, pass) in self.passes.iter().enumerate() {
+            if !kept[i] {
+                continue;
+            }
+            for (image, _) in pass.uses() {
+                if let GraphResource::Imported(_) = self.resources[image.0] {
+                    continue;
+                }
+                match lifetimes[image.0].as_mut() {
+                    Some(x) => x.last = i,
+                    None => {
+                        lifetimes[image.0] = Some(Lifetime {
+                            first: i,
+                            last: i,
+                            alias_of: None,
+                            offset: 0,
+                        })
+                    }
+                }
+            }
+        }
+        lifetimes
+    }
+
+    fn record(
+        self,
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        recorder: &CommandRecorder,
+        transients: &mut TransientImages,
+    ) -> NxResult<()> {
+        let kept = self.kept_passes();
+        let mut lifetimes = self.lifetimes(&kept);
+
+        // Create the transient images in the order they are first used and place each one
+        // either after the others or over an image whose last use comes before its first use.
+        let mut order: Vec<usize> = (0..lifetimes.len())
+            .filter(|x| lifetimes[*x].is_some())
+            .collect();
+        order.sort_by_key(|x| lifetimes[*x].as_ref().unwrap().first);
+
+        let mut unbound = vec![];
+        let mut slots: Vec<(usize, u64, u64)> = vec![];
+        let mut mem_req = MemoryRequirements {
+            size: 0,
+            alignment: 1,
+            memory_type_bits: !0,
+        };
+        for index in &order {
+            let descriptor = match &self.resources[*index] {
+                GraphResource::Transient(x) => x,
+                GraphResource::Imported(_) => unreachable!(),
+            };
+            let (image, requirements) =
+                match Image::create_unbound(instance, device, connecter, descriptor) {
+                    Ok(x) => x,
+                    Err(e) => {
+                        for (image, _, _) in &unbound {
+                            unsafe {
+                                device.device.destroy_image(*image, None);
+                            }
+                        }
+                        return Err(e);
+                    }
+                };
+            let req = requirements.to_vk();
+            unbound.push((image, requirements, *index));
+            mem_req.memory_type_bits &= req.memory_type_bits;
+            mem_req.alignment = mem_req.alignment.max(req.alignment);
+
+            let first = lifetimes[*index].as_ref().unwrap().first;
+            let slot = slots.iter_mut().find(|(owner, offset, size)| {
+                lifetimes[*owner].as_ref().unwrap().last < first
+                    && *size >= req.size
+                    && offset % req.alignment == 0
+            });
+            match slot {
+                Some((owner, offset, _)) => {
+                    let alias_of = *owner;
+                    let offset = *offset;
+                    *owner = *index;
+                    let lifetime = lifetimes[*index].as_mut().unwrap();
+                    lifetime.alias_of = Some(alias_of);
+                    lifetime.offset = offset;
+                }
+                None => {
+                    let offset = mem_req.size.div_ceil(req.alignment) * req.alignment;
+                    mem_req.size = offset + req.size;
+                    slots.push((*index, offset, req.size));
+                    lifetimes[*index].as_mut().unwrap().offset = offset;
+                }
+            }
+        }
+
+        let mut positions = vec![None; self.resources.len()];
+        if !unbound.is_empty() {
+            let memory = if mem_req.memory_type_bits == 0 {
+                Err(NxError::InvalidDescriptor(
+                    "transient images have no memory type in common".to_string(),
+                ))
+            } else {
+                DeviceMemory::with_requirements(
+                    instance,
+                    connecter,
+                    device,
+                    mem_req,
+                    MemoryPropertyFlags::DEVICE_LOCAL,
+                )
+            };
+            let memory = match memory {
+                Ok(x) => x,
+                Err(e) => {
+                    for (image, _, _) in &unbound {
+                        unsafe {
+                            device.device.destroy_image(*image, None);
+                        }
+                    }
+                    return Err(e);
+                }
+            };
+            let memory = transients.memory.insert(memory);
+            let mut unbound = unbound.into_iter();
+            while let Some((image, requirements, index)) = unbound.next() {
+                let descriptor = match &self.resources[index] {
+                    GraphResource::Transient(x) => x,
+                    GraphResource::Imported(_) => unreachable!(),
+                };
+                let offset = lifetimes[index].as_ref().unwrap().offset;
+                match Image::bind_aliased(device, image, &requirements, descriptor, memory, offset)
+                {
+                    Ok(x) => {
+                        positions[index] = Some(transients.images.len());
+                        transients.images.push(x);
+                    }
+                    Err(e) => {
+                        for (image, _, _) in unbound {
+                            unsafe {
+                                device.device.destroy_image(image, None);
+                            }
+                        }
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        let images = GraphImages {
+            images: self
+                .resources
+                .iter()
+                .enumerate()
+                .map(|(i, x)| match x {
+                    GraphResource::Imported(image) => Some(*image),
+                    GraphResource::Transient(_) => positions[i].map(|x| &transients.images[x]),
+                })
+                .collect(),
+        };
+        for (i, pass) in self.passes.into_iter().enumerate() {
+            if !kept[i] {
+                continue;
+            }
+            let mut batch = BarrierBatch::new();
+            for (image, state) in pass.uses() {
+                // Every image used by a kept pass has been created.
+                let graph_image = images.image(*image)?;
+                if let Some(lifetime) = lifetimes[image.0].as_ref() {
+                    if lifetime.first == i && graph_image.state().is_none() {
+                        // The previous contents are discarded, but the barrier must still wait
+                        // for the last use of the image sharing the memory.
+                        let initial = match lifetime.alias_of {
+                            Some(x) => match images.image(GraphImage(x))?.state() {
+                                Some(x) => ResourceState {
+                                    access: x.access,
+                                    stages: x.stages,
+                                    ..ResourceState::UNDEFINED
+                                },
+                                None => ResourceState::UNDEFINED,
+                            },
+                            None => ResourceState::UNDEFINED,
+                        };
+                        graph_image.track_state(initial);
+                    }
+                }
+                graph_image.batch_transition(device, &mut batch, *state)?;
+            }
+            batch.flush(device, recorder);
+            if let Some(record) = pass.record {
+                record(recorder, &images)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for RenderGraph<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
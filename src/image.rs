@@ -1,13 +1,18 @@
 use std::os::raw::c_void;
 
 use crate::{
-    Destroy, Device, DeviceConnecter, DeviceMemory, Extent3d, Instance, NxError, NxResult,
+    convert_pixels, BarrierBatch, Buffer, BufferDescriptor, BufferUsage, CommandRecorder,
+    DedicatedAllocation, Destroy, Device, DeviceConnecter, DeviceMemory, Extent3d,
+    ExternalMemoryHandleType, Instance, NxError, NxResult, PixelFormat, QueueSelection,
+    ResourceMemoryRequirements, ResourceState, SubresourceRange,
 };
 use ash::vk::{
-    ComponentMapping, ComponentSwizzle, Format, ImageAspectFlags, ImageCreateInfo, ImageLayout,
-    ImageSubresourceRange, ImageTiling, ImageUsageFlags, ImageViewCreateInfo, ImageViewType,
-    MemoryMapFlags, SampleCountFlags, SharingMode,
+    BufferImageCopy, ComponentMapping, ComponentSwizzle, ExternalMemoryImageCreateInfo, Format,
+    FormatFeatureFlags, ImageAspectFlags, ImageCreateFlags, ImageCreateInfo, ImageSubresource,
+    ImageSubresourceLayers, ImageSubresourceRange, ImageUsageFlags, ImageViewCreateInfo,
+    ImageViewType, MemoryMapFlags, MemoryPropertyFlags, SampleCountFlags, SharingMode,
 };
+use std::sync::Mutex;
 
 /// Indicates the format of the image.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -29,6 +34,24 @@ pub enum ImageFormat {
     A2B10G10R10UintPack32,
     Undefined,
     B8G8R8A8Unorm,
+    D16Unorm,
+    D32Sfloat,
+    D24UnormS8Uint,
+    D32SfloatS8Uint,
+    Bc1RgbaUnormBlock,
+    Bc1RgbaSrgbBlock,
+    Bc2UnormBlock,
+    Bc2SrgbBlock,
+    Bc3UnormBlock,
+    Bc3SrgbBlock,
+    Bc4UnormBlock,
+    Bc4SnormBlock,
+    Bc5UnormBlock,
+    Bc5SnormBlock,
+    Bc6hUfloatBlock,
+    Bc6hSfloatBlock,
+    Bc7UnormBlock,
+    Bc7SrgbBlock,
 }
 
 impl Into<ImageFormat> for Format {
@@ -50,6 +73,24 @@ impl Into<ImageFormat> for Format {
             Format::R8G8B8A8_SSCALED => ImageFormat::R8G8B8A8Sscaled,
             Format::R8G8B8A8_SNORM => ImageFormat::R8G8B8A8Snorm,
             Format::R8G8B8A8_UINT => ImageFormat::R8G8B8A8Uint,
+            Format::D16_UNORM => ImageFormat::D16Unorm,
+            Format::D32_SFLOAT => ImageFormat::D32Sfloat,
+            Format::D24_UNORM_S8_UINT => ImageFormat::D24UnormS8Uint,
+            Format::D32_SFLOAT_S8_UINT => ImageFormat::D32SfloatS8Uint,
+            Format::BC1_RGBA_UNORM_BLOCK => ImageFormat::Bc1RgbaUnormBlock,
+            Format::BC1_RGBA_SRGB_BLOCK => ImageFormat::Bc1RgbaSrgbBlock,
+            Format::BC2_UNORM_BLOCK => ImageFormat::Bc2UnormBlock,
+            Format::BC2_SRGB_BLOCK => ImageFormat::Bc2SrgbBlock,
+            Format::BC3_UNORM_BLOCK => ImageFormat::Bc3UnormBlock,
+            Format::BC3_SRGB_BLOCK => ImageFormat::Bc3SrgbBlock,
+            Format::BC4_UNORM_BLOCK => ImageFormat::Bc4UnormBlock,
+            Format::BC4_SNORM_BLOCK => ImageFormat::Bc4SnormBlock,
+            Format::BC5_UNORM_BLOCK => ImageFormat::Bc5UnormBlock,
+            Format::BC5_SNORM_BLOCK => ImageFormat::Bc5SnormBlock,
+            Format::BC6H_UFLOAT_BLOCK => ImageFormat::Bc6hUfloatBlock,
+            Format::BC6H_SFLOAT_BLOCK => ImageFormat::Bc6hSfloatBlock,
+            Format::BC7_UNORM_BLOCK => ImageFormat::Bc7UnormBlock,
+            Format::BC7_SRGB_BLOCK => ImageFormat::Bc7SrgbBlock,
 
             _ => ImageFormat::Undefined,
         }
@@ -75,12 +116,119 @@ impl Into<Format> for ImageFormat {
             ImageFormat::A2B10G10R10SnormPack32 => Format::A2B10G10R10_SNORM_PACK32,
             ImageFormat::A2B10G10R10SscaledPack32 => Format::A2B10G10R10_SSCALED_PACK32,
             ImageFormat::A2B10G10R10UintPack32 => Format::A2B10G10R10_UINT_PACK32,
+            ImageFormat::D16Unorm => Format::D16_UNORM,
+            ImageFormat::D32Sfloat => Format::D32_SFLOAT,
+            ImageFormat::D24UnormS8Uint => Format::D24_UNORM_S8_UINT,
+            ImageFormat::D32SfloatS8Uint => Format::D32_SFLOAT_S8_UINT,
+            ImageFormat::Bc1RgbaUnormBlock => Format::BC1_RGBA_UNORM_BLOCK,
+            ImageFormat::Bc1RgbaSrgbBlock => Format::BC1_RGBA_SRGB_BLOCK,
+            ImageFormat::Bc2UnormBlock => Format::BC2_UNORM_BLOCK,
+            ImageFormat::Bc2SrgbBlock => Format::BC2_SRGB_BLOCK,
+            ImageFormat::Bc3UnormBlock => Format::BC3_UNORM_BLOCK,
+            ImageFormat::Bc3SrgbBlock => Format::BC3_SRGB_BLOCK,
+            ImageFormat::Bc4UnormBlock => Format::BC4_UNORM_BLOCK,
+            ImageFormat::Bc4SnormBlock => Format::BC4_SNORM_BLOCK,
+            ImageFormat::Bc5UnormBlock => Format::BC5_UNORM_BLOCK,
+            ImageFormat::Bc5SnormBlock => Format::BC5_SNORM_BLOCK,
+            ImageFormat::Bc6hUfloatBlock => Format::BC6H_UFLOAT_BLOCK,
+            ImageFormat::Bc6hSfloatBlock => Format::BC6H_SFLOAT_BLOCK,
+            ImageFormat::Bc7UnormBlock => Format::BC7_UNORM_BLOCK,
+            ImageFormat::Bc7SrgbBlock => Format::BC7_SRGB_BLOCK,
 
             ImageFormat::Undefined => Format::UNDEFINED,
         }
     }
 }
 
+impl ImageFormat {
+    /// Whether the format stores integer values, which can not be averaged.
+    #[doc(hidden)]
+    pub(crate) fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::R8G8B8A8Sint
+                | ImageFormat::R8G8B8A8Uint
+                | ImageFormat::B8G8R8Sint
+                | ImageFormat::A2B10G10R10SintPack32
+                | ImageFormat::A2B10G10R10UintPack32
+        )
+    }
+
+    /// Whether the format stores signed integer values.
+    #[doc(hidden)]
+    pub(crate) fn is_signed_integer(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::R8G8B8A8Sint
+                | ImageFormat::B8G8R8Sint
+                | ImageFormat::A2B10G10R10SintPack32
+        )
+    }
+
+    /// Aspects of the image accessed by barriers and views.
+    #[doc(hidden)]
+    pub(crate) fn aspect(&self) -> ImageAspectFlags {
+        match self {
+            ImageFormat::D16Unorm | ImageFormat::D32Sfloat => ImageAspectFlags::DEPTH,
+            ImageFormat::D24UnormS8Uint | ImageFormat::D32SfloatS8Uint => {
+                ImageAspectFlags::DEPTH | ImageAspectFlags::STENCIL
+            }
+            _ => ImageAspectFlags::COLOR,
+        }
+    }
+
+    /// Size in bytes of a texel, or of a 4x4 block of texels for block-compressed formats.
+    #[doc(hidden)]
+    #[cfg_attr(not(feature = "ktx2"), allow(dead_code))]
+    pub(crate) fn texel_block_size(&self) -> u64 {
+        match self {
+            ImageFormat::Undefined => 0,
+            ImageFormat::A1R5G5B5UnormPack16 | ImageFormat::D16Unorm => 2,
+            ImageFormat::B8G8R8Sscaled
+            | ImageFormat::B8G8R8Srgb
+            | ImageFormat::B8G8R8Snorm
+            | ImageFormat::B8G8R8Sint => 3,
+            ImageFormat::D32SfloatS8Uint
+            | ImageFormat::Bc1RgbaUnormBlock
+            | ImageFormat::Bc1RgbaSrgbBlock
+            | ImageFormat::Bc4UnormBlock
+            | ImageFormat::Bc4SnormBlock => 8,
+            ImageFormat::Bc2UnormBlock
+            | ImageFormat::Bc2SrgbBlock
+            | ImageFormat::Bc3UnormBlock
+            | ImageFormat::Bc3SrgbBlock
+            | ImageFormat::Bc5UnormBlock
+            | ImageFormat::Bc5SnormBlock
+            | ImageFormat::Bc6hUfloatBlock
+            | ImageFormat::Bc6hSfloatBlock
+            | ImageFormat::Bc7UnormBlock
+            | ImageFormat::Bc7SrgbBlock => 16,
+            _ => 4,
+        }
+    }
+
+    /// Whether the format stores texels in compressed 4x4 blocks.
+    pub fn is_block_compressed(&self) -> bool {
+        matches!(
+            self,
+            ImageFormat::Bc1RgbaUnormBlock
+                | ImageFormat::Bc1RgbaSrgbBlock
+                | ImageFormat::Bc2UnormBlock
+                | ImageFormat::Bc2SrgbBlock
+                | ImageFormat::Bc3UnormBlock
+                | ImageFormat::Bc3SrgbBlock
+                | ImageFormat::Bc4UnormBlock
+                | ImageFormat::Bc4SnormBlock
+                | ImageFormat::Bc5UnormBlock
+                | ImageFormat::Bc5SnormBlock
+                | ImageFormat::Bc6hUfloatBlock
+                | ImageFormat::Bc6hSfloatBlock
+                | ImageFormat::Bc7UnormBlock
+                | ImageFormat::Bc7SrgbBlock
+        )
+    }
+}
+
 /// Represents the dimension of the image.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ImageDimension {
@@ -97,13 +245,195 @@ impl Into<ash::vk::ImageType> for ImageDimension {
     }
 }
 
+/// Indicates the layout of the image memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageLayout {
+    Undefined,
+    /// Contents written by the host are preserved.
+    /// Only valid as the initial layout of linear images.
+    Preinitialized,
+    General,
+    ColorAttachment,
+    TransferSrc,
+    TransferDst,
+    ShaderReadOnly,
+    /// Layout required to present a swapchain image.
+    PresentSrc,
+    DepthStencilAttachment,
+    DepthStencilReadOnly,
+    /// Depth aspect used as an attachment.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    DepthAttachment,
+    /// Depth aspect read in shaders or depth tests.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    DepthReadOnly,
+    /// Stencil aspect used as an attachment.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    StencilAttachment,
+    /// Stencil aspect read in shaders or stencil tests.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    StencilReadOnly,
+}
+
+impl ImageLayout {
+    /// Whether the layout requires the `separate_depth_stencil_layouts` device feature.
+    #[doc(hidden)]
+    pub(crate) fn is_separate_depth_stencil(&self) -> bool {
+        matches!(
+            self,
+            ImageLayout::DepthAttachment
+                | ImageLayout::DepthReadOnly
+                | ImageLayout::StencilAttachment
+                | ImageLayout::StencilReadOnly
+        )
+    }
+}
+
+impl From<ImageLayout> for ash::vk::ImageLayout {
+    fn from(value: ImageLayout) -> Self {
+        match value {
+            ImageLayout::Undefined => ash::vk::ImageLayout::UNDEFINED,
+            ImageLayout::Preinitialized => ash::vk::ImageLayout::PREINITIALIZED,
+            ImageLayout::General => ash::vk::ImageLayout::GENERAL,
+            ImageLayout::ColorAttachment => ash::vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ImageLayout::TransferSrc => ash::vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            ImageLayout::TransferDst => ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            ImageLayout::ShaderReadOnly => ash::vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ImageLayout::PresentSrc => ash::vk::ImageLayout::PRESENT_SRC_KHR,
+            ImageLayout::DepthStencilAttachment => {
+                ash::vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+            }
+            ImageLayout::DepthStencilReadOnly => {
+                ash::vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+            }
+            ImageLayout::DepthAttachment => ash::vk::ImageLayout::DEPTH_ATTACHMENT_OPTIMAL,
+            ImageLayout::DepthReadOnly => ash::vk::ImageLayout::DEPTH_READ_ONLY_OPTIMAL,
+            ImageLayout::StencilAttachment => ash::vk::ImageLayout::STENCIL_ATTACHMENT_OPTIMAL,
+            ImageLayout::StencilReadOnly => ash::vk::ImageLayout::STENCIL_READ_ONLY_OPTIMAL,
+        }
+    }
+}
+
+/// Number of samples per pixel of an image.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SampleCount {
+    X1,
+    X2,
+    X4,
+    X8,
+    X16,
+    X32,
+    X64,
+}
+
+impl From<SampleCount> for SampleCountFlags {
+    fn from(value: SampleCount) -> Self {
+        match value {
+            SampleCount::X1 => SampleCountFlags::TYPE_1,
+            SampleCount::X2 => SampleCountFlags::TYPE_2,
+            SampleCount::X4 => SampleCountFlags::TYPE_4,
+            SampleCount::X8 => SampleCountFlags::TYPE_8,
+            SampleCount::X16 => SampleCountFlags::TYPE_16,
+            SampleCount::X32 => SampleCountFlags::TYPE_32,
+            SampleCount::X64 => SampleCountFlags::TYPE_64,
+        }
+    }
+}
+
+impl SampleCount {
+    #[doc(hidden)]
+    pub(crate) const ALL: [SampleCount; 7] = [
+        SampleCount::X1,
+        SampleCount::X2,
+        SampleCount::X4,
+        SampleCount::X8,
+        SampleCount::X16,
+        SampleCount::X32,
+        SampleCount::X64,
+    ];
+}
+
+/// Arrangement of the texels of an image in memory.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum ImageTiling {
+    /// `Linear` for images with `ImageLayout::Preinitialized`, whose contents the host writes,
+    /// and `Optimal` for every other image.
+    #[default]
+    Auto,
+    /// Row-major texels in host-visible memory, which the host can read and write directly.
+    /// Slow to sample and render to.
+    Linear,
+    /// Texels arranged by the driver in device-local memory.
+    /// Written from the host with `Image::upload_pixels`, which copies through a staging buffer.
+    Optimal,
+}
+
+/// Indicates how the image will be used.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ImageUsage {
+    TransferSrc,
+    TransferDst,
+    Sampled,
+    ColorAttachment,
+    DepthStencilAttachment,
+    /// Read and written by shaders without a sampler, see `Resource::bind_storage_image`.
+    Storage,
+}
+
+impl From<ImageUsage> for ImageUsageFlags {
+    fn from(value: ImageUsage) -> Self {
+        match value {
+            ImageUsage::TransferSrc => ImageUsageFlags::TRANSFER_SRC,
+            ImageUsage::TransferDst => ImageUsageFlags::TRANSFER_DST,
+            ImageUsage::Sampled => ImageUsageFlags::SAMPLED,
+            ImageUsage::ColorAttachment => ImageUsageFlags::COLOR_ATTACHMENT,
+            ImageUsage::DepthStencilAttachment => ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            ImageUsage::Storage => ImageUsageFlags::STORAGE,
+        }
+    }
+}
+
+impl ImageUsage {
+    #[doc(hidden)]
+    const ALL: [ImageUsage; 6] = [
+        ImageUsage::TransferSrc,
+        ImageUsage::TransferDst,
+        ImageUsage::Sampled,
+        ImageUsage::ColorAttachment,
+        ImageUsage::DepthStencilAttachment,
+        ImageUsage::Storage,
+    ];
+
+    /// Format feature required to use an image of some format this way.
+    #[doc(hidden)]
+    fn required_format_feature(&self) -> FormatFeatureFlags {
+        match self {
+            ImageUsage::TransferSrc => FormatFeatureFlags::TRANSFER_SRC,
+            ImageUsage::TransferDst => FormatFeatureFlags::TRANSFER_DST,
+            ImageUsage::Sampled => FormatFeatureFlags::SAMPLED_IMAGE,
+            ImageUsage::ColorAttachment => FormatFeatureFlags::COLOR_ATTACHMENT,
+            ImageUsage::DepthStencilAttachment => FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+            ImageUsage::Storage => FormatFeatureFlags::STORAGE_IMAGE,
+        }
+    }
+}
+
 /// Stores information needed to create a Image.
+#[derive(Clone)]
 pub struct ImageDescriptor {
     image_type: ImageDimension,
     extent: Extent3d,
     mip_levels: u32,
     array_layers: u32,
     format: ImageFormat,
+    usage: ImageUsageFlags,
+    initial_layout: ImageLayout,
+    samples: SampleCount,
+    external_memory: Option<ExternalMemoryHandleType>,
+    dedicated_allocation: DedicatedAllocation,
+    concurrent_queues: Vec<QueueSelection>,
+    tiling: ImageTiling,
+    transient: bool,
 }
 
 impl ImageDescriptor {
@@ -116,6 +446,14 @@ impl ImageDescriptor {
             mip_levels: 1,
             array_layers: 1,
             format: ImageFormat::R8G8B8A8Unorm,
+            usage: ImageUsageFlags::COLOR_ATTACHMENT,
+            initial_layout: ImageLayout::Undefined,
+            samples: SampleCount::X1,
+            external_memory: None,
+            dedicated_allocation: DedicatedAllocation::Auto,
+            concurrent_queues: Vec::new(),
+            tiling: ImageTiling::Auto,
+            transient: false,
         }
     }
 
@@ -133,17 +471,145 @@ impl ImageDescriptor {
         self
     }
 
+    #[inline]
+    /// Specifies the number of mip levels of the Image.
+    pub fn mip_levels(mut self, mip_levels: u32) -> Self {
+        self.mip_levels = mip_levels;
+        self
+    }
+
+    #[inline]
+    /// Specifies the number of array layers of the Image.
+    pub fn array_layers(mut self, array_layers: u32) -> Self {
+        self.array_layers = array_layers;
+        self
+    }
+
     #[inline]
     /// Specifies the format of the Image.
     pub fn format(mut self, format: ImageFormat) -> Self {
         self.format = format;
         self
     }
+
+    #[inline]
+    /// Specifies the usage of the Image, replacing any usage set before.
+    pub fn usage(mut self, usage: ImageUsage) -> Self {
+        self.usage = usage.into();
+        self
+    }
+
+    #[inline]
+    /// Adds a usage to the Image.
+    pub fn add_usage(mut self, usage: ImageUsage) -> Self {
+        self.usage |= usage.into();
+        self
+    }
+
+    #[inline]
+    /// Specifies the initial layout of the Image.
+    /// Only `ImageLayout::Undefined` and `ImageLayout::Preinitialized` are allowed.
+    /// Use `Preinitialized` to write pixels from the host before the first transition.
+    pub fn initial_layout(mut self, initial_layout: ImageLayout) -> Self {
+        self.initial_layout = initial_layout;
+        self
+    }
+
+    #[inline]
+    /// Specifies the number of samples per pixel.
+    /// Must be one of `DeviceConnecter::supported_sample_counts` for the format and usage.
+    /// Multisampled images use optimal tiling and cannot have mipmaps.
+    pub fn samples(mut self, samples: SampleCount) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    #[inline]
+    /// Allocates memory that can be exported as a handle of `handle_type`.
+    /// Requires the `external_memory` device feature.
+    pub fn external_memory(mut self, handle_type: ExternalMemoryHandleType) -> Self {
+        self.external_memory = Some(handle_type);
+        self
+    }
+
+    #[inline]
+    /// Specifies whether the Image gets a dedicated allocation.
+    /// The driver decides by default.
+    pub fn dedicated_allocation(mut self, dedicated_allocation: DedicatedAllocation) -> Self {
+        self.dedicated_allocation = dedicated_allocation;
+        self
+    }
+
+    #[inline]
+    /// Lets the Image be used by `queues` at the same time without ownership transfers.
+    /// Concurrent access may be slower, so by default the Image is owned by one queue family.
+    /// Has no effect if the queues all belong to the same family.
+    pub fn concurrent(mut self, queues: &[QueueSelection]) -> Self {
+        self.concurrent_queues = queues.to_vec();
+        self
+    }
+
+    #[inline]
+    /// Specifies the tiling of the Image. Chosen from the usage by default.
+    pub fn tiling(mut self, tiling: ImageTiling) -> Self {
+        self.tiling = tiling;
+        self
+    }
+
+    #[inline]
+    /// Marks the Image as an attachment whose contents only live during a render pass,
+    /// such as a multisampled color or a depth attachment that is never stored.
+    /// Uses lazily allocated memory if the device has it, which tiled GPUs may never back
+    /// with main memory, otherwise device local memory.
+    /// The usage must only contain attachment usages, and the tiling must be optimal.
+    pub fn transient(mut self, transient: bool) -> Self {
+        self.transient = transient;
+        self
+    }
+
+    /// Resolves `ImageTiling::Auto` from the initial layout.
+    #[doc(hidden)]
+    fn resolved_tiling(&self) -> ImageTiling {
+        match self.tiling {
+            ImageTiling::Auto if self.initial_layout == ImageLayout::Preinitialized => {
+                ImageTiling::Linear
+            }
+            ImageTiling::Auto => ImageTiling::Optimal,
+            tiling => tiling,
+        }
+    }
+
+    /// Usage the Image is created with.
+    /// Automatically tiled images that are not only attachments can also be written
+    /// with `Image::upload_pixels`.
+    #[doc(hidden)]
+    fn resolved_usage(&self) -> ImageUsageFlags {
+        let attachment_usage = ImageUsageFlags::COLOR_ATTACHMENT
+            | ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+            | ImageUsageFlags::INPUT_ATTACHMENT;
+        if self.transient {
+            self.usage | ImageUsageFlags::TRANSIENT_ATTACHMENT
+        } else if self.tiling == ImageTiling::Auto
+            && self.resolved_tiling() == ImageTiling::Optimal
+            && !attachment_usage.contains(self.usage)
+        {
+            self.usage | ImageUsageFlags::TRANSFER_DST
+        } else {
+            self.usage
+        }
+    }
 }
 
 pub struct Image {
-    image: ash::vk::Image,
+    pub(crate) image: ash::vk::Image,
     memory: Option<DeviceMemory>,
+    pub(crate) format: ImageFormat,
+    pub(crate) usage: ImageUsageFlags,
+    pub(crate) samples: SampleCount,
+    state: Mutex<Option<ResourceState>>,
+    tiling: ImageTiling,
+    extent: Option<Extent3d>,
+    pub(crate) mip_levels: u32,
 
     size: Option<u64>,
 }
@@ -162,30 +628,347 @@ impl Image {
         connecter: DeviceConnecter,
         descriptor: &ImageDescriptor,
     ) -> NxResult<Self> {
-        let create_info = ImageCreateInfo::builder()
-            .image_type(descriptor.image_type.into())
-            .extent(descriptor.extent.into())
-            .mip_levels(descriptor.mip_levels)
-            .array_layers(descriptor.array_layers)
-            .format(Format::R8G8B8A8_UNORM)
-            .tiling(ImageTiling::LINEAR)
-            .initial_layout(ImageLayout::UNDEFINED)
-            .usage(ImageUsageFlags::COLOR_ATTACHMENT)
-            .sharing_mode(SharingMode::EXCLUSIVE)
-            .samples(SampleCountFlags::TYPE_1)
-            .build();
-        let image = unsafe { device.device.create_image(&create_info, None) }.unwrap();
+        let image = Self::create_raw(
+            instance,
+            device,
+            connecter,
+            descriptor,
+            ImageCreateFlags::empty(),
+        )?;
         let mem_props = connecter.get_memory_properties(instance);
-        let mem_req = unsafe { device.device.get_image_memory_requirements(image) };
-
-        let memory = DeviceMemory::alloc_image_memory(&device.device, image, mem_props, mem_req)?;
+        let non_coherent_atom_size = instance
+            .get_properties(connecter.0)
+            .limits
+            .non_coherent_atom_size;
+        let tiling = descriptor.resolved_tiling();
+        let required_flags = match tiling {
+            ImageTiling::Optimal if descriptor.transient => {
+                MemoryPropertyFlags::DEVICE_LOCAL | MemoryPropertyFlags::LAZILY_ALLOCATED
+            }
+            ImageTiling::Optimal => MemoryPropertyFlags::DEVICE_LOCAL,
+            _ => MemoryPropertyFlags::HOST_VISIBLE,
+        };
+        let memory = DeviceMemory::alloc_image_memory(
+            device,
+            image,
+            mem_props,
+            non_coherent_atom_size,
+            descriptor.external_memory,
+            descriptor.dedicated_allocation,
+            required_flags,
+        )?;
+        if descriptor.initial_layout == ImageLayout::Preinitialized
+            && !memory
+                .property_flags
+                .contains(ash::vk::MemoryPropertyFlags::HOST_VISIBLE)
+        {
+            unsafe {
+                device.device.destroy_image(image, None);
+            }
+            device.destroy(&memory);
+            return Err(NxError::InvalidDescriptor(
+                "Preinitialized layout requires host-visible memory".to_string(),
+            ));
+        }
         Ok(Self {
             image,
-            size: Some(mem_req.size),
+            size: Some(memory.allocation_size),
             memory: Some(memory),
+            format: descriptor.format,
+            usage: descriptor.resolved_usage(),
+            samples: descriptor.samples,
+            state: Mutex::new(None),
+            tiling,
+            extent: Some(descriptor.extent),
+            mip_levels: descriptor.mip_levels,
         })
     }
 
+    /// Create an Image that aliases `memory` at `offset` instead of allocating its own memory.
+    /// Several images may alias the same memory as long as only one of them is in use at a time.
+    /// The memory is not destroyed with the image.
+    /// Fails if the memory cannot hold the image, see `ResourceMemoryRequirements::is_compatible`.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `device` - Reference to the appropriate device.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `descriptor` - Appropriate ImageDescriptor.
+    /// * `memory` - Memory to bind the image to.
+    /// * `offset` - Offset in `memory`. Must satisfy the alignment required by the image.
+    pub fn create_aliased(
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        descriptor: &ImageDescriptor,
+        memory: &DeviceMemory,
+        offset: u64,
+    ) -> NxResult<Self> {
+        let (image, requirements) = Self::create_unbound(instance, device, connecter, descriptor)?;
+        Self::bind_aliased(device, image, &requirements, descriptor, memory, offset)
+    }
+
+    /// Get the memory an image created with `descriptor` needs, without allocating any.
+    /// Lets an allocator decide where to place the image, e.g. with `create_aliased`.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `device` - Reference to the appropriate device.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `descriptor` - Appropriate ImageDescriptor.
+    pub fn memory_requirements(
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        descriptor: &ImageDescriptor,
+    ) -> NxResult<ResourceMemoryRequirements> {
+        let image = Self::create_raw(
+            instance,
+            device,
+            connecter,
+            descriptor,
+            ImageCreateFlags::ALIAS,
+        )?;
+        let requirements = ResourceMemoryRequirements::of_image(device, image);
+        unsafe {
+            device.device.destroy_image(image, None);
+        }
+        Ok(requirements)
+    }
+
+    /// Creates an image with the `ALIAS` flag and no memory bound.
+    pub(crate) fn create_unbound(
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        descriptor: &ImageDescriptor,
+    ) -> NxResult<(ash::vk::Image, ResourceMemoryRequirements)> {
+        let image = Self::create_raw(
+            instance,
+            device,
+            connecter,
+            descriptor,
+            ImageCreateFlags::ALIAS,
+        )?;
+        let requirements = ResourceMemoryRequirements::of_image(device, image);
+        Ok((image, requirements))
+    }
+
+    /// Binds an image created by `create_unbound` to `memory`.
+    /// The image is destroyed if it cannot be bound.
+    pub(crate) fn bind_aliased(
+        device: &Device,
+        image: ash::vk::Image,
+        requirements: &ResourceMemoryRequirements,
+        descriptor: &ImageDescriptor,
+        memory: &DeviceMemory,
+        offset: u64,
+    ) -> NxResult<Self> {
+        let error = if !requirements.is_compatible(memory) {
+            Some("the memory type is not compatible with the image".to_string())
+        } else if requirements.requires_dedicated() {
+            Some("the image requires a dedicated allocation".to_string())
+        } else if offset % requirements.alignment() != 0 {
+            Some(format!(
+                "offset {} is not aligned to {}",
+                offset,
+                requirements.alignment()
+            ))
+        } else if offset
+            .checked_add(requirements.size())
+            .map_or(true, |end| end > memory.allocation_size)
+        {
+            Some(format!(
+                "the image needs {} bytes at offset {} but the memory has {} bytes",
+                requirements.size(),
+                offset,
+                memory.allocation_size
+            ))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            unsafe {
+                device.device.destroy_image(image, None);
+            }
+            return Err(NxError::InvalidDescriptor(error));
+        }
+        if let Err(e) = unsafe {
+            device
+                .device
+                .bind_image_memory(image, memory.memory, offset)
+        } {
+            unsafe {
+                device.device.destroy_image(image, None);
+            }
+            return Err(e.into());
+        }
+        Ok(Self {
+            image,
+            size: Some(requirements.size()),
+            memory: None,
+            format: descriptor.format,
+            usage: descriptor.resolved_usage(),
+            samples: descriptor.samples,
+            state: Mutex::new(None),
+            tiling: descriptor.resolved_tiling(),
+            extent: Some(descriptor.extent),
+            mip_levels: descriptor.mip_levels,
+        })
+    }
+
+    fn create_raw(
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        descriptor: &ImageDescriptor,
+        flags: ImageCreateFlags,
+    ) -> NxResult<ash::vk::Image> {
+        let tiling = match descriptor.resolved_tiling() {
+            ImageTiling::Optimal => ash::vk::ImageTiling::OPTIMAL,
+            _ => ash::vk::ImageTiling::LINEAR,
+        };
+        // Multisampled images are only supported with optimal tiling.
+        if descriptor.samples != SampleCount::X1 && tiling == ash::vk::ImageTiling::LINEAR {
+            return Err(NxError::InvalidDescriptor(
+                "multisampled images require optimal tiling".to_string(),
+            ));
+        }
+        Self::validate_usage(instance, connecter, descriptor, tiling)?;
+        if descriptor.transient {
+            let attachment_usage = ImageUsageFlags::COLOR_ATTACHMENT
+                | ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                | ImageUsageFlags::INPUT_ATTACHMENT;
+            if !attachment_usage.contains(descriptor.usage) || descriptor.usage.is_empty() {
+                return Err(NxError::InvalidDescriptor(
+                    "transient images can only be used as attachments".to_string(),
+                ));
+            }
+            if tiling != ash::vk::ImageTiling::OPTIMAL {
+                return Err(NxError::InvalidDescriptor(
+                    "transient images require optimal tiling".to_string(),
+                ));
+            }
+        }
+        Self::validate_samples(instance, connecter, descriptor)?;
+        match descriptor.initial_layout {
+            ImageLayout::Undefined => {}
+            ImageLayout::Preinitialized => {
+                if tiling != ash::vk::ImageTiling::LINEAR {
+                    return Err(NxError::InvalidDescriptor(
+                        "Preinitialized layout requires linear tiling".to_string(),
+                    ));
+                }
+            }
+            layout => {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "{:?} cannot be used as an initial layout",
+                    layout
+                )))
+            }
+        }
+        if descriptor.external_memory.is_some() && device.external_memory.is_none() {
+            return Err(NxError::UnsupportedFeature(
+                "external_memory is not enabled on the device".to_string(),
+            ));
+        }
+        let mut external_info = ExternalMemoryImageCreateInfo::builder().handle_types(
+            descriptor
+                .external_memory
+                .map(|x| x.into())
+                .unwrap_or_default(),
+        );
+        let mut queue_family_indices = descriptor
+            .concurrent_queues
+            .iter()
+            .map(|x| device.queue_family_index(*x) as u32)
+            .collect::<Vec<u32>>();
+        queue_family_indices.sort_unstable();
+        queue_family_indices.dedup();
+        // Concurrent sharing requires at least two distinct families.
+        let sharing_mode = if queue_family_indices.len() > 1 {
+            SharingMode::CONCURRENT
+        } else {
+            queue_family_indices.clear();
+            SharingMode::EXCLUSIVE
+        };
+        let mut create_info = ImageCreateInfo::builder()
+            .flags(flags)
+            .image_type(descriptor.image_type.into())
+            .extent(descriptor.extent.into())
+            .mip_levels(descriptor.mip_levels)
+            .array_layers(descriptor.array_layers)
+            .format(descriptor.format.into())
+            .tiling(tiling)
+            .initial_layout(descriptor.initial_layout.into())
+            .usage(descriptor.resolved_usage())
+            .sharing_mode(sharing_mode)
+            .queue_family_indices(&queue_family_indices)
+            .samples(descriptor.samples.into());
+        if descriptor.external_memory.is_some() {
+            create_info = create_info.push_next(&mut external_info);
+        }
+        let image = unsafe { device.device.create_image(&create_info, None) }?;
+        Ok(image)
+    }
+
+    /// Checks that the sample count is supported for the format and usage.
+    #[doc(hidden)]
+    fn validate_samples(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        descriptor: &ImageDescriptor,
+    ) -> NxResult<()> {
+        if descriptor.samples == SampleCount::X1 {
+            return Ok(());
+        }
+        if descriptor.image_type != ImageDimension::D2 || descriptor.mip_levels != 1 {
+            return Err(NxError::InvalidDescriptor(
+                "multisampled images must be 2D with a single mip level".to_string(),
+            ));
+        }
+        let supported = connecter.sample_count_flags(instance, descriptor.format, descriptor.usage);
+        if !supported.contains(descriptor.samples.into()) {
+            return Err(NxError::UnsupportedFeature(format!(
+                "{:?} samples are not supported for format {:?} with this usage",
+                descriptor.samples, descriptor.format
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that the format supports every requested usage with the given tiling.
+    #[doc(hidden)]
+    fn validate_usage(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        descriptor: &ImageDescriptor,
+        tiling: ash::vk::ImageTiling,
+    ) -> NxResult<()> {
+        if descriptor.format == ImageFormat::Undefined {
+            return Err(NxError::UnsupportedFeature(
+                "images cannot be created with an undefined format".to_string(),
+            ));
+        }
+        let properties = connecter.get_format_properties(instance, descriptor.format.into());
+        let features = match tiling {
+            ash::vk::ImageTiling::LINEAR => properties.linear_tiling_features,
+            _ => properties.optimal_tiling_features,
+        };
+        for usage in ImageUsage::ALL {
+            let flags: ImageUsageFlags = usage.into();
+            if descriptor.resolved_usage().contains(flags)
+                && !features.contains(usage.required_format_feature())
+            {
+                return Err(NxError::UnsupportedFeature(format!(
+                    "format {:?} does not support {:?} usage with {:?} tiling",
+                    descriptor.format, usage, tiling
+                )));
+            }
+        }
+        Ok(())
+    }
+
     #[deprecated(since = "0.1.0", note = "Please use as_raw_data()")]
     /// Maps the memory of the image
     pub fn map_memory(&self, device: &Device) -> NxResult<*mut c_void> {
@@ -207,19 +990,354 @@ impl Image {
         }
     }
 
+    /// Read `width` * `height` pixels of 4 bytes from the start of the image memory.
+    /// The image must have linear tiling and host visible memory.
     pub fn as_raw_data(&self, device: &Device, width: u32, height: u32) -> NxResult<Vec<u8>> {
-        match self.memory.as_ref() {
-            None => Err(NxError::Unknown),
-            Some(x) => {
-                let data = x.map(device, self.size.unwrap())?;
-                let slice: &[u8] = unsafe {
-                    std::slice::from_raw_parts(data as *const u8, (width * height * 4) as usize)
-                };
-                let data = slice.to_vec();
-                x.unmap(device);
-                Ok(data)
+        let memory = self.memory.as_ref().ok_or(NxError::Unknown)?;
+        if self.tiling != ImageTiling::Linear
+            || !memory
+                .property_flags
+                .contains(MemoryPropertyFlags::HOST_VISIBLE)
+        {
+            return Err(NxError::InvalidDescriptor(
+                "only linear images in host visible memory can be read".to_string(),
+            ));
+        }
+        let size = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|x| x.checked_mul(4))
+            .filter(|x| *x as u64 <= memory.allocation_size)
+            .ok_or_else(|| {
+                NxError::InvalidDescriptor(format!(
+                    "{}x{} pixels do not fit in the image memory",
+                    width, height
+                ))
+            })?;
+        let mapped = memory.map_guard(device)?;
+        mapped.invalidate(0, size as u64)?;
+        Ok(mapped.as_slice()[..size].to_vec())
+    }
+
+    /// Get the format of the image.
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Get the memory owned by the image.
+    /// Returns None if the image aliases memory it does not own.
+    pub fn memory(&self) -> Option<&DeviceMemory> {
+        self.memory.as_ref()
+    }
+
+    /// Write pixels to the image, converting them to the format of the image if they differ.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `width` - Width of the pixel data.
+    /// * `height` - Height of the pixel data.
+    /// * `format` - Layout of `data`.
+    /// * `data` - Pixels to write, row by row without padding.
+    pub fn write_pixels(
+        &self,
+        device: &Device,
+        width: u32,
+        height: u32,
+        format: PixelFormat,
+        data: &[u8],
+    ) -> NxResult<()> {
+        if self.tiling == ImageTiling::Optimal {
+            return Err(NxError::InvalidDescriptor(
+                "optimally tiled images must be written with upload_pixels".to_string(),
+            ));
+        }
+        let memory = match self.memory.as_ref() {
+            Some(x) => x,
+            None => return Err(NxError::NoValue),
+        };
+        let dst_format = match PixelFormat::from_image_format(self.format) {
+            Some(x) => x,
+            None => {
+                return Err(NxError::UnsupportedFeature(format!(
+                    "pixels cannot be written to an image of format {:?}",
+                    self.format
+                )))
+            }
+        };
+        let pixels = convert_pixels(format, dst_format, data)?;
+        let row_size = width as usize * dst_format.bytes_per_pixel();
+        if pixels.len() != row_size * height as usize {
+            return Err(NxError::InvalidDescriptor(format!(
+                "expected {}x{} pixels but got {} bytes",
+                width,
+                height,
+                data.len()
+            )));
+        }
+        if pixels.is_empty() {
+            return Ok(());
+        }
+        let layout = unsafe {
+            device.device.get_image_subresource_layout(
+                self.image,
+                ImageSubresource::builder()
+                    .aspect_mask(ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .array_layer(0)
+                    .build(),
+            )
+        };
+        let row_pitch = layout.row_pitch as usize;
+        let end = layout.offset as usize + row_pitch * (height as usize - 1) + row_size;
+        if row_pitch < row_size || end > memory.allocation_size as usize {
+            return Err(NxError::InvalidDescriptor(format!(
+                "{}x{} pixels do not fit in the image",
+                width, height
+            )));
+        }
+        let mut mapped = memory.map_guard(device)?;
+        let dst = mapped.as_mut_slice();
+        for (y, row) in pixels.chunks_exact(row_size).enumerate() {
+            let start = layout.offset as usize + y * row_pitch;
+            dst[start..start + row_size].copy_from_slice(row);
+        }
+        mapped.flush(0, mapped.len())
+    }
+
+    /// Write pixels to the whole first mip level and array layer of the image.
+    /// Linearly tiled images are written directly like `write_pixels`.
+    /// Optimally tiled images are written through a staging buffer on the graphics queue
+    /// and are left in `ResourceState::shader_read`, which becomes their tracked state.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `format` - Layout of `data`.
+    /// * `data` - Pixels to write, row by row without padding.
+    pub fn upload_pixels(
+        &self,
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        format: PixelFormat,
+        data: &[u8],
+    ) -> NxResult<()> {
+        let extent = self.extent.ok_or(NxError::NoValue)?;
+        if self.tiling != ImageTiling::Optimal {
+            return self.write_pixels(device, extent.width(), extent.height(), format, data);
+        }
+        let dst_format = match PixelFormat::from_image_format(self.format) {
+            Some(x) => x,
+            None => {
+                return Err(NxError::UnsupportedFeature(format!(
+                    "pixels cannot be written to an image of format {:?}",
+                    self.format
+                )))
+            }
+        };
+        let pixels = convert_pixels(format, dst_format, data)?;
+        let expected = extent.width() as usize
+            * extent.height() as usize
+            * extent.depth() as usize
+            * dst_format.bytes_per_pixel();
+        if pixels.len() != expected {
+            return Err(NxError::InvalidDescriptor(format!(
+                "expected {}x{}x{} pixels but got {} bytes",
+                extent.width(),
+                extent.height(),
+                extent.depth(),
+                data.len()
+            )));
+        }
+        if pixels.is_empty() {
+            return Ok(());
+        }
+        let region = BufferImageCopy::builder()
+            .image_subresource(
+                ImageSubresourceLayers::builder()
+                    .aspect_mask(self.format.aspect())
+                    .mip_level(0)
+                    .base_array_layer(0)
+                    .layer_count(1)
+                    .build(),
+            )
+            .image_extent(extent.into())
+            .build();
+        self.upload_regions(instance, connecter, device, &pixels, &[region])
+    }
+
+    /// Copies `data` to the image through a staging buffer on the graphics queue,
+    /// then moves the whole image to `ResourceState::shader_read` and tracks that state.
+    #[doc(hidden)]
+    pub(crate) fn upload_regions(
+        &self,
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        data: &[u8],
+        regions: &[BufferImageCopy],
+    ) -> NxResult<()> {
+        let staging = Buffer::new(
+            instance,
+            connecter,
+            device,
+            &BufferDescriptor::empty()
+                .size(data.len())
+                .usage(BufferUsage::TransferSrc),
+        )?;
+        let result = staging
+            .map(device)
+            .and_then(|mut mapped| {
+                mapped.as_mut_slice()[..data.len()].copy_from_slice(data);
+                mapped.flush(0, mapped.len())
+            })
+            .and_then(|_| {
+                let old = self.state().unwrap_or(ResourceState::UNDEFINED);
+                let new = ResourceState::shader_read();
+                device.immediate_submit(QueueSelection::Graphics, |recorder| {
+                    let range = SubresourceRange::all().to_vk(self.format.aspect());
+                    recorder.image_barrier(
+                        device,
+                        self.image,
+                        range,
+                        &old,
+                        &ResourceState::transfer_dst(),
+                    );
+                    unsafe {
+                        device.device.cmd_copy_buffer_to_image(
+                            recorder.buffer,
+                            staging.buffer,
+                            self.image,
+                            ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                            regions,
+                        );
+                    }
+                    recorder.image_barrier(
+                        device,
+                        self.image,
+                        range,
+                        &ResourceState::transfer_dst(),
+                        &new,
+                    );
+                    Ok(())
+                })?;
+                self.track_state(new);
+                Ok(())
+            });
+        device.destroy(&staging);
+        result
+    }
+
+    /// Starts tracking the state of the image so that `transition_to` can be used.
+    /// Tracking is optional; images that are not tracked are not affected.
+    pub fn track_state(&self, state: ResourceState) {
+        *self.state.lock().unwrap() = Some(state);
+    }
+
+    /// Get the last known state of the image, if it is tracked.
+    pub fn state(&self) -> Option<ResourceState> {
+        *self.state.lock().unwrap()
+    }
+
+    /// Records the barrier needed to use the image in `state` and remembers the new state.
+    /// No barrier is recorded when both the old and new uses only read the image
+    /// in the same layout.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `recorder` - CommandRecorder to record the barrier to, outside a render pass.
+    /// * `state` - How the image will be used next.
+    pub fn transition_to(
+        &self,
+        device: &Device,
+        recorder: &CommandRecorder,
+        state: ResourceState,
+    ) -> NxResult<()> {
+        let mut batch = BarrierBatch::new();
+        self.batch_transition(device, &mut batch, state)?;
+        batch.flush(device, recorder);
+        Ok(())
+    }
+
+    /// Like `transition_to`, but adds the barrier to `batch` instead of recording it.
+    /// The tracked state is updated immediately,
+    /// so the batch must be flushed before the image is used.
+    pub fn batch_transition(
+        &self,
+        device: &Device,
+        batch: &mut BarrierBatch,
+        state: ResourceState,
+    ) -> NxResult<()> {
+        if state.uses_separate_depth_stencil() && !device.features.separate_depth_stencil_layouts {
+            return Err(NxError::UnsupportedFeature(
+                "separateDepthStencilLayouts is not enabled on the device".to_string(),
+            ));
+        }
+        let mut current = self.state.lock().unwrap();
+        let old = match *current {
+            Some(x) => x,
+            None => {
+                return Err(NxError::InvalidDescriptor(
+                    "the state of the image is not tracked".to_string(),
+                ))
             }
+        };
+        if old.layout == state.layout
+            && old.get_stencil_layout() == state.get_stencil_layout()
+            && old.is_read_only()
+            && state.is_read_only()
+        {
+            *current = Some(ResourceState {
+                layout: state.layout,
+                stencil_layout: state.stencil_layout,
+                stages: old.stages | state.stages,
+                access: old.access | state.access,
+            });
+            return Ok(());
+        }
+        let range = SubresourceRange::all().to_vk(self.format.aspect());
+        let ignored = ash::vk::QUEUE_FAMILY_IGNORED;
+        batch.push_image(self.image, range, &old, &state, (ignored, ignored));
+        *current = Some(state);
+        Ok(())
+    }
+
+    /// Records a single barrier moving `range` of the image from `old` to `new`,
+    /// for example to transition a whole mip chain or texture array at once.
+    /// If `range` covers the whole image, the tracked state becomes `new`. Otherwise the image
+    /// is no longer tracked; call `track_state` once all of it is in the same state again.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `recorder` - CommandRecorder to record the barrier to, outside a render pass.
+    /// * `range` - Mip levels and array layers to transition.
+    /// * `old` - How the range was last used.
+    /// * `new` - How the range will be used next.
+    pub fn transition_range(
+        &self,
+        device: &Device,
+        recorder: &CommandRecorder,
+        range: SubresourceRange,
+        old: ResourceState,
+        new: ResourceState,
+    ) -> NxResult<()> {
+        if (old.uses_separate_depth_stencil() || new.uses_separate_depth_stencil())
+            && !device.features.separate_depth_stencil_layouts
+        {
+            return Err(NxError::UnsupportedFeature(
+                "separateDepthStencilLayouts is not enabled on the device".to_string(),
+            ));
         }
+        let mut current = self.state.lock().unwrap();
+        recorder.image_barrier(
+            device,
+            self.image,
+            range.to_vk(self.format.aspect()),
+            &old,
+            &new,
+        );
+        *current = if range.is_all() { Some(new) } else { None };
+        Ok(())
     }
 
     /// Create a image view from self.
@@ -232,17 +1350,55 @@ impl Image {
     }
 
     #[doc(hidden)]
-    pub(crate) fn from_raw(image: ash::vk::Image) -> Self {
+    #[cfg(feature = "window")]
+    pub(crate) fn from_raw(image: ash::vk::Image, format: ImageFormat) -> Self {
         Self {
             image,
             memory: None,
             size: None,
+            format,
+            usage: ImageUsageFlags::COLOR_ATTACHMENT,
+            samples: SampleCount::X1,
+            state: Mutex::new(None),
+            tiling: ImageTiling::Optimal,
+            extent: None,
+            mip_levels: 1,
+        }
+    }
+}
+
+/// Source of a component of the texels read through an ImageView.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Swizzle {
+    /// The component itself.
+    Identity,
+    R,
+    G,
+    B,
+    A,
+    Zero,
+    One,
+}
+
+impl From<Swizzle> for ComponentSwizzle {
+    fn from(value: Swizzle) -> Self {
+        match value {
+            Swizzle::Identity => ComponentSwizzle::IDENTITY,
+            Swizzle::R => ComponentSwizzle::R,
+            Swizzle::G => ComponentSwizzle::G,
+            Swizzle::B => ComponentSwizzle::B,
+            Swizzle::A => ComponentSwizzle::A,
+            Swizzle::Zero => ComponentSwizzle::ZERO,
+            Swizzle::One => ComponentSwizzle::ONE,
         }
     }
 }
 
 pub struct ImageViewDescriptor {
     format: ImageFormat,
+    base_array_layer: u32,
+    layer_count: u32,
+    swizzle: [Swizzle; 4],
 }
 
 impl ImageViewDescriptor {
@@ -250,6 +1406,9 @@ impl ImageViewDescriptor {
     pub const fn empty() -> Self {
         Self {
             format: ImageFormat::R8G8B8A8Unorm,
+            base_array_layer: 0,
+            layer_count: 1,
+            swizzle: [Swizzle::Identity; 4],
         }
     }
 
@@ -258,40 +1417,94 @@ impl ImageViewDescriptor {
         self.format = format;
         self
     }
+
+    /// Specifies the array layers seen through the view.
+    /// A view of more than one layer is a 2D array view, as used by multiview render passes.
+    #[inline]
+    pub const fn array_layers(mut self, base: u32, count: u32) -> Self {
+        self.base_array_layer = base;
+        self.layer_count = count;
+        self
+    }
+
+    /// Specifies where the r, g, b and a components read through the view come from.
+    /// For example `(R, R, R, One)` broadcasts a single channel image to grayscale RGBA,
+    /// and `(B, G, R, A)` reads a BGRA image as RGBA.
+    #[inline]
+    pub const fn swizzle(mut self, r: Swizzle, g: Swizzle, b: Swizzle, a: Swizzle) -> Self {
+        self.swizzle = [r, g, b, a];
+        self
+    }
 }
 
 pub struct ImageView {
     pub(crate) image_view: ash::vk::ImageView,
+    pub(crate) layer_count: u32,
+    /// Usage of the image the view was created from.
+    pub(crate) usage: ImageUsageFlags,
+    pub(crate) format: ImageFormat,
+    /// Samples per pixel of the image the view was created from.
+    pub(crate) samples: SampleCount,
 }
 
 impl ImageView {
     #[inline]
     #[doc(hidden)]
     pub(crate) fn new(device: &Device, image: &Image, descriptor: &ImageViewDescriptor) -> Self {
+        Self::create(&device.device, image, descriptor).unwrap()
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn create(
+        device: &ash::Device,
+        image: &Image,
+        descriptor: &ImageViewDescriptor,
+    ) -> NxResult<Self> {
+        let view_type = if descriptor.layer_count > 1 {
+            ImageViewType::TYPE_2D_ARRAY
+        } else {
+            ImageViewType::TYPE_2D
+        };
         let create_info = ImageViewCreateInfo::builder()
             .image(image.image)
-            .view_type(ImageViewType::TYPE_2D)
+            .view_type(view_type)
             .format(descriptor.format.into())
             .components(
                 ComponentMapping::builder()
-                    .r(ComponentSwizzle::IDENTITY)
-                    .g(ComponentSwizzle::IDENTITY)
-                    .b(ComponentSwizzle::IDENTITY)
-                    .a(ComponentSwizzle::IDENTITY)
+                    .r(descriptor.swizzle[0].into())
+                    .g(descriptor.swizzle[1].into())
+                    .b(descriptor.swizzle[2].into())
+                    .a(descriptor.swizzle[3].into())
                     .build(),
             )
             .subresource_range(
                 ImageSubresourceRange::builder()
-                    .aspect_mask(ImageAspectFlags::COLOR)
+                    .aspect_mask(descriptor.format.aspect())
                     .base_mip_level(0)
-                    .level_count(1)
-                    .base_array_layer(0)
-                    .layer_count(1)
+                    .level_count(image.mip_levels)
+                    .base_array_layer(descriptor.base_array_layer)
+                    .layer_count(descriptor.layer_count)
                     .build(),
             )
             .build();
-        let image_view = unsafe { device.device.create_image_view(&create_info, None) }.unwrap();
-        Self { image_view }
+        let image_view = unsafe { device.create_image_view(&create_info, None) }?;
+        Ok(Self {
+            image_view,
+            layer_count: descriptor.layer_count,
+            usage: image.usage,
+            format: descriptor.format,
+            samples: image.samples,
+        })
+    }
+
+    /// Get the format the view was created with.
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// Get the number of samples per pixel of the image.
+    pub fn samples(&self) -> SampleCount {
+        self.samples
     }
 }
 
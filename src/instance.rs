@@ -1,17 +1,26 @@
+use crate::debug::DebugLoader;
+use crate::device::QueueFamilyIndices;
 use crate::{NxError, NxResult};
-use ash::extensions::ext::DebugUtils;
+#[allow(deprecated)]
+use ash::extensions::ext::{DebugMarker, DebugUtils};
 use ash::vk::{
     self, DebugUtilsMessengerEXT, DeviceCreateInfo, PhysicalDevice, PhysicalDeviceMemoryProperties,
+    PhysicalDeviceProperties, PhysicalDeviceType,
 };
 use ash::{vk::InstanceCreateInfo, Entry};
-use std::ffi::c_char;
+use std::ffi::{c_char, CStr, CString};
 
-use crate::{vulkan_debug_callback, Device, DeviceConnecter, DeviceFeature};
+#[cfg(feature = "window")]
+use crate::Surface;
+use crate::{
+    vulkan_debug_callback, Device, DeviceConfig, DeviceConnecter, DeviceFeature, DeviceFeatures,
+    InstanceConfig,
+};
 
 /// Represents an additional feature of the instance.
 pub struct InstanceFeature {
     #[doc(hidden)]
-    extensions: Vec<*const c_char>,
+    extensions: Vec<CString>,
     #[doc(hidden)]
     device_exts: Vec<DeviceFeature>,
 }
@@ -41,11 +50,47 @@ impl InstanceFeature {
             Err(e) => return Err(NxError::InternalError(e)),
         };
         for i in ext {
-            self.extensions.push(*i);
+            self.extensions
+                .push(unsafe { CStr::from_ptr(*i) }.to_owned());
         }
         self.device_exts.push(DeviceFeature::Swapchain);
         Ok(())
     }
+
+    /// Allows swapchains to use color spaces other than sRGB, such as HDR10.
+    /// Enables the `VK_EXT_swapchain_colorspace` extension, which must be supported.
+    /// **"window" feature is required.**
+    #[cfg(feature = "window")]
+    #[inline]
+    pub fn use_swapchain_colorspace(&mut self) {
+        self.extensions
+            .push(vk::ExtSwapchainColorspaceFn::name().to_owned());
+    }
+
+    /// Enables the extensions of `config`, to create an instance like the one it was obtained from.
+    /// `VK_EXT_debug_utils` is enabled whenever it is available, regardless of `config`.
+    pub fn from_config(config: &InstanceConfig) -> NxResult<Self> {
+        if !config.layers.is_empty() {
+            return Err(NxError::UnsupportedFeature(
+                "instances cannot be created with layers".to_string(),
+            ));
+        }
+        let mut feature = Self::empty();
+        for name in &config.extensions {
+            let name = CString::new(name.as_str()).map_err(|_| {
+                NxError::InvalidDescriptor(format!("{:?} is not a valid extension name", name))
+            })?;
+            if name.as_c_str() == DebugUtils::name() {
+                continue;
+            }
+            // Surfaces are presented to with swapchains.
+            if name.as_c_str() == ash::extensions::khr::Surface::name() {
+                feature.device_exts.push(DeviceFeature::Swapchain);
+            }
+            feature.extensions.push(name);
+        }
+        Ok(feature)
+    }
 }
 
 impl Default for InstanceFeature {
@@ -57,6 +102,7 @@ impl Default for InstanceFeature {
 /// Object that allows building windows.
 pub struct InstanceBuilder {
     feature: InstanceFeature,
+    api_version: u32,
 }
 
 impl InstanceBuilder {
@@ -64,6 +110,7 @@ impl InstanceBuilder {
     pub fn new() -> Self {
         Self {
             feature: Default::default(),
+            api_version: vk::API_VERSION_1_0,
         }
     }
 
@@ -73,43 +120,83 @@ impl InstanceBuilder {
         self
     }
 
+    /// Specifies the highest Vulkan version the application uses,
+    /// encoded like `VK_MAKE_API_VERSION` as returned by `InstanceConfig::api_version`.
+    /// Capabilities of later versions are reported as unsupported even if a device has them.
+    /// Defaults to Vulkan 1.0.
+    pub fn api_version(mut self, api_version: u32) -> Self {
+        self.api_version = api_version;
+        self
+    }
+
     /// Create an instance.
-    /// This will fail if there is insufficient memory or if the device does not support **Vulkan 1.3** or **later**.
+    /// This will fail if there is insufficient memory or if the requested version is not available.
     pub fn build(mut self) -> NxResult<Instance> {
-        self.feature
-            .extensions
-            .push(DebugUtils::name().as_ptr() as *const c_char);
         let entry = Entry::linked();
+        // Debug utils are optional; without them, debug names fall back to debug markers.
+        let has_debug_utils = entry
+            .enumerate_instance_extension_properties(None)
+            .unwrap_or_default()
+            .iter()
+            .any(|x| unsafe { CStr::from_ptr(x.extension_name.as_ptr()) } == DebugUtils::name());
+        if has_debug_utils {
+            self.feature.extensions.push(DebugUtils::name().to_owned());
+        }
+        let extensions = self
+            .feature
+            .extensions
+            .iter()
+            .map(|x| x.as_ptr())
+            .collect::<Vec<*const c_char>>();
+        let app_info = vk::ApplicationInfo::builder()
+            .api_version(self.api_version)
+            .build();
         let create_info = InstanceCreateInfo::builder()
-            .enabled_extension_names(&self.feature.extensions)
+            .application_info(&app_info)
+            .enabled_extension_names(&extensions)
             .build();
         let instance = match unsafe { entry.create_instance(&create_info, None) } {
             Ok(x) => x,
             Err(e) => return Err(NxError::InternalError(e)),
         };
-        let mut debug_info = vk::DebugUtilsMessengerCreateInfoEXT::default();
-
-        debug_info.message_severity = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
-            | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
-            | vk::DebugUtilsMessageSeverityFlagsEXT::INFO;
-        debug_info.message_type = vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
-            | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
-            | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE;
-
-        debug_info.pfn_user_callback = Some(vulkan_debug_callback);
-
-        let debug_utils = DebugUtils::new(&entry, &instance);
-        let debug_call_back =
-            match unsafe { debug_utils.create_debug_utils_messenger(&debug_info, None) } {
-                Ok(x) => x,
-                Err(e) => return Err(NxError::InternalError(e)),
-            };
+        let debug_utils = if has_debug_utils {
+            let mut debug_info = vk::DebugUtilsMessengerCreateInfoEXT::default();
+
+            debug_info.message_severity = vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+                | vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::INFO;
+            debug_info.message_type = vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE;
+
+            debug_info.pfn_user_callback = Some(vulkan_debug_callback);
+
+            let debug_utils = DebugUtils::new(&entry, &instance);
+            let debug_call_back =
+                match unsafe { debug_utils.create_debug_utils_messenger(&debug_info, None) } {
+                    Ok(x) => x,
+                    Err(e) => return Err(NxError::InternalError(e)),
+                };
+            Some((debug_utils, debug_call_back))
+        } else {
+            None
+        };
+        let config = InstanceConfig {
+            api_version: self.api_version,
+            extensions: self
+                .feature
+                .extensions
+                .iter()
+                .map(|x| x.to_string_lossy().into_owned())
+                .collect(),
+            layers: vec![],
+        };
         Ok(Instance {
             instance,
             entry,
             device_exts: self.feature.device_exts,
             debug_utils,
-            debug_call_back,
+            config,
         })
     }
 }
@@ -120,13 +207,184 @@ impl Default for InstanceBuilder {
     }
 }
 
+/// Indicates the type of a physical device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DeviceType {
+    Other,
+    IntegratedGpu,
+    DiscreteGpu,
+    VirtualGpu,
+    Cpu,
+}
+
+impl From<PhysicalDeviceType> for DeviceType {
+    fn from(value: PhysicalDeviceType) -> Self {
+        match value {
+            PhysicalDeviceType::INTEGRATED_GPU => DeviceType::IntegratedGpu,
+            PhysicalDeviceType::DISCRETE_GPU => DeviceType::DiscreteGpu,
+            PhysicalDeviceType::VIRTUAL_GPU => DeviceType::VirtualGpu,
+            PhysicalDeviceType::CPU => DeviceType::Cpu,
+            _ => DeviceType::Other,
+        }
+    }
+}
+
+/// Properties of a physical device, useful for bug reports and driver-specific workarounds.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DeviceProperties {
+    name: String,
+    device_type: DeviceType,
+    vendor_id: u32,
+    device_id: u32,
+    api_version: u32,
+    driver_version: u32,
+    driver_name: Option<String>,
+    driver_info: Option<String>,
+}
+
+impl DeviceProperties {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn device_type(&self) -> DeviceType {
+        self.device_type
+    }
+
+    /// PCI vendor ID, such as 0x10DE for NVIDIA.
+    pub fn vendor_id(&self) -> u32 {
+        self.vendor_id
+    }
+
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// Vulkan version supported by the device, such as "1.3.250".
+    pub fn api_version(&self) -> String {
+        format!(
+            "{}.{}.{}",
+            vk::api_version_major(self.api_version),
+            vk::api_version_minor(self.api_version),
+            vk::api_version_patch(self.api_version)
+        )
+    }
+
+    /// Raw driver version, encoded in a vendor-specific way.
+    pub fn raw_driver_version(&self) -> u32 {
+        self.driver_version
+    }
+
+    /// Driver version decoded according to the vendor's encoding.
+    pub fn driver_version(&self) -> String {
+        let v = self.driver_version;
+        match self.vendor_id {
+            // NVIDIA uses 10.8.8.6 bits.
+            0x10DE => format!(
+                "{}.{}.{}.{}",
+                v >> 22,
+                (v >> 14) & 0xff,
+                (v >> 6) & 0xff,
+                v & 0x3f
+            ),
+            // Intel on Windows uses 18.14 bits.
+            0x8086 if cfg!(windows) => format!("{}.{}", v >> 14, v & 0x3fff),
+            _ => format!(
+                "{}.{}.{}",
+                vk::api_version_major(v),
+                vk::api_version_minor(v),
+                vk::api_version_patch(v)
+            ),
+        }
+    }
+
+    /// Name of the driver. Requires Vulkan 1.2 or `VK_KHR_driver_properties`.
+    pub fn driver_name(&self) -> Option<&str> {
+        self.driver_name.as_deref()
+    }
+
+    /// Additional information about the driver, usually its version string.
+    /// Requires Vulkan 1.2 or `VK_KHR_driver_properties`.
+    pub fn driver_info(&self) -> Option<&str> {
+        self.driver_info.as_deref()
+    }
+}
+
+/// Indicates which kind of physical device should be selected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DevicePreference {
+    /// Any device is accepted, in the order reported by the driver.
+    Any,
+    /// Only discrete GPUs are accepted.
+    DiscreteOnly,
+    /// Only integrated GPUs are accepted.
+    IntegratedOnly,
+    /// Any device is accepted, but integrated GPUs are preferred.
+    LowPower,
+    /// Any device is accepted, but discrete GPUs are preferred.
+    HighPerformance,
+}
+
+impl DevicePreference {
+    #[doc(hidden)]
+    fn accepts(&self, device_type: DeviceType) -> bool {
+        match self {
+            DevicePreference::DiscreteOnly => device_type == DeviceType::DiscreteGpu,
+            DevicePreference::IntegratedOnly => device_type == DeviceType::IntegratedGpu,
+            _ => true,
+        }
+    }
+
+    /// Lower is better.
+    #[doc(hidden)]
+    fn rank(&self, device_type: DeviceType) -> u32 {
+        let order: &[DeviceType] = match self {
+            DevicePreference::LowPower => &[
+                DeviceType::IntegratedGpu,
+                DeviceType::DiscreteGpu,
+                DeviceType::VirtualGpu,
+            ],
+            DevicePreference::HighPerformance => &[
+                DeviceType::DiscreteGpu,
+                DeviceType::IntegratedGpu,
+                DeviceType::VirtualGpu,
+            ],
+            _ => &[],
+        };
+        order
+            .iter()
+            .position(|x| *x == device_type)
+            .unwrap_or(order.len()) as u32
+    }
+}
+
 pub struct RequestConnecterDescriptor {
     is_graphic_support: bool,
     is_compute_support: bool,
     is_transfer_support: bool,
+    preference: DevicePreference,
+    #[cfg(feature = "window")]
+    surfaces: Vec<vk::SurfaceKHR>,
 }
 
 impl RequestConnecterDescriptor {
+    /// Specifies which kind of device should be selected.
+    /// Devices are filtered or ordered according to their type.
+    pub fn preference(mut self, preference: DevicePreference) -> Self {
+        self.preference = preference;
+        self
+    }
+
+    /// Only devices with a queue family that can present to `surface` are selected.
+    /// When called for several surfaces, the same queue family must present to all of them.
+    /// Create the device with `DeviceConnecter::create_device_for_surface`.
+    /// **"window" feature is required.**
+    #[cfg(feature = "window")]
+    pub fn present_support(mut self, surface: &Surface) -> Self {
+        self.surfaces.push(surface.khr);
+        self
+    }
+
     pub fn graphic_support(mut self, is_graphic_support: bool) -> Self {
         self.is_graphic_support = is_graphic_support;
         self
@@ -149,6 +407,9 @@ impl RequestConnecterDescriptor {
             is_graphic_support: true,
             is_compute_support: true,
             is_transfer_support: true,
+            preference: DevicePreference::Any,
+            #[cfg(feature = "window")]
+            surfaces: vec![],
         }
     }
 }
@@ -165,8 +426,8 @@ pub struct Instance {
 
     pub(crate) device_exts: Vec<DeviceFeature>,
 
-    debug_utils: DebugUtils,
-    debug_call_back: DebugUtilsMessengerEXT,
+    debug_utils: Option<(DebugUtils, DebugUtilsMessengerEXT)>,
+    config: InstanceConfig,
 }
 
 impl Instance {
@@ -216,47 +477,70 @@ impl Instance {
         }
     }
 
+    /// Request connecters that satisfy the descriptors.
+    /// One connecter is returned for each descriptor.
+    /// If no device satisfies a descriptor, `NxError::NoValue` is returned.
     pub fn request_connecters(
         &self,
         descriptors: &[RequestConnecterDescriptor],
     ) -> NxResult<Vec<DeviceConnecter>> {
         let mut connecter = vec![];
         for desc in descriptors {
-            let mut connecters = self.enumerate_connecters()?;
-            let mut index = 0;
-            let mut count = 0;
-            for i in &connecters {
-                let properties = i.get_queue_family_properties(self).unwrap();
-                for (n, i) in properties.iter().enumerate() {
-                    count = 0;
-                    if i.is_graphic_support() == desc.is_graphic_support {
-                        count += 1;
-                    }
-                    if i.is_compute_support() == desc.is_compute_support {
-                        count += 1;
-                    }
-                    if i.is_transfer_support() == desc.is_transfer_support {
-                        count += 1;
-                    }
-
-                    if count == 3 {
-                        index = n;
-                        break;
-                    }
+            let mut candidates = vec![];
+            for c in self.enumerate_connecters()? {
+                let device_type = c.device_type(self);
+                if !desc.preference.accepts(device_type) {
+                    continue;
+                }
+                let properties = c.get_queue_family_properties(self)?;
+                #[cfg(feature = "window")]
+                if !self.is_present_support(c, &desc.surfaces, properties.len()) {
+                    continue;
+                }
+                let index = properties.iter().position(|i| {
+                    i.is_graphic_support() == desc.is_graphic_support
+                        && i.is_compute_support() == desc.is_compute_support
+                        && i.is_transfer_support() == desc.is_transfer_support
+                });
+                if let Some(index) = index {
+                    candidates.push((
+                        DeviceConnecter(c.0, index),
+                        desc.preference.rank(device_type),
+                    ));
                 }
             }
-            if count != 3 {
-                return Err(NxError::NoValue);
+            // Stable sort keeps the driver's order among devices of the same rank.
+            candidates.sort_by_key(|x| x.1);
+            match candidates.first() {
+                None => return Err(NxError::NoValue),
+                Some(x) => connecter.push(x.0),
             }
-
-            let mut c = connecters[index];
-            c.1 = index;
-            connecter.push(c);
         }
 
         Ok(connecter)
     }
 
+    /// Whether any of the `count` queue families of the device can present to every surface.
+    #[cfg(feature = "window")]
+    #[doc(hidden)]
+    fn is_present_support(
+        &self,
+        c: DeviceConnecter,
+        surfaces: &[vk::SurfaceKHR],
+        count: usize,
+    ) -> bool {
+        if surfaces.is_empty() {
+            return true;
+        }
+        let loader = ash::extensions::khr::Surface::new(&self.entry, &self.instance);
+        (0..count as u32).any(|i| {
+            surfaces.iter().all(|surface| {
+                unsafe { loader.get_physical_device_surface_support(c.0, i, *surface) }
+                    .unwrap_or(false)
+            })
+        })
+    }
+
     /// Get the first connector.
     #[deprecated(
         since = "0.1.0",
@@ -289,12 +573,54 @@ impl Instance {
         &self,
         connecter: DeviceConnecter,
         info: &DeviceCreateInfo,
+        features: DeviceFeatures,
+        queue_families: QueueFamilyIndices,
+        debug_marker: bool,
+        extensions: Vec<String>,
     ) -> NxResult<Device> {
         let device = match unsafe { self.instance.create_device(connecter.0, info, None) } {
             Ok(x) => x,
             Err(e) => return Err(NxError::InternalError(e)),
         };
-        Ok(Device::from(device))
+        #[allow(deprecated)]
+        let debug = match &self.debug_utils {
+            Some((debug_utils, _)) => DebugLoader::Utils(debug_utils.clone()),
+            None if debug_marker => DebugLoader::Marker(DebugMarker::new(&self.instance, &device)),
+            None => DebugLoader::None,
+        };
+        let properties = self.get_device_properties(connecter);
+        let config = DeviceConfig {
+            device_name: properties.name,
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            api_version: properties.api_version,
+            extensions,
+            features,
+            graphics_queue_family: queue_families.graphics,
+            transfer_queue_family: queue_families.transfer,
+            compute_queue_family: queue_families.compute,
+            present_queue_family: queue_families.present,
+        };
+        Ok(Device::from(
+            &self.instance,
+            connecter.0,
+            device,
+            self.device_api_version(connecter.0),
+            queue_families,
+            debug,
+            config,
+        ))
+    }
+
+    /// Get the extensions and layers the instance was created with.
+    pub fn config(&self) -> InstanceConfig {
+        self.config.clone()
+    }
+
+    #[doc(hidden)]
+    /// Whether `VK_EXT_debug_utils` is enabled on the instance.
+    pub(crate) fn has_debug_utils(&self) -> bool {
+        self.debug_utils.is_some()
     }
 
     #[doc(hidden)]
@@ -317,6 +643,61 @@ impl Instance {
         }
     }
 
+    #[doc(hidden)]
+    pub(crate) fn get_properties(
+        &self,
+        physical_device: PhysicalDevice,
+    ) -> PhysicalDeviceProperties {
+        unsafe {
+            self.instance
+                .get_physical_device_properties(physical_device)
+        }
+    }
+
+    /// Vulkan version usable with the device, the lower of the instance and device versions.
+    #[doc(hidden)]
+    pub(crate) fn device_api_version(&self, physical_device: PhysicalDevice) -> u32 {
+        self.get_properties(physical_device)
+            .api_version
+            .min(self.config.api_version)
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn get_device_properties(&self, connecter: DeviceConnecter) -> DeviceProperties {
+        let properties = self.get_properties(connecter.0);
+        // vkGetPhysicalDeviceProperties2 is core since Vulkan 1.1.
+        let api_version = self.device_api_version(connecter.0);
+        let has_driver_properties = api_version >= vk::API_VERSION_1_2
+            || (api_version >= vk::API_VERSION_1_1
+                && connecter.is_extension_supported(self, vk::KhrDriverPropertiesFn::name()));
+        let (driver_name, driver_info) = if has_driver_properties {
+            let mut driver = vk::PhysicalDeviceDriverProperties::default();
+            let mut properties2 = vk::PhysicalDeviceProperties2::builder()
+                .push_next(&mut driver)
+                .build();
+            unsafe {
+                self.instance
+                    .get_physical_device_properties2(connecter.0, &mut properties2)
+            };
+            (
+                Some(c_str_to_string(&driver.driver_name)),
+                Some(c_str_to_string(&driver.driver_info)),
+            )
+        } else {
+            (None, None)
+        };
+        DeviceProperties {
+            name: c_str_to_string(&properties.device_name),
+            device_type: properties.device_type.into(),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            api_version: properties.api_version,
+            driver_version: properties.driver_version,
+            driver_name,
+            driver_info,
+        }
+    }
+
     #[doc(hidden)]
     pub(crate) fn get_memory_properties(
         &self,
@@ -331,6 +712,15 @@ impl Instance {
 
 impl Drop for Instance {
     fn drop(&mut self) {
+        if let Some((debug_utils, debug_call_back)) = &self.debug_utils {
+            unsafe { debug_utils.destroy_debug_utils_messenger(*debug_call_back, None) }
+        }
         unsafe { self.instance.destroy_instance(None) }
     }
 }
+
+fn c_str_to_string(s: &[c_char]) -> String {
+    unsafe { CStr::from_ptr(s.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,261 @@
+use crate::{
+    Device, DeviceConnecter, Extent3d, Image, ImageDescriptor, ImageDimension, ImageFormat,
+    ImageTiling, ImageUsage, Instance, NxError, NxResult,
+};
+use ash::vk::{BufferImageCopy, Format, ImageSubresourceLayers};
+
+const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+/// Identifier, 9 header fields and the index of the data format, key/value and
+/// supercompression global data.
+const HEADER_SIZE: usize = 12 + 9 * 4 + 4 * 4 + 2 * 8;
+const LEVEL_INDEX_ENTRY_SIZE: usize = 3 * 8;
+
+/// Header fields of a KTX2 file needed to create the image.
+struct Header {
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    depth: u32,
+    layer_count: u32,
+    level_count: u32,
+}
+
+/// Location of a mip level in the file.
+struct Level {
+    offset: usize,
+    length: usize,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u64(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+fn error(message: impl Into<String>) -> NxError {
+    NxError::Ktx2Error(message.into())
+}
+
+fn parse(data: &[u8]) -> NxResult<(Header, Vec<Level>)> {
+    if data.len() < HEADER_SIZE || data[..12] != IDENTIFIER {
+        return Err(error("not a KTX2 file"));
+    }
+    let vk_format = read_u32(data, 12);
+    let face_count = read_u32(data, 36);
+    let supercompression = read_u32(data, 44);
+    if vk_format == 0 {
+        return Err(error("Basis Universal textures are not supported"));
+    }
+    if supercompression != 0 {
+        return Err(error(format!(
+            "supercompression scheme {} is not supported",
+            supercompression
+        )));
+    }
+    if face_count != 1 {
+        return Err(error("cube maps are not supported"));
+    }
+    let format: ImageFormat = Format::from_raw(vk_format as i32).into();
+    if format == ImageFormat::Undefined {
+        return Err(error(format!("VkFormat {} is not supported", vk_format)));
+    }
+    // Zero depth, layer and level counts mean a 2D texture, no array and no mipmaps.
+    let header = Header {
+        format,
+        width: read_u32(data, 20),
+        height: read_u32(data, 24).max(1),
+        depth: read_u32(data, 28).max(1),
+        layer_count: read_u32(data, 32).max(1),
+        level_count: read_u32(data, 40).max(1),
+    };
+    let index_end = (header.level_count as usize)
+        .checked_mul(LEVEL_INDEX_ENTRY_SIZE)
+        .and_then(|x| x.checked_add(HEADER_SIZE));
+    if index_end.map_or(true, |end| data.len() < end) {
+        return Err(error("the level index is truncated"));
+    }
+    let levels = (0..header.level_count as usize)
+        .map(|i| {
+            let entry = HEADER_SIZE + i * LEVEL_INDEX_ENTRY_SIZE;
+            let offset = read_u64(data, entry) as usize;
+            let length = read_u64(data, entry + 8) as usize;
+            let extent =
+                Extent3d::new(header.width, header.height, header.depth).mip_extent(i as u32);
+            let expected = level_size(&header, extent)?;
+            if length as u64 != expected {
+                return Err(error(format!(
+                    "level {} has {} bytes but {} were expected",
+                    i, length, expected
+                )));
+            }
+            if offset
+                .checked_add(length)
+                .map_or(true, |end| end > data.len())
+            {
+                return Err(error(format!("level {} is outside of the file", i)));
+            }
+            Ok(Level { offset, length })
+        })
+        .collect::<NxResult<Vec<Level>>>()?;
+    Ok((header, levels))
+}
+
+/// Size in bytes of a mip level of the given extent, including every array layer.
+fn level_size(header: &Header, extent: Extent3d) -> NxResult<u64> {
+    let block = if header.format.is_block_compressed() {
+        4
+    } else {
+        1
+    };
+    (extent.width().div_ceil(block) as u64)
+        .checked_mul(extent.height().div_ceil(block) as u64)
+        .and_then(|x| x.checked_mul(extent.depth() as u64))
+        .and_then(|x| x.checked_mul(header.layer_count as u64))
+        .and_then(|x| x.checked_mul(header.format.texel_block_size()))
+        .ok_or_else(|| error("the level size overflows"))
+}
+
+impl Image {
+    /// Create a sampled Image from a KTX2 file and upload every mip level and array layer.
+    /// Uncompressed and BC block-compressed formats are supported.
+    /// Cube maps, Basis Universal and supercompressed files are not.
+    /// The image is left in `ResourceState::shader_read`, which becomes its tracked state.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `device` - Reference to the appropriate device.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `data` - Contents of the KTX2 file.
+    pub fn from_ktx2(
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        data: &[u8],
+    ) -> NxResult<Self> {
+        let (header, levels) = parse(data)?;
+        if header.width == 0 {
+            return Err(error("1D textures are not supported"));
+        }
+        let extent = Extent3d::new(header.width, header.height, header.depth);
+        let image_type = if header.depth > 1 {
+            ImageDimension::D3
+        } else {
+            ImageDimension::D2
+        };
+        let descriptor = ImageDescriptor::new()
+            .image_type(image_type)
+            .extent(extent)
+            .format(header.format)
+            .mip_levels(header.level_count)
+            .array_layers(header.layer_count)
+            .usage(ImageUsage::Sampled)
+            .add_usage(ImageUsage::TransferDst)
+            .tiling(ImageTiling::Optimal);
+        let image = Self::create(instance, device, connecter, &descriptor)?;
+
+        // Copy offsets must be multiples of both the texel block size and 4.
+        let block_size = header.format.texel_block_size();
+        let alignment = if block_size % 4 == 0 {
+            block_size
+        } else {
+            block_size * 4
+        };
+        let mut staging = vec![];
+        let mut regions = vec![];
+        for (i, level) in levels.iter().enumerate() {
+            staging.resize(
+                (staging.len() as u64).next_multiple_of(alignment) as usize,
+                0,
+            );
+            regions.push(
+                BufferImageCopy::builder()
+                    .buffer_offset(staging.len() as u64)
+                    .image_subresource(
+                        ImageSubresourceLayers::builder()
+                            .aspect_mask(header.format.aspect())
+                            .mip_level(i as u32)
+                            .base_array_layer(0)
+                            .layer_count(header.layer_count)
+                            .build(),
+                    )
+                    .image_extent(extent.mip_extent(i as u32).into())
+                    .build(),
+            );
+            staging.extend_from_slice(&data[level.offset..level.offset + level.length]);
+        }
+        if let Err(e) = image.upload_regions(instance, connecter, device, &staging, &regions) {
+            device.destroy(&image);
+            return Err(e);
+        }
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A KTX2 file with one R8G8B8A8_UNORM level of `width` x `height` x `depth` texels
+    /// and `layer_count` layers. The level data is `length` bytes long.
+    fn file(width: u32, height: u32, depth: u32, layer_count: u32, length: usize) -> Vec<u8> {
+        let mut data = IDENTIFIER.to_vec();
+        for field in [
+            Format::R8G8B8A8_UNORM.as_raw() as u32,
+            1,
+            width,
+            height,
+            depth,
+            layer_count,
+            1,
+            1,
+            0,
+        ] {
+            data.extend_from_slice(&field.to_le_bytes());
+        }
+        data.resize(HEADER_SIZE, 0);
+        let offset = (HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE) as u64;
+        data.extend_from_slice(&offset.to_le_bytes());
+        data.extend_from_slice(&(length as u64).to_le_bytes());
+        data.extend_from_slice(&(length as u64).to_le_bytes());
+        data.resize(data.len() + length, 0);
+        data
+    }
+
+    #[test]
+    fn parses_valid_file() {
+        let (header, levels) = parse(&file(4, 4, 0, 2, 128)).unwrap();
+        assert_eq!(header.format, ImageFormat::R8G8B8A8Unorm);
+        assert_eq!((header.width, header.height, header.depth), (4, 4, 1));
+        assert_eq!((header.layer_count, header.level_count), (2, 1));
+        assert_eq!(levels.len(), 1);
+        assert_eq!(levels[0].offset, HEADER_SIZE + LEVEL_INDEX_ENTRY_SIZE);
+        assert_eq!(levels[0].length, 128);
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let data = file(4, 4, 0, 1, 64);
+        assert!(matches!(
+            parse(&data[..HEADER_SIZE - 1]),
+            Err(NxError::Ktx2Error(_))
+        ));
+        assert!(matches!(
+            parse(&data[..HEADER_SIZE + 8]),
+            Err(NxError::Ktx2Error(_))
+        ));
+        assert!(matches!(
+            parse(&data[..data.len() - 1]),
+            Err(NxError::Ktx2Error(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_overflowing_level_size() {
+        let data = file(u32::MAX, u32::MAX, u32::MAX, u32::MAX, 0);
+        assert!(matches!(parse(&data), Err(NxError::Ktx2Error(_))));
+    }
+}
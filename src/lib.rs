@@ -50,25 +50,42 @@
 #[macro_use]
 extern crate log;
 
-use std::ffi::c_char;
+use std::ffi::{c_char, CString};
 use std::{borrow::Cow, ffi::CStr};
 
 use ash::vk::{
     self, DebugUtilsMessageSeverityFlagsEXT, DeviceCreateInfo, DeviceQueueCreateInfo, QueueFlags,
 };
 
+mod barrier;
 mod buffer;
+mod capabilities;
+mod config;
+mod debug;
+#[cfg(feature = "debug-draw")]
+mod debug_draw;
 mod device;
+mod external;
 mod fence;
 mod frame_buffer;
+mod graph;
 mod image;
 mod instance;
+#[cfg(feature = "ktx2")]
+mod ktx2;
 #[doc(hidden)]
 mod mem;
 mod pipeline;
+mod pixel;
+mod query;
 mod queue;
+mod ray_tracing;
 mod recorder;
+#[cfg(feature = "reflect")]
+mod reflect;
+mod rendering;
 mod renderpass;
+mod sampler;
 mod shader;
 #[cfg(feature = "window")]
 mod surface;
@@ -76,17 +93,35 @@ mod surface;
 mod swapchain;
 mod sync;
 
+pub use barrier::*;
 pub use buffer::*;
+pub use capabilities::DeviceCapabilities;
+pub use config::*;
+pub use debug::DebugObject;
+#[cfg(feature = "debug-draw")]
+pub use debug_draw::{DebugDraw, Rect};
 pub use device::*;
+pub use external::{
+    ExternalMemoryHandle, ExternalMemoryHandleType, ExternalSemaphoreHandle,
+    ExternalSemaphoreHandleType,
+};
 pub use fence::*;
 pub use frame_buffer::*;
+pub use graph::*;
 pub use image::*;
 pub use instance::*;
-pub(crate) use mem::*;
+pub use mem::{DedicatedAllocation, DeviceMemory, MappedMemory, ResourceMemoryRequirements};
 pub use pipeline::*;
+pub use pixel::*;
+pub use query::*;
 pub use queue::*;
+pub use ray_tracing::*;
 pub use recorder::*;
+#[cfg(feature = "reflect")]
+pub use reflect::*;
+pub use rendering::*;
 pub use renderpass::*;
+pub use sampler::*;
 pub use shader::*;
 #[cfg(feature = "window")]
 pub use surface::*;
@@ -94,6 +129,7 @@ pub use surface::*;
 pub use swapchain::*;
 pub use sync::*;
 
+use device::QueueFamilyIndices;
 use thiserror::Error;
 
 pub type NxResult<T> = std::result::Result<T, NxError>;
@@ -117,6 +153,32 @@ pub enum NxError {
     InternalError(#[from] ash::vk::Result),
     #[error("`{0}`")]
     IoError(String),
+    #[error("Shader reflection failed: {0}")]
+    ReflectionError(String),
+    #[error("KTX2 parsing failed: {0}")]
+    Ktx2Error(String),
+    #[error("Unsupported feature: {0}")]
+    UnsupportedFeature(String),
+    #[error("Invalid descriptor: {0}")]
+    InvalidDescriptor(String),
+    /// Every problem found by `PipelineDescriptor::validate`.
+    #[error(
+        "Pipeline validation failed: {}",
+        .0.iter().map(|x| x.to_string()).collect::<Vec<String>>().join("; ")
+    )]
+    PipelineValidation(Vec<PipelineIssue>),
+    /// The device was lost, for example because the GPU was reset or crashed.
+    /// The Device and every object created from it are invalid after this error
+    /// and must be recreated.
+    #[error("Device lost")]
+    DeviceLost,
+    /// Waiting for the GPU did not finish within the given time.
+    #[error("Timed out")]
+    Timeout,
+    /// A CommandRecorder whose recording failed was submitted.
+    /// It must be recorded again before it can be submitted.
+    #[error("Submitted a command buffer whose recording failed")]
+    RecordingFailed,
 }
 
 pub struct QueueFamilyProperties {
@@ -154,22 +216,353 @@ pub struct DeviceConnecter(pub(crate) vk::PhysicalDevice, pub(crate) usize);
 impl DeviceConnecter {
     /// Create a device.
     pub fn create_device(self, instance: &Instance, queue_family_index: usize) -> NxResult<Device> {
-        let extensions = &instance
+        self.create_device_with_features(instance, queue_family_index, &DeviceFeatures::empty())
+    }
+
+    /// Create a device with optional features enabled.
+    /// Returns `NxError::UnsupportedFeature` if the device does not support one of them.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `queue_family_index` - Index of the queue family to create a queue from.
+    /// * `features` - Features to enable.
+    pub fn create_device_with_features(
+        self,
+        instance: &Instance,
+        queue_family_index: usize,
+        features: &DeviceFeatures,
+    ) -> NxResult<Device> {
+        self.create(instance, queue_family_index, None, features)
+    }
+
+    /// Create a device that presents to `surface`.
+    /// Presentation uses the queue family `queue_family_index` if it can present to the surface,
+    /// otherwise the device gets a queue of a separate family that can,
+    /// see `QueueSelection::Present`.
+    /// Returns `NxError::UnsupportedFeature` if no queue family can present to the surface.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `queue_family_index` - Index of the queue family to create a queue from.
+    /// * `surface` - Surface the device presents to.
+    /// * `features` - Features to enable.
+    #[cfg(feature = "window")]
+    pub fn create_device_for_surface(
+        self,
+        instance: &Instance,
+        queue_family_index: usize,
+        surface: &Surface,
+        features: &DeviceFeatures,
+    ) -> NxResult<Device> {
+        self.create_device_for_surfaces(instance, queue_family_index, &[surface], features)
+    }
+
+    /// Create a device that presents to every surface in `surfaces`, e.g. one per window.
+    /// Like `create_device_for_surface`, but the queue family used for presentation
+    /// must be able to present to all of the surfaces.
+    /// Returns `NxError::UnsupportedFeature` if no queue family can present to all of them.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `queue_family_index` - Index of the queue family to create a queue from.
+    /// * `surfaces` - Surfaces the device presents to.
+    /// * `features` - Features to enable.
+    #[cfg(feature = "window")]
+    pub fn create_device_for_surfaces(
+        self,
+        instance: &Instance,
+        queue_family_index: usize,
+        surfaces: &[&Surface],
+        features: &DeviceFeatures,
+    ) -> NxResult<Device> {
+        let presents = |i: usize| surfaces.iter().all(|x| self.is_present_support(x, i));
+        let present = if presents(queue_family_index) {
+            None
+        } else {
+            let count = self.get_queue_family_properties(instance)?.len();
+            match (0..count).find(|i| presents(*i)) {
+                Some(i) => Some(i),
+                None => {
+                    return Err(NxError::UnsupportedFeature(
+                        "no queue family of the device can present to the surfaces".to_string(),
+                    ))
+                }
+            }
+        };
+        self.create(instance, queue_family_index, present, features)
+    }
+
+    /// Create a device with the features and queue families of `config`,
+    /// for example to reproduce the configuration of a bug report.
+    /// Fails if this is not the physical device the configuration was obtained from,
+    /// or if it does not support the configuration.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `config` - Configuration obtained with `Device::config`.
+    pub fn create_device_from_config(
+        self,
+        instance: &Instance,
+        config: &DeviceConfig,
+    ) -> NxResult<Device> {
+        self.validate_config(instance, config)?;
+        self.create(
+            instance,
+            config.graphics_queue_family,
+            config.present_queue_family,
+            &config.features,
+        )
+    }
+
+    /// Checks that a device with `config` can be created from this connecter.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `config` - Configuration obtained with `Device::config`.
+    pub fn validate_config(&self, instance: &Instance, config: &DeviceConfig) -> NxResult<()> {
+        let properties = instance.get_device_properties(*self);
+        if properties.vendor_id() != config.vendor_id || properties.device_id() != config.device_id
+        {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the configuration is for {} but the connecter is {}",
+                config.device_name,
+                properties.name()
+            )));
+        }
+        let queue_family_count = self.get_queue_family_properties(instance)?.len();
+        for index in [
+            Some(config.graphics_queue_family),
+            config.transfer_queue_family,
+            config.compute_queue_family,
+            config.present_queue_family,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            if index >= queue_family_count {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "queue family {} does not exist on a device with {} queue families",
+                    index, queue_family_count
+                )));
+            }
+        }
+        if let Some(name) = config.features.first_missing(&self.features(instance)) {
+            return Err(NxError::UnsupportedFeature(format!(
+                "{} is not supported by the device",
+                name
+            )));
+        }
+        for name in &config.extensions {
+            let supported = CString::new(name.as_str())
+                .is_ok_and(|x| self.is_extension_supported(instance, &x));
+            if !supported {
+                return Err(NxError::UnsupportedFeature(format!(
+                    "{} is not supported by the device",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    fn create(
+        self,
+        instance: &Instance,
+        queue_family_index: usize,
+        present_queue_family_index: Option<usize>,
+        features: &DeviceFeatures,
+    ) -> NxResult<Device> {
+        if let Some(name) = features.first_missing(&self.features(instance)) {
+            return Err(NxError::UnsupportedFeature(format!(
+                "{} is not supported by the device",
+                name
+            )));
+        }
+        let enabled_features = (*features).into();
+        // Debug markers are only needed when the instance has no debug utils.
+        let debug_marker = !instance.has_debug_utils()
+            && self.is_extension_supported(instance, DeviceFeature::DebugMarker.name());
+        let feature_extensions = features.extensions();
+        let extension_features = instance
             .device_exts
             .iter()
-            .map(|x| match x {
-                DeviceFeature::Swapchain => ash::extensions::khr::Swapchain::name().as_ptr(),
-            })
+            .chain(feature_extensions.iter())
+            .chain(debug_marker.then_some(&DeviceFeature::DebugMarker))
+            .collect::<Vec<&DeviceFeature>>();
+        let extensions = &extension_features
+            .iter()
+            .map(|x| x.name().as_ptr())
             .collect::<Vec<*const c_char>>();
-        let queue_infos = vec![DeviceQueueCreateInfo::builder()
+        let mut conditional_rendering = vk::PhysicalDeviceConditionalRenderingFeaturesEXT::builder()
+            .conditional_rendering(true)
+            .build();
+        let queue_family_props = self.get_queue_family_properties(instance)?;
+        let queue_families = QueueFamilyIndices {
+            graphics: queue_family_index,
+            transfer: Self::find_transfer_queue_family(&queue_family_props, queue_family_index),
+            compute: Self::find_compute_queue_family(&queue_family_props, queue_family_index),
+            present: present_queue_family_index,
+        };
+        let mut queue_infos = vec![DeviceQueueCreateInfo::builder()
             .queue_family_index(queue_family_index as u32)
             .queue_priorities(&[1.0])
             .build()];
-        let create_info = DeviceCreateInfo::builder()
+        for index in [
+            queue_families.transfer,
+            queue_families.compute,
+            queue_families.present,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            // The transfer, compute and present queues may come from the same family.
+            if queue_infos
+                .iter()
+                .all(|x| x.queue_family_index != index as u32)
+            {
+                queue_infos.push(
+                    DeviceQueueCreateInfo::builder()
+                        .queue_family_index(index as u32)
+                        .queue_priorities(&[1.0])
+                        .build(),
+                );
+            }
+        }
+        let mut create_info = DeviceCreateInfo::builder()
             .queue_create_infos(&queue_infos)
             .enabled_extension_names(extensions)
+            .enabled_features(&enabled_features);
+        if features.conditional_rendering {
+            create_info = create_info.push_next(&mut conditional_rendering);
+        }
+        let mut ray_tracing_pipeline = vk::PhysicalDeviceRayTracingPipelineFeaturesKHR::builder()
+            .ray_tracing_pipeline(true)
             .build();
-        instance.create_device(self, &create_info)
+        if features.ray_tracing_pipeline {
+            create_info = create_info.push_next(&mut ray_tracing_pipeline);
+        }
+        let update_after_bind = features.descriptor_update_after_bind;
+        let mut vulkan12 = vk::PhysicalDeviceVulkan12Features::builder()
+            .buffer_device_address(features.ray_tracing_pipeline)
+            .separate_depth_stencil_layouts(features.separate_depth_stencil_layouts)
+            .descriptor_binding_partially_bound(features.descriptor_binding_partially_bound)
+            .descriptor_binding_sampled_image_update_after_bind(update_after_bind)
+            .descriptor_binding_storage_image_update_after_bind(update_after_bind)
+            .descriptor_binding_storage_buffer_update_after_bind(update_after_bind)
+            .draw_indirect_count(features.draw_indirect_count)
+            .host_query_reset(features.host_query_reset)
+            .build();
+        if features.ray_tracing_pipeline
+            || features.separate_depth_stencil_layouts
+            || features.descriptor_binding_partially_bound
+            || update_after_bind
+            || features.draw_indirect_count
+            || features.host_query_reset
+        {
+            create_info = create_info.push_next(&mut vulkan12);
+        }
+        let mut robustness2 = vk::PhysicalDeviceRobustness2FeaturesEXT::builder()
+            .null_descriptor(true)
+            .build();
+        if features.null_descriptor {
+            create_info = create_info.push_next(&mut robustness2);
+        }
+        let mut vulkan11 = vk::PhysicalDeviceVulkan11Features::builder()
+            .multiview(true)
+            .build();
+        if features.multiview {
+            create_info = create_info.push_next(&mut vulkan11);
+        }
+        let mut vulkan13 = vk::PhysicalDeviceVulkan13Features::builder()
+            .dynamic_rendering(true)
+            .build();
+        if features.dynamic_rendering {
+            create_info = create_info.push_next(&mut vulkan13);
+        }
+        let create_info = create_info.build();
+        instance.create_device(
+            self,
+            &create_info,
+            *features,
+            queue_families,
+            debug_marker,
+            extension_features
+                .iter()
+                .map(|x| x.name().to_string_lossy().into_owned())
+                .collect(),
+        )
+    }
+
+    #[doc(hidden)]
+    /// Finds a transfer queue family other than `queue_family_index` that cannot do graphics,
+    /// preferring one that cannot do compute either.
+    fn find_transfer_queue_family(
+        props: &[QueueFamilyProperties],
+        queue_family_index: usize,
+    ) -> Option<usize> {
+        let candidates = props
+            .iter()
+            .enumerate()
+            .filter(|(i, x)| {
+                *i != queue_family_index && x.is_transfer_support() && !x.is_graphic_support()
+            })
+            .collect::<Vec<_>>();
+        candidates
+            .iter()
+            .find(|(_, x)| !x.is_compute_support())
+            .or(candidates.first())
+            .map(|(i, _)| *i)
+    }
+
+    #[doc(hidden)]
+    /// Finds a compute queue family other than `queue_family_index` that cannot do graphics,
+    /// so that compute work can run alongside graphics work.
+    fn find_compute_queue_family(
+        props: &[QueueFamilyProperties],
+        queue_family_index: usize,
+    ) -> Option<usize> {
+        props
+            .iter()
+            .enumerate()
+            .find(|(i, x)| {
+                *i != queue_family_index && x.is_compute_support() && !x.is_graphic_support()
+            })
+            .map(|(i, _)| i)
+    }
+
+    /// Get the optional features supported by the device.
+    pub fn features(&self, instance: &Instance) -> DeviceFeatures {
+        let capabilities = self.capabilities(instance);
+        DeviceFeatures {
+            occlusion_query_precise: capabilities.occlusion_query_precise,
+            pipeline_statistics_query: capabilities.pipeline_statistics_query,
+            conditional_rendering: capabilities.conditional_rendering,
+            wide_lines: capabilities.wide_lines,
+            sampler_anisotropy: capabilities.sampler_anisotropy,
+            ray_tracing_pipeline: capabilities.ray_tracing_pipeline,
+            separate_depth_stencil_layouts: capabilities.separate_depth_stencil_layouts,
+            external_memory: capabilities.external_memory,
+            external_semaphore: capabilities.external_semaphore,
+            sparse_buffer: capabilities.sparse_binding && capabilities.sparse_residency_buffer,
+            multiview: capabilities.multiview,
+            robust_buffer_access: capabilities.robust_buffer_access,
+            descriptor_binding_partially_bound: capabilities.descriptor_binding_partially_bound,
+            descriptor_update_after_bind: capabilities.descriptor_update_after_bind,
+            null_descriptor: capabilities.null_descriptor,
+            dynamic_rendering: capabilities.dynamic_rendering,
+            draw_indirect_count: capabilities.draw_indirect_count,
+            host_query_reset: capabilities.host_query_reset,
+            extended_dynamic_state: capabilities.extended_dynamic_state,
+            hdr_metadata: capabilities.hdr_metadata,
+            push_descriptor: capabilities.push_descriptor,
+        }
+    }
+
+    /// Get the optional features and extensions supported by the device,
+    /// including ones that cannot be enabled through `DeviceFeatures`.
+    pub fn capabilities(&self, instance: &Instance) -> DeviceCapabilities {
+        DeviceCapabilities::query(instance, *self)
     }
 
     pub fn get_queue_family_properties(
@@ -183,6 +576,38 @@ impl DeviceConnecter {
         self.1
     }
 
+    /// Get the type of the device.
+    pub fn device_type(&self, instance: &Instance) -> DeviceType {
+        instance.get_properties(self.0).device_type.into()
+    }
+
+    /// Get the properties of the device, including vendor and driver information.
+    pub fn properties(&self, instance: &Instance) -> DeviceProperties {
+        instance.get_device_properties(*self)
+    }
+
+    /// Get the name of the device.
+    pub fn name(&self, instance: &Instance) -> String {
+        let properties = instance.get_properties(self.0);
+        unsafe { CStr::from_ptr(properties.device_name.as_ptr()) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// Check whether the queue family can present to the surface.
+    /// **"window" feature is required.**
+    #[cfg(feature = "window")]
+    pub fn is_present_support(&self, surface: &Surface, queue_family_index: usize) -> bool {
+        unsafe {
+            surface.surface.get_physical_device_surface_support(
+                self.0,
+                queue_family_index as u32,
+                surface.khr,
+            )
+        }
+        .unwrap_or(false)
+    }
+
     #[doc(hidden)]
     pub(crate) fn get_memory_properties(
         &self,
@@ -191,6 +616,119 @@ impl DeviceConnecter {
         instance.get_memory_properties(self.0)
     }
 
+    /// Get the sample counts that can be used for images of `format` with every usage in `usage`.
+    /// Always contains `SampleCount::X1` if the format supports the usage.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `format` - Format of the image.
+    /// * `usage` - Usages of the image.
+    pub fn supported_sample_counts(
+        &self,
+        instance: &Instance,
+        format: ImageFormat,
+        usage: &[ImageUsage],
+    ) -> Vec<SampleCount> {
+        let usage = usage
+            .iter()
+            .fold(vk::ImageUsageFlags::empty(), |flags, x| flags | (*x).into());
+        let supported = self.sample_count_flags(instance, format, usage);
+        SampleCount::ALL
+            .into_iter()
+            .filter(|x| supported.contains((*x).into()))
+            .collect()
+    }
+
+    /// Returns true if images of `format` with optimal tiling can be sampled with linear filtering.
+    /// Many float and depth formats only support nearest filtering.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `format` - Format of the image.
+    pub fn supports_linear_filter(&self, instance: &Instance, format: ImageFormat) -> bool {
+        format != ImageFormat::Undefined
+            && self
+                .get_format_properties(instance, format.into())
+                .optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+    }
+
+    #[doc(hidden)]
+    /// Combines the sample count limits of each usage with the limits of the format.
+    pub(crate) fn sample_count_flags(
+        &self,
+        instance: &Instance,
+        format: ImageFormat,
+        usage: vk::ImageUsageFlags,
+    ) -> vk::SampleCountFlags {
+        let limits = instance.get_properties(self.0).limits;
+        let aspect = format.aspect();
+        let mut counts = match unsafe {
+            instance.instance.get_physical_device_image_format_properties(
+                self.0,
+                format.into(),
+                vk::ImageType::TYPE_2D,
+                vk::ImageTiling::OPTIMAL,
+                usage,
+                vk::ImageCreateFlags::empty(),
+            )
+        } {
+            Ok(x) => x.sample_counts,
+            Err(_) => return vk::SampleCountFlags::empty(),
+        };
+        if usage.contains(vk::ImageUsageFlags::COLOR_ATTACHMENT) {
+            counts &= limits.framebuffer_color_sample_counts;
+        }
+        if usage.contains(vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT) {
+            if aspect.contains(vk::ImageAspectFlags::DEPTH) {
+                counts &= limits.framebuffer_depth_sample_counts;
+            }
+            if aspect.contains(vk::ImageAspectFlags::STENCIL) {
+                counts &= limits.framebuffer_stencil_sample_counts;
+            }
+        }
+        if usage.contains(vk::ImageUsageFlags::SAMPLED) {
+            if aspect.contains(vk::ImageAspectFlags::COLOR) {
+                counts &= limits.sampled_image_color_sample_counts;
+            }
+            if aspect.contains(vk::ImageAspectFlags::DEPTH) {
+                counts &= limits.sampled_image_depth_sample_counts;
+            }
+            if aspect.contains(vk::ImageAspectFlags::STENCIL) {
+                counts &= limits.sampled_image_stencil_sample_counts;
+            }
+        }
+        counts
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn get_format_properties(
+        &self,
+        instance: &Instance,
+        format: vk::Format,
+    ) -> vk::FormatProperties {
+        unsafe {
+            instance
+                .instance
+                .get_physical_device_format_properties(self.0, format)
+        }
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn is_extension_supported(&self, instance: &Instance, name: &CStr) -> bool {
+        let extensions = match unsafe {
+            instance
+                .instance
+                .enumerate_device_extension_properties(self.0)
+        } {
+            Ok(x) => x,
+            Err(_) => return false,
+        };
+        extensions
+            .iter()
+            .any(|x| unsafe { CStr::from_ptr(x.extension_name.as_ptr()) } == name)
+    }
+
     #[doc(hidden)]
     #[cfg(feature = "window")]
     pub(crate) fn is_support_swapchain(&self, instance: &Instance) -> bool {
@@ -212,35 +750,32 @@ impl DeviceConnecter {
 
     #[doc(hidden)]
     #[cfg(feature = "window")]
-    pub(crate) fn get_surface_capabilities(&self, surface: &Surface) -> vk::SurfaceCapabilitiesKHR {
-        unsafe {
-            surface
-                .surface
-                .get_physical_device_surface_capabilities(self.0, surface.khr)
-                .unwrap()
-        }
+    pub(crate) fn get_surface_capabilities(
+        &self,
+        surface: &ash::extensions::khr::Surface,
+        khr: vk::SurfaceKHR,
+    ) -> NxResult<vk::SurfaceCapabilitiesKHR> {
+        Ok(unsafe { surface.get_physical_device_surface_capabilities(self.0, khr) }?)
     }
 
     #[doc(hidden)]
     #[cfg(feature = "window")]
-    pub(crate) fn get_surface_formats(&self, surface: &Surface) -> Vec<vk::SurfaceFormatKHR> {
-        unsafe {
-            surface
-                .surface
-                .get_physical_device_surface_formats(self.0, surface.khr)
-                .unwrap()
-        }
+    pub(crate) fn get_surface_formats(
+        &self,
+        surface: &ash::extensions::khr::Surface,
+        khr: vk::SurfaceKHR,
+    ) -> NxResult<Vec<vk::SurfaceFormatKHR>> {
+        Ok(unsafe { surface.get_physical_device_surface_formats(self.0, khr) }?)
     }
 
     #[doc(hidden)]
     #[cfg(feature = "window")]
-    pub(crate) fn get_surface_present_modes(&self, surface: &Surface) -> Vec<vk::PresentModeKHR> {
-        unsafe {
-            surface
-                .surface
-                .get_physical_device_surface_present_modes(self.0, surface.khr)
-                .unwrap()
-        }
+    pub(crate) fn get_surface_present_modes(
+        &self,
+        surface: &ash::extensions::khr::Surface,
+        khr: vk::SurfaceKHR,
+    ) -> NxResult<Vec<vk::PresentModeKHR>> {
+        Ok(unsafe { surface.get_physical_device_surface_present_modes(self.0, khr) }?)
     }
 }
 
@@ -258,6 +793,49 @@ impl From<vk::QueueFamilyProperties> for QueueFamilyProperties {
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Extent2d {
+    width: u32,
+    height: u32,
+}
+
+impl Extent2d {
+    pub const fn new(width: u32, height: u32) -> Self {
+        Self { width, height }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the width divided by the height.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+}
+
+impl From<vk::Extent2D> for Extent2d {
+    fn from(value: vk::Extent2D) -> Self {
+        Self {
+            width: value.width,
+            height: value.height,
+        }
+    }
+}
+
+impl From<Extent2d> for vk::Extent2D {
+    fn from(value: Extent2d) -> Self {
+        vk::Extent2D {
+            width: value.width,
+            height: value.height,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct Extent3d {
     width: u32,
@@ -285,6 +863,28 @@ impl Extent3d {
     pub fn depth(&self) -> u32 {
         self.depth
     }
+
+    /// Get the width divided by the height.
+    pub fn aspect_ratio(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+
+    /// Get the extent of mip level `level`.
+    /// Each dimension is halved per level and never drops below 1.
+    pub fn mip_extent(&self, level: u32) -> Self {
+        let mip = |x: u32| x.checked_shr(level).unwrap_or(0).max(1);
+        Self {
+            width: mip(self.width),
+            height: mip(self.height),
+            depth: mip(self.depth),
+        }
+    }
+
+    /// Get the number of levels of a full mip chain, down to a 1x1x1 level.
+    pub fn mip_count(&self) -> u32 {
+        let largest = self.width.max(self.height).max(self.depth).max(1);
+        largest.ilog2() + 1
+    }
 }
 
 impl From<Extent3d> for vk::Extent3D {
@@ -319,6 +919,44 @@ unsafe extern "system" fn vulkan_debug_callback(
         CStr::from_ptr(callback_data.p_message).to_string_lossy()
     };
 
+    // With the "tracing" feature, messages are emitted as tracing events instead of log records.
+    #[cfg(feature = "tracing")]
+    match message_severity {
+        DebugUtilsMessageSeverityFlagsEXT::ERROR => tracing::error!(
+            target: "nexg::vulkan",
+            message_id_name = %_message_id_name,
+            message_id_number = _message_id_number,
+            message_type = ?_message_type,
+            "{}",
+            message
+        ),
+        DebugUtilsMessageSeverityFlagsEXT::WARNING => tracing::warn!(
+            target: "nexg::vulkan",
+            message_id_name = %_message_id_name,
+            message_id_number = _message_id_number,
+            message_type = ?_message_type,
+            "{}",
+            message
+        ),
+        DebugUtilsMessageSeverityFlagsEXT::INFO => tracing::info!(
+            target: "nexg::vulkan",
+            message_id_name = %_message_id_name,
+            message_id_number = _message_id_number,
+            message_type = ?_message_type,
+            "{}",
+            message
+        ),
+        _ => tracing::trace!(
+            target: "nexg::vulkan",
+            message_id_name = %_message_id_name,
+            message_id_number = _message_id_number,
+            message_type = ?_message_type,
+            "{}",
+            message
+        ),
+    }
+
+    #[cfg(not(feature = "tracing"))]
     match message_severity {
         DebugUtilsMessageSeverityFlagsEXT::INFO => info!("[Vulkan] {}", message),
         DebugUtilsMessageSeverityFlagsEXT::WARNING => warn!("[Vulkan] {}", message),
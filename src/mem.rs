@@ -1,12 +1,154 @@
-use crate::{Destroy, Device, Instance, NxError, NxResult};
+use crate::{
+    Buffer, Destroy, Device, DeviceConnecter, ExternalMemoryHandle, ExternalMemoryHandleType,
+    Instance, NxError, NxResult,
+};
 use ash::vk::{
-    MemoryAllocateInfo, MemoryMapFlags, MemoryPropertyFlags, MemoryRequirements,
-    PhysicalDeviceMemoryProperties,
+    BufferMemoryRequirementsInfo2, ExportMemoryAllocateInfo, ImageMemoryRequirementsInfo2,
+    ImportMemoryFdInfoKHR, ImportMemoryWin32HandleInfoKHR, MappedMemoryRange, MemoryAllocateFlags,
+    MemoryAllocateFlagsInfo, MemoryAllocateInfo, MemoryDedicatedAllocateInfo,
+    MemoryDedicatedRequirements, MemoryMapFlags, MemoryPropertyFlags, MemoryRequirements,
+    MemoryRequirements2, PhysicalDeviceMemoryProperties, API_VERSION_1_1,
 };
 use std::ffi::c_void;
 
+/// Whether a resource gets a block of memory of its own.
+/// Dedicated allocations let the driver optimize large resources such as render targets.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DedicatedAllocation {
+    /// Use a dedicated allocation when the driver prefers or requires one.
+    #[default]
+    Auto,
+    /// Always use a dedicated allocation. Requires Vulkan 1.1.
+    Always,
+    /// Never use a dedicated allocation.
+    /// Creating the resource fails if the driver requires one.
+    Never,
+}
+
+impl DedicatedAllocation {
+    fn resolve(self, device: &Device, requirements: &ResourceMemoryRequirements) -> NxResult<bool> {
+        let required = requirements.requires_dedicated;
+        match self {
+            Self::Auto => Ok(required || requirements.prefers_dedicated),
+            Self::Always if device.api_version < API_VERSION_1_1 => Err(
+                NxError::UnsupportedFeature("dedicated allocations require Vulkan 1.1".to_string()),
+            ),
+            Self::Always => Ok(true),
+            Self::Never if required => Err(NxError::InvalidDescriptor(
+                "the driver requires a dedicated allocation for the resource".to_string(),
+            )),
+            Self::Never => Ok(false),
+        }
+    }
+}
+
+/// Memory needed by a buffer or an image, queried before memory is bound to it.
+/// See `Buffer::memory_requirements` and `Image::memory_requirements`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResourceMemoryRequirements {
+    size: u64,
+    alignment: u64,
+    memory_type_bits: u32,
+    prefers_dedicated: bool,
+    requires_dedicated: bool,
+}
+
+impl ResourceMemoryRequirements {
+    #[doc(hidden)]
+    pub(crate) fn of_image(device: &Device, image: ash::vk::Image) -> Self {
+        // vkGetImageMemoryRequirements2 and dedicated allocations are core since Vulkan 1.1.
+        if device.api_version < API_VERSION_1_1 {
+            let requirements = unsafe { device.device.get_image_memory_requirements(image) };
+            return Self::new(requirements, MemoryDedicatedRequirements::default());
+        }
+        let info = ImageMemoryRequirementsInfo2::builder().image(image);
+        let mut dedicated = MemoryDedicatedRequirements::default();
+        let mut requirements = MemoryRequirements2::builder().push_next(&mut dedicated);
+        unsafe {
+            device
+                .device
+                .get_image_memory_requirements2(&info, &mut requirements)
+        };
+        Self::new(requirements.memory_requirements, dedicated)
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn of_buffer(device: &Device, buffer: ash::vk::Buffer) -> Self {
+        if device.api_version < API_VERSION_1_1 {
+            let requirements = unsafe { device.device.get_buffer_memory_requirements(buffer) };
+            return Self::new(requirements, MemoryDedicatedRequirements::default());
+        }
+        let info = BufferMemoryRequirementsInfo2::builder().buffer(buffer);
+        let mut dedicated = MemoryDedicatedRequirements::default();
+        let mut requirements = MemoryRequirements2::builder().push_next(&mut dedicated);
+        unsafe {
+            device
+                .device
+                .get_buffer_memory_requirements2(&info, &mut requirements)
+        };
+        Self::new(requirements.memory_requirements, dedicated)
+    }
+
+    fn new(requirements: MemoryRequirements, dedicated: MemoryDedicatedRequirements) -> Self {
+        Self {
+            size: requirements.size,
+            alignment: requirements.alignment,
+            memory_type_bits: requirements.memory_type_bits,
+            prefers_dedicated: dedicated.prefers_dedicated_allocation != 0,
+            requires_dedicated: dedicated.requires_dedicated_allocation != 0,
+        }
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn to_vk(self) -> MemoryRequirements {
+        MemoryRequirements {
+            size: self.size,
+            alignment: self.alignment,
+            memory_type_bits: self.memory_type_bits,
+        }
+    }
+
+    /// Size of the memory in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Alignment of the offset the resource is bound at.
+    pub fn alignment(&self) -> u64 {
+        self.alignment
+    }
+
+    /// Bit mask of the memory types the resource can be bound to.
+    pub fn memory_type_bits(&self) -> u32 {
+        self.memory_type_bits
+    }
+
+    /// Whether the driver prefers a dedicated allocation for the resource.
+    pub fn prefers_dedicated(&self) -> bool {
+        self.prefers_dedicated
+    }
+
+    /// Whether the driver requires a dedicated allocation for the resource.
+    /// Such resources cannot be placed in shared memory.
+    pub fn requires_dedicated(&self) -> bool {
+        self.requires_dedicated
+    }
+
+    /// Whether the resource can be bound to `memory`, ignoring offset and size.
+    pub fn is_compatible(&self, memory: &DeviceMemory) -> bool {
+        self.memory_type_bits & (1 << memory.memory_type_index) != 0
+    }
+}
+
+/// A block of device memory.
+/// Memory created with `DeviceMemory::new` can be shared by several aliased images.
 pub struct DeviceMemory {
     pub(crate) memory: ash::vk::DeviceMemory,
+    pub(crate) memory_type_index: u32,
+    pub(crate) property_flags: MemoryPropertyFlags,
+    pub(crate) allocation_size: u64,
+    pub(crate) non_coherent_atom_size: u64,
+    pub(crate) export_handle_type: Option<ExternalMemoryHandleType>,
 }
 
 impl DeviceMemory {
@@ -14,28 +156,68 @@ impl DeviceMemory {
         device: &ash::Device,
         mem_props: PhysicalDeviceMemoryProperties,
         mem_req: MemoryRequirements,
-    ) -> NxResult<ash::vk::DeviceMemory> {
+        allocate_flags: MemoryAllocateFlags,
+        export_handle_type: Option<ExternalMemoryHandleType>,
+        dedicated_info: Option<MemoryDedicatedAllocateInfo>,
+        required_flags: MemoryPropertyFlags,
+    ) -> NxResult<(ash::vk::DeviceMemory, u32, MemoryPropertyFlags)> {
+        let mut flags_info = MemoryAllocateFlagsInfo::builder().flags(allocate_flags);
+        let mut export_info = ExportMemoryAllocateInfo::builder()
+            .handle_types(export_handle_type.map(|x| x.into()).unwrap_or_default());
+        let mut dedicated_info = dedicated_info;
         let mut info = MemoryAllocateInfo::builder().allocation_size(mem_req.size);
-        let mut mem_found = false;
-
-        for i in 0..mem_props.memory_type_count {
-            if (mem_req.memory_type_bits & (1 << i)) != 0
-                && (mem_props.memory_types[i as usize].property_flags
-                    & MemoryPropertyFlags::HOST_VISIBLE)
-                    .as_raw()
-                    != 0
-            {
-                info.memory_type_index = i;
-                mem_found = true;
-            }
+        if !allocate_flags.is_empty() {
+            info = info.push_next(&mut flags_info);
         }
-
-        if !mem_found {
-            panic!("No suitable memory found");
+        if export_handle_type.is_some() {
+            info = info.push_next(&mut export_info);
+        }
+        if let Some(dedicated_info) = dedicated_info.as_mut() {
+            info = info.push_next(dedicated_info);
+        }
+        // Lazily allocated memory is only preferred, since not every device has it,
+        // and only transient images, which request it, can be bound to it.
+        let lazily_allocated = required_flags.contains(MemoryPropertyFlags::LAZILY_ALLOCATED);
+        let required_flags = required_flags & !MemoryPropertyFlags::LAZILY_ALLOCATED;
+        let mut candidates = (0..mem_props.memory_type_count).filter(|i| {
+            let property_flags = mem_props.memory_types[*i as usize].property_flags;
+            (mem_req.memory_type_bits & (1 << i)) != 0
+                && property_flags.contains(required_flags)
+                && (lazily_allocated
+                    || !property_flags.contains(MemoryPropertyFlags::LAZILY_ALLOCATED))
+        });
+        // Host visible memory is preferably coherent, but some devices only have
+        // non-coherent types, whose writes must be flushed and reads invalidated.
+        let preferred_flags = if required_flags.contains(MemoryPropertyFlags::HOST_VISIBLE) {
+            required_flags | MemoryPropertyFlags::HOST_COHERENT
+        } else if lazily_allocated {
+            required_flags | MemoryPropertyFlags::LAZILY_ALLOCATED
+        } else {
+            required_flags
+        };
+        let memory_type_index = candidates
+            .clone()
+            .rev()
+            .find(|i| {
+                mem_props.memory_types[*i as usize]
+                    .property_flags
+                    .contains(preferred_flags)
+            })
+            .or_else(|| candidates.next_back());
+        match memory_type_index {
+            Some(i) => info.memory_type_index = i,
+            None => {
+                return Err(NxError::UnsupportedFeature(format!(
+                    "no memory type with {:?} satisfies the requirements",
+                    required_flags
+                )))
+            }
         }
 
+        let memory_type_index = info.memory_type_index;
+        let property_flags = mem_props.memory_types[memory_type_index as usize].property_flags;
         match unsafe { device.allocate_memory(&info.build(), None) } {
-            Ok(x) => Ok(x),
+            Ok(x) => Ok((x, memory_type_index, property_flags)),
             Err(e) => match e {
                 ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
                 ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
@@ -44,17 +226,214 @@ impl DeviceMemory {
         }
     }
 
-    pub fn alloc_image_memory(
-        device: &ash::Device,
+    /// Allocate a block of memory that resources can be bound to.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `size` - Size of the memory in bytes.
+    /// * `requirements` - Requirements of a resource that will be bound to the memory,
+    ///   obtained with `Image::memory_requirements` or `Buffer::memory_requirements`.
+    /// * `host_visible` - Whether the memory must be mappable.
+    ///   Otherwise device local memory is used.
+    pub fn new(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        size: u64,
+        requirements: &ResourceMemoryRequirements,
+        host_visible: bool,
+    ) -> NxResult<Self> {
+        let mem_req = MemoryRequirements {
+            size,
+            alignment: 1,
+            memory_type_bits: requirements.memory_type_bits,
+        };
+        let required_flags = if host_visible {
+            MemoryPropertyFlags::HOST_VISIBLE
+        } else {
+            MemoryPropertyFlags::DEVICE_LOCAL
+        };
+        Self::with_requirements(instance, connecter, device, mem_req, required_flags)
+    }
+
+    /// Allocate memory that can be bound to parts of a sparse buffer with `Queue::bind_sparse`.
+    /// The memory is device local when possible and may not be mappable.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `buffer` - Sparse buffer the memory will be bound to.
+    /// * `size` - Size of the memory in bytes. Rounded up to the sparse alignment of the buffer.
+    pub fn for_sparse_buffer(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        buffer: &Buffer,
+        size: u64,
+    ) -> NxResult<Self> {
+        let mut mem_req = buffer
+            .sparse_requirements
+            .ok_or_else(|| NxError::InvalidDescriptor("the buffer is not sparse".to_string()))?;
+        mem_req.size = size.next_multiple_of(mem_req.alignment.max(1));
+        // Sparse resources rarely allow host visible memory. Device local memory is preferred,
+        // but any memory type the buffer allows is used otherwise.
+        let flags = MemoryPropertyFlags::DEVICE_LOCAL;
+        match Self::with_requirements(instance, connecter, device, mem_req, flags) {
+            Err(NxError::UnsupportedFeature(_)) => Self::with_requirements(
+                instance,
+                connecter,
+                device,
+                mem_req,
+                MemoryPropertyFlags::empty(),
+            ),
+            result => result,
+        }
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn with_requirements(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        mem_req: MemoryRequirements,
+        required_flags: MemoryPropertyFlags,
+    ) -> NxResult<Self> {
+        let mem_props = connecter.get_memory_properties(instance);
+        let (memory, memory_type_index, property_flags) = Self::alloc(
+            &device.device,
+            mem_props,
+            mem_req,
+            MemoryAllocateFlags::empty(),
+            None,
+            None,
+            required_flags,
+        )?;
+        Ok(Self {
+            memory,
+            memory_type_index,
+            property_flags,
+            allocation_size: mem_req.size,
+            non_coherent_atom_size: instance
+                .get_properties(connecter.0)
+                .limits
+                .non_coherent_atom_size,
+            export_handle_type: None,
+        })
+    }
+
+    /// Import memory exported by another API or process.
+    /// Requires the `external_memory` device feature.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `handle` - Handle to import.
+    /// * `size` - Size of the exported memory in bytes.
+    /// * `memory_type_index` - Memory type of the exported memory.
+    pub fn import(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        handle: ExternalMemoryHandle,
+        size: u64,
+        memory_type_index: u32,
+    ) -> NxResult<Self> {
+        let loader = device.external_memory.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature("external_memory is not enabled on the device".to_string())
+        })?;
+        loader.validate(handle)?;
+        let mem_props = connecter.get_memory_properties(instance);
+        if memory_type_index >= mem_props.memory_type_count {
+            return Err(NxError::InvalidDescriptor(format!(
+                "memory type {} does not exist",
+                memory_type_index
+            )));
+        }
+        let handle_type = handle.handle_type().into();
+        let mut fd_info = ImportMemoryFdInfoKHR::builder().handle_type(handle_type);
+        let mut win32_info = ImportMemoryWin32HandleInfoKHR::builder().handle_type(handle_type);
+        let info = MemoryAllocateInfo::builder()
+            .allocation_size(size)
+            .memory_type_index(memory_type_index);
+        let info = match handle {
+            ExternalMemoryHandle::OpaqueFd(fd) => {
+                fd_info = fd_info.fd(fd);
+                info.push_next(&mut fd_info)
+            }
+            ExternalMemoryHandle::OpaqueWin32(x) => {
+                win32_info = win32_info.handle(x);
+                info.push_next(&mut win32_info)
+            }
+        };
+        let memory = unsafe { device.device.allocate_memory(&info, None) }?;
+        Ok(Self {
+            memory,
+            memory_type_index,
+            property_flags: mem_props.memory_types[memory_type_index as usize].property_flags,
+            allocation_size: size,
+            non_coherent_atom_size: instance
+                .get_properties(connecter.0)
+                .limits
+                .non_coherent_atom_size,
+            export_handle_type: None,
+        })
+    }
+
+    /// Export the memory as an OS handle.
+    /// The memory must belong to a resource created with `external_memory` in its descriptor.
+    /// The caller owns the returned handle.
+    pub fn export(&self, device: &Device) -> NxResult<ExternalMemoryHandle> {
+        let handle_type = self.export_handle_type.ok_or_else(|| {
+            NxError::InvalidDescriptor("the memory was not allocated as exportable".to_string())
+        })?;
+        let loader = device.external_memory.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature("external_memory is not enabled on the device".to_string())
+        })?;
+        loader.export(self.memory, handle_type)
+    }
+
+    /// Index of the memory type of the allocation.
+    /// Needed to import exported memory elsewhere.
+    pub fn memory_type_index(&self) -> u32 {
+        self.memory_type_index
+    }
+
+    /// Size of the allocation in bytes.
+    pub fn allocation_size(&self) -> u64 {
+        self.allocation_size
+    }
+
+    pub(crate) fn alloc_image_memory(
+        device: &Device,
         image: ash::vk::Image,
         mem_props: PhysicalDeviceMemoryProperties,
-        mem_req: MemoryRequirements,
+        non_coherent_atom_size: u64,
+        export_handle_type: Option<ExternalMemoryHandleType>,
+        dedicated: DedicatedAllocation,
+        required_flags: MemoryPropertyFlags,
     ) -> NxResult<Self> {
-        let memory = match Self::alloc(device, mem_props, mem_req) {
+        let requirements = ResourceMemoryRequirements::of_image(device, image);
+        let mem_req = requirements.to_vk();
+        let dedicated_info = dedicated
+            .resolve(device, &requirements)?
+            .then(|| MemoryDedicatedAllocateInfo::builder().image(image).build());
+        let (memory, memory_type_index, property_flags) = match Self::alloc(
+            &device.device,
+            mem_props,
+            mem_req,
+            MemoryAllocateFlags::empty(),
+            export_handle_type,
+            dedicated_info,
+            required_flags,
+        ) {
             Ok(x) => x,
             Err(e) => return Err(e),
         };
-        match unsafe { device.bind_image_memory(image, memory, 0) } {
+        match unsafe { device.device.bind_image_memory(image, memory, 0) } {
             Ok(_) => {}
             Err(e) => match e {
                 ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
@@ -62,20 +441,45 @@ impl DeviceMemory {
                 _ => Err(NxError::Unknown),
             }?,
         }
-        Ok(Self { memory })
+        Ok(Self {
+            memory,
+            memory_type_index,
+            property_flags,
+            allocation_size: mem_req.size,
+            non_coherent_atom_size,
+            export_handle_type,
+        })
     }
 
-    pub fn alloc_buffer_memory(
-        device: &ash::Device,
+    pub(crate) fn alloc_buffer_memory(
+        device: &Device,
         buffer: ash::vk::Buffer,
         mem_props: PhysicalDeviceMemoryProperties,
-        mem_req: MemoryRequirements,
+        non_coherent_atom_size: u64,
+        allocate_flags: MemoryAllocateFlags,
+        export_handle_type: Option<ExternalMemoryHandleType>,
+        dedicated: DedicatedAllocation,
     ) -> NxResult<Self> {
-        let memory = match Self::alloc(device, mem_props, mem_req) {
+        let requirements = ResourceMemoryRequirements::of_buffer(device, buffer);
+        let mem_req = requirements.to_vk();
+        let dedicated_info = dedicated.resolve(device, &requirements)?.then(|| {
+            MemoryDedicatedAllocateInfo::builder()
+                .buffer(buffer)
+                .build()
+        });
+        let (memory, memory_type_index, property_flags) = match Self::alloc(
+            &device.device,
+            mem_props,
+            mem_req,
+            allocate_flags,
+            export_handle_type,
+            dedicated_info,
+            MemoryPropertyFlags::HOST_VISIBLE,
+        ) {
             Ok(x) => x,
             Err(e) => return Err(e),
         };
-        match unsafe { device.bind_buffer_memory(buffer, memory, 0) } {
+        match unsafe { device.device.bind_buffer_memory(buffer, memory, 0) } {
             Ok(_) => {}
             Err(e) => match e {
                 ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
@@ -83,7 +487,14 @@ impl DeviceMemory {
                 _ => Err(NxError::Unknown),
             }?,
         }
-        Ok(Self { memory })
+        Ok(Self {
+            memory,
+            memory_type_index,
+            property_flags,
+            allocation_size: mem_req.size,
+            non_coherent_atom_size,
+            export_handle_type,
+        })
     }
 
     pub fn size(&self, device: &Device) -> u64 {
@@ -91,10 +502,14 @@ impl DeviceMemory {
     }
 
     pub fn map(&self, device: &Device, size: u64) -> NxResult<*mut c_void> {
+        self.map_at(device, 0, size)
+    }
+
+    fn map_at(&self, device: &Device, offset: u64, size: u64) -> NxResult<*mut c_void> {
         match unsafe {
             device
                 .device
-                .map_memory(self.memory, 0, size, MemoryMapFlags::empty())
+                .map_memory(self.memory, offset, size, MemoryMapFlags::empty())
         } {
             Ok(x) => Ok(x),
             Err(e) => match e {
@@ -111,6 +526,174 @@ impl DeviceMemory {
             device.device.unmap_memory(self.memory);
         }
     }
+
+    /// Whether the memory is host coherent.
+    pub fn is_coherent(&self) -> bool {
+        self.property_flags
+            .contains(MemoryPropertyFlags::HOST_COHERENT)
+    }
+
+    /// Make host writes to `offset..offset + size` of mapped memory visible to the device.
+    /// Does nothing for coherent memory.
+    #[doc(hidden)]
+    pub(crate) fn flush_range(&self, device: &Device, offset: u64, size: u64) -> NxResult<()> {
+        if self.is_coherent() || size == 0 {
+            return Ok(());
+        }
+        let range = self.aligned_range(offset, size);
+        unsafe { device.device.flush_mapped_memory_ranges(&[range]) }?;
+        Ok(())
+    }
+
+    /// Make device writes to `offset..offset + size` of mapped memory visible to the host.
+    /// Does nothing for coherent memory.
+    #[doc(hidden)]
+    pub(crate) fn invalidate_range(&self, device: &Device, offset: u64, size: u64) -> NxResult<()> {
+        if self.is_coherent() || size == 0 {
+            return Ok(());
+        }
+        let range = self.aligned_range(offset, size);
+        unsafe { device.device.invalidate_mapped_memory_ranges(&[range]) }?;
+        Ok(())
+    }
+
+    /// Expands a range to a multiple of `nonCoherentAtomSize`, as flushes require.
+    fn aligned_range(&self, offset: u64, size: u64) -> MappedMemoryRange {
+        let (start, end) = self.aligned_bounds(offset, size);
+        MappedMemoryRange::builder()
+            .memory(self.memory)
+            .offset(start)
+            .size(end - start)
+            .build()
+    }
+
+    /// Map the whole memory and return a guard that unmaps it when dropped.
+    pub(crate) fn map_guard<'a>(&'a self, device: &'a Device) -> NxResult<MappedMemory<'a>> {
+        let ptr = self.map(device, ash::vk::WHOLE_SIZE)?;
+        Ok(MappedMemory {
+            device,
+            memory: self,
+            ptr,
+            offset: 0,
+            size: self.allocation_size,
+        })
+    }
+
+    /// Map the part of the memory containing `offset..offset + size` and return a guard
+    /// that unmaps it when dropped.
+    /// The mapped range is expanded to a multiple of `nonCoherentAtomSize`,
+    /// so the guard may start before `offset`.
+    pub(crate) fn map_range_guard<'a>(
+        &'a self,
+        device: &'a Device,
+        offset: u64,
+        size: u64,
+    ) -> NxResult<MappedMemory<'a>> {
+        let (start, end) = self.aligned_bounds(offset, size);
+        let ptr = self.map_at(device, start, end - start)?;
+        Ok(MappedMemory {
+            device,
+            memory: self,
+            ptr,
+            offset: start,
+            size: end - start,
+        })
+    }
+
+    /// Expands a range to a multiple of `nonCoherentAtomSize`, or to the end of the memory.
+    fn aligned_bounds(&self, offset: u64, size: u64) -> (u64, u64) {
+        let atom = self.non_coherent_atom_size.max(1);
+        let start = offset / atom * atom;
+        let end = offset.saturating_add(size);
+        let end = if end >= self.allocation_size {
+            self.allocation_size
+        } else {
+            end.div_ceil(atom) * atom
+        };
+        (start, end.min(self.allocation_size))
+    }
+}
+
+/// Memory mapped to the host address space.
+/// The memory is unmapped when the guard is dropped.
+///
+/// Nothing is flushed or invalidated implicitly. If the memory is not coherent,
+/// call `flush` after writing and `invalidate` before reading.
+pub struct MappedMemory<'a> {
+    device: &'a Device,
+    memory: &'a DeviceMemory,
+    ptr: *mut c_void,
+    /// Offset of the mapped range in the memory.
+    offset: u64,
+    size: u64,
+}
+
+impl<'a> MappedMemory<'a> {
+    /// Get a pointer to the beginning of the mapped memory.
+    pub fn as_ptr(&self) -> *mut c_void {
+        self.ptr
+    }
+
+    /// Size of the mapped memory in bytes.
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Offset of the mapped range in the memory.
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Whether the mapped memory is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the memory is host coherent.
+    /// If so, `flush` and `invalidate` do nothing.
+    pub fn is_coherent(&self) -> bool {
+        self.memory.is_coherent()
+    }
+
+    /// Get the mapped memory as a byte slice.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len() as usize) }
+    }
+
+    /// Get the mapped memory as a mutable byte slice.
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr as *mut u8, self.len() as usize) }
+    }
+
+    /// Make host writes to the range visible to the device.
+    /// The range is expanded to a multiple of `nonCoherentAtomSize`.
+    /// # Arguments
+    ///
+    /// * `offset` - Offset in bytes from the beginning of the mapped memory.
+    /// * `size` - Size of the range in bytes.
+    pub fn flush(&self, offset: u64, size: u64) -> NxResult<()> {
+        let size = size.min(self.size.saturating_sub(offset));
+        self.memory
+            .flush_range(self.device, self.offset + offset, size)
+    }
+
+    /// Make device writes to the range visible to the host.
+    /// The range is expanded to a multiple of `nonCoherentAtomSize`.
+    /// # Arguments
+    ///
+    /// * `offset` - Offset in bytes from the beginning of the mapped memory.
+    /// * `size` - Size of the range in bytes.
+    pub fn invalidate(&self, offset: u64, size: u64) -> NxResult<()> {
+        let size = size.min(self.size.saturating_sub(offset));
+        self.memory
+            .invalidate_range(self.device, self.offset + offset, size)
+    }
+}
+
+impl Drop for MappedMemory<'_> {
+    fn drop(&mut self) {
+        self.memory.unmap(self.device);
+    }
 }
 
 impl Destroy for DeviceMemory {
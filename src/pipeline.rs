@@ -1,20 +1,25 @@
 use std::ffi::CString;
 
 use ash::vk::{
-    ColorComponentFlags, CullModeFlags, DescriptorPool, DescriptorPoolCreateInfo,
-    DescriptorPoolSize, DescriptorSet, DescriptorSetAllocateInfo, DescriptorSetLayoutCreateInfo,
-    DescriptorType, Extent2D, Format, FrontFace, GraphicsPipelineCreateInfo, Offset2D,
+    BlendFactor, BlendOp, ColorComponentFlags, CopyDescriptorSet, CullModeFlags,
+    DescriptorBindingFlags, DescriptorBufferInfo, DescriptorImageInfo, DescriptorPool,
+    DescriptorPoolCreateFlags, DescriptorPoolCreateInfo, DescriptorPoolSize, DescriptorSet,
+    DescriptorSetAllocateInfo, DescriptorSetLayoutBindingFlagsCreateInfo,
+    DescriptorSetLayoutCreateFlags, DescriptorSetLayoutCreateInfo, DescriptorType, DynamicState,
+    Extent2D, Format, GraphicsPipelineCreateInfo, ImageAspectFlags, ImageUsageFlags, Offset2D,
     PipelineCache, PipelineColorBlendAttachmentState, PipelineColorBlendStateCreateInfo,
+    PipelineDepthStencilStateCreateInfo, PipelineDynamicStateCreateInfo,
     PipelineInputAssemblyStateCreateInfo, PipelineLayoutCreateInfo,
     PipelineMultisampleStateCreateInfo, PipelineRasterizationStateCreateInfo,
-    PipelineShaderStageCreateInfo, PipelineVertexInputStateCreateInfo,
-    PipelineViewportStateCreateInfo, PolygonMode, Rect2D, SampleCountFlags,
+    PipelineRenderingCreateInfo, PipelineShaderStageCreateInfo, PipelineVertexInputStateCreateInfo,
+    PipelineViewportStateCreateInfo, PolygonMode, Rect2D, SampleCountFlags, ShaderStageFlags,
     VertexInputAttributeDescription, VertexInputBindingDescription, VertexInputRate, Viewport,
+    WriteDescriptorSet,
 };
 
 use crate::{
-    Buffer, Destroy, Device, Instance, NxError, NxResult, RenderPass, ShaderStage,
-    ShaderStageDescriptor,
+    Buffer, Destroy, Device, Extent2d, ImageFormat, ImageLayout, ImageView, Instance, NxError,
+    NxResult, RenderPass, Sampler, Shader, ShaderStage, ShaderStageDescriptor, Spirv,
 };
 
 #[derive(Clone,Copy,Debug,Eq,PartialEq)]
@@ -36,6 +41,118 @@ impl From<crate::PrimitiveTopology> for ash::vk::PrimitiveTopology {
     }
 }
 
+/// Which triangles are discarded based on the direction they face.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+    FrontAndBack,
+}
+
+impl From<CullMode> for CullModeFlags {
+    fn from(value: CullMode) -> Self {
+        match value {
+            CullMode::None => CullModeFlags::NONE,
+            CullMode::Front => CullModeFlags::FRONT,
+            CullMode::Back => CullModeFlags::BACK,
+            CullMode::FrontAndBack => CullModeFlags::FRONT_AND_BACK,
+        }
+    }
+}
+
+/// Winding order of the vertices of front-facing triangles.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FrontFace {
+    CounterClockwise,
+    Clockwise,
+}
+
+impl From<FrontFace> for ash::vk::FrontFace {
+    fn from(value: FrontFace) -> Self {
+        match value {
+            FrontFace::CounterClockwise => ash::vk::FrontFace::COUNTER_CLOCKWISE,
+            FrontFace::Clockwise => ash::vk::FrontFace::CLOCKWISE,
+        }
+    }
+}
+
+/// Comparison used by the depth test, between the new depth and the stored depth.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompareOp {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+impl From<CompareOp> for ash::vk::CompareOp {
+    fn from(value: CompareOp) -> Self {
+        match value {
+            CompareOp::Never => ash::vk::CompareOp::NEVER,
+            CompareOp::Less => ash::vk::CompareOp::LESS,
+            CompareOp::Equal => ash::vk::CompareOp::EQUAL,
+            CompareOp::LessOrEqual => ash::vk::CompareOp::LESS_OR_EQUAL,
+            CompareOp::Greater => ash::vk::CompareOp::GREATER,
+            CompareOp::NotEqual => ash::vk::CompareOp::NOT_EQUAL,
+            CompareOp::GreaterOrEqual => ash::vk::CompareOp::GREATER_OR_EQUAL,
+            CompareOp::Always => ash::vk::CompareOp::ALWAYS,
+        }
+    }
+}
+
+/// Pipeline state that is set while recording instead of when the pipeline is created.
+/// Requires the `extended_dynamic_state` device feature.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PipelineDynamicState {
+    /// Set with `CommandRecorder::set_cull_mode`.
+    CullMode,
+    /// Set with `CommandRecorder::set_front_face`.
+    FrontFace,
+    /// Set with `CommandRecorder::set_primitive_topology`.
+    PrimitiveTopology,
+    /// Set with `CommandRecorder::set_depth_test_enable`.
+    DepthTestEnable,
+    /// Set with `CommandRecorder::set_depth_write_enable`.
+    DepthWriteEnable,
+    /// Set with `CommandRecorder::set_depth_compare_op`.
+    DepthCompareOp,
+}
+
+impl PipelineDynamicState {
+    #[doc(hidden)]
+    /// Bit of the state in `Pipeline::dynamic_states`.
+    pub(crate) const fn mask(self) -> u32 {
+        1 << self as u32
+    }
+
+    #[doc(hidden)]
+    /// Whether the state needs depth/stencil state in the pipeline.
+    const fn is_depth(self) -> bool {
+        matches!(
+            self,
+            Self::DepthTestEnable | Self::DepthWriteEnable | Self::DepthCompareOp
+        )
+    }
+}
+
+impl From<PipelineDynamicState> for DynamicState {
+    fn from(value: PipelineDynamicState) -> Self {
+        match value {
+            PipelineDynamicState::CullMode => DynamicState::CULL_MODE,
+            PipelineDynamicState::FrontFace => DynamicState::FRONT_FACE,
+            PipelineDynamicState::PrimitiveTopology => DynamicState::PRIMITIVE_TOPOLOGY,
+            PipelineDynamicState::DepthTestEnable => DynamicState::DEPTH_TEST_ENABLE,
+            PipelineDynamicState::DepthWriteEnable => DynamicState::DEPTH_WRITE_ENABLE,
+            PipelineDynamicState::DepthCompareOp => DynamicState::DEPTH_COMPARE_OP,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum BindPoint {
     Graphics,
@@ -49,9 +166,64 @@ impl Into<ash::vk::PipelineBindPoint> for BindPoint {
     }
 }
 
+/// Describes a range of push constants accessible from the given shader stages.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PushConstantRange {
+    pub(crate) stage: ShaderStageFlags,
+    pub(crate) offset: u32,
+    pub(crate) size: u32,
+}
+
+impl PushConstantRange {
+    #[inline]
+    pub fn empty() -> Self {
+        Self {
+            stage: ShaderStageFlags::VERTEX,
+            offset: 0,
+            size: 0,
+        }
+    }
+
+    #[inline]
+    pub fn stage(mut self, stage: ShaderStage) -> Self {
+        self.stage = stage.into();
+        self
+    }
+
+    /// Makes the range visible to an additional shader stage.
+    #[inline]
+    pub fn add_stage(mut self, stage: ShaderStage) -> Self {
+        self.stage |= stage.into();
+        self
+    }
+
+    #[inline]
+    pub fn offset(mut self, offset: u32) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    #[inline]
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+}
+
+impl From<PushConstantRange> for ash::vk::PushConstantRange {
+    fn from(value: PushConstantRange) -> Self {
+        ash::vk::PushConstantRange::builder()
+            .stage_flags(value.stage)
+            .offset(value.offset)
+            .size(value.size)
+            .build()
+    }
+}
+
 pub struct PipelineLayoutDescriptor<'a> {
     renderpass: Option<&'a RenderPass>,
-    set_layout_descriptor: Option<&'a ResourceLayout>,
+    set_layouts: Vec<&'a ResourceLayout>,
+    push_constant_ranges: &'a [PushConstantRange],
 }
 
 impl<'a> PipelineLayoutDescriptor<'a> {
@@ -59,13 +231,28 @@ impl<'a> PipelineLayoutDescriptor<'a> {
     pub fn empty() -> Self {
         Self {
             renderpass: None,
-            set_layout_descriptor: None,
+            set_layouts: vec![],
+            push_constant_ranges: &[],
         }
     }
 
+    #[inline]
+    pub fn push_constant_ranges(mut self, ranges: &'a [PushConstantRange]) -> Self {
+        self.push_constant_ranges = ranges;
+        self
+    }
+
+    /// Uses `resource` as the only descriptor set, set 0.
     #[inline]
     pub fn resource(mut self, resource: &'a ResourceLayout) -> Self {
-        self.set_layout_descriptor = Some(resource);
+        self.set_layouts = vec![resource];
+        self
+    }
+
+    /// Uses `resources` as the descriptor sets, in order of the set numbers.
+    #[inline]
+    pub fn resources(mut self, resources: &[&'a ResourceLayout]) -> Self {
+        self.set_layouts = resources.to_vec();
         self
     }
 
@@ -107,6 +294,17 @@ pub enum DataFormat {
     R32G32B32A32SFloat,
 }
 
+impl DataFormat {
+    /// Size of one element in bytes.
+    pub const fn size(&self) -> usize {
+        match self {
+            DataFormat::R32G32SFloat => 8,
+            DataFormat::R32G32B32SFloat => 12,
+            DataFormat::R32G32B32A32SFloat => 16,
+        }
+    }
+}
+
 impl Into<Format> for DataFormat {
     fn into(self) -> Format {
         match self {
@@ -179,19 +377,43 @@ impl<'a> PipelineVertexInputDescriptor<'a> {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ResourceType {
     UniformBuffer,
+    StorageBuffer,
+    CombinedImageSampler,
+    SampledImage,
+    Sampler,
+    /// Image read and written by shaders without a sampler, in `ImageLayout::General`.
+    StorageImage,
+}
+
+impl ResourceType {
+    #[doc(hidden)]
+    pub(crate) const ALL: [ResourceType; 6] = [
+        ResourceType::UniformBuffer,
+        ResourceType::StorageBuffer,
+        ResourceType::CombinedImageSampler,
+        ResourceType::SampledImage,
+        ResourceType::Sampler,
+        ResourceType::StorageImage,
+    ];
 }
 
 impl From<ResourceType> for DescriptorType {
     fn from(value: ResourceType) -> Self {
         match value {
             ResourceType::UniformBuffer => DescriptorType::UNIFORM_BUFFER,
+            ResourceType::StorageBuffer => DescriptorType::STORAGE_BUFFER,
+            ResourceType::CombinedImageSampler => DescriptorType::COMBINED_IMAGE_SAMPLER,
+            ResourceType::SampledImage => DescriptorType::SAMPLED_IMAGE,
+            ResourceType::Sampler => DescriptorType::SAMPLER,
+            ResourceType::StorageImage => DescriptorType::STORAGE_IMAGE,
         }
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct ResourcePoolSize {
     resource_type: ResourceType,
     count: u32,
@@ -204,11 +426,58 @@ impl ResourcePoolSize {
             count: 1,
         }
     }
+
+    pub fn resource_type(mut self, resource_type: ResourceType) -> Self {
+        self.resource_type = resource_type;
+        self
+    }
+
+    /// Number of resources of the type the pool can hold.
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    /// Tally the pool sizes and the number of sets needed to allocate
+    /// each layout the given number of times.
+    /// Returns `NxError::InvalidDescriptor` for push descriptor layouts,
+    /// which are never allocated from a pool.
+    pub fn for_layouts(layouts: &[(&ResourceLayout, u32)]) -> NxResult<(Vec<Self>, u32)> {
+        let overflow = || {
+            NxError::InvalidDescriptor("the layouts need more than u32::MAX resources".to_string())
+        };
+        let mut counts = [0u32; ResourceType::ALL.len()];
+        let mut max_sets = 0u32;
+        for (layout, sets) in layouts {
+            layout.validate_allocatable()?;
+            max_sets = max_sets.checked_add(*sets).ok_or_else(overflow)?;
+            for (count, layout_count) in counts.iter_mut().zip(layout.counts) {
+                let needed = layout_count.checked_mul(*sets).ok_or_else(overflow)?;
+                *count = count.checked_add(needed).ok_or_else(overflow)?;
+            }
+        }
+        if max_sets == 0 {
+            return Err(NxError::InvalidDescriptor(
+                "at least one resource must be allocated from the pool".to_string(),
+            ));
+        }
+        let pool_sizes = ResourceType::ALL
+            .into_iter()
+            .zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|(resource_type, count)| Self {
+                resource_type,
+                count,
+            })
+            .collect();
+        Ok((pool_sizes, max_sets))
+    }
 }
 
 pub struct ResourcePoolDescriptor<'a> {
     pool_sizes: &'a [ResourcePoolSize],
     max_sets: u32,
+    update_after_bind: bool,
 }
 
 impl<'a> ResourcePoolDescriptor<'a> {
@@ -216,6 +485,7 @@ impl<'a> ResourcePoolDescriptor<'a> {
         Self {
             pool_sizes: &[],
             max_sets: 1,
+            update_after_bind: false,
         }
     }
 
@@ -228,6 +498,12 @@ impl<'a> ResourcePoolDescriptor<'a> {
         self.max_sets = max_sets;
         self
     }
+
+    /// Allows allocating resources whose layout has `update_after_bind` bindings.
+    pub fn update_after_bind(mut self, update_after_bind: bool) -> Self {
+        self.update_after_bind = update_after_bind;
+        self
+    }
 }
 
 pub struct ResourcePool {
@@ -236,8 +512,23 @@ pub struct ResourcePool {
 
 impl ResourcePool {
     pub fn new(device: &Device, descriptor: &ResourcePoolDescriptor) -> Self {
-        let pool_sizes = descriptor
-            .pool_sizes
+        Self::create(
+            device,
+            descriptor.pool_sizes,
+            descriptor.max_sets,
+            descriptor.update_after_bind,
+        )
+        .unwrap()
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn create(
+        device: &Device,
+        pool_sizes: &[ResourcePoolSize],
+        max_sets: u32,
+        update_after_bind: bool,
+    ) -> NxResult<Self> {
+        let pool_sizes = pool_sizes
             .iter()
             .map(|x| {
                 DescriptorPoolSize::builder()
@@ -246,12 +537,36 @@ impl ResourcePool {
                     .build()
             })
             .collect::<Vec<DescriptorPoolSize>>();
+        let flags = if update_after_bind {
+            DescriptorPoolCreateFlags::UPDATE_AFTER_BIND
+        } else {
+            DescriptorPoolCreateFlags::empty()
+        };
         let create_info = DescriptorPoolCreateInfo::builder()
-            .max_sets(descriptor.max_sets)
+            .flags(flags)
+            .max_sets(max_sets)
             .pool_sizes(&pool_sizes)
             .build();
-        let pool = unsafe { device.device.create_descriptor_pool(&create_info, None) }.unwrap();
-        Self { pool }
+        let pool = match unsafe { device.device.create_descriptor_pool(&create_info, None) } {
+            Ok(x) => x,
+            Err(e) => match e {
+                ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                _ => Err(NxError::Unknown),
+            }?,
+        };
+        Ok(Self { pool })
+    }
+
+    /// Create a pool sized to allocate each layout the given number of times.
+    /// # Arguments
+    ///
+    /// * `device` - Device to create the pool on.
+    /// * `layouts` - Each layout with the number of resources that will be allocated with it.
+    pub fn for_layouts(device: &Device, layouts: &[(&ResourceLayout, u32)]) -> NxResult<Self> {
+        let (pool_sizes, max_sets) = ResourcePoolSize::for_layouts(layouts)?;
+        let update_after_bind = layouts.iter().any(|(x, _)| x.update_after_bind);
+        Self::create(device, &pool_sizes, max_sets, update_after_bind)
     }
 }
 
@@ -267,7 +582,7 @@ impl Destroy for ResourcePool {
 
 #[derive(Clone, Copy)]
 pub struct ResourceBufferDescriptor<'a> {
-    pub(crate) buffer: &'a Buffer,
+    pub(crate) buffer: Option<&'a Buffer>,
     pub(crate) offset: u64,
     pub(crate) range: usize,
 }
@@ -276,11 +591,68 @@ impl<'a> ResourceBufferDescriptor<'a> {
     pub fn new<T>(buffer: &'a Buffer) -> Self {
         let range = std::mem::size_of::<T>();
         Self {
-            buffer,
+            buffer: Some(buffer),
             offset: 0,
             range,
         }
     }
+
+    /// A write of no buffer. Shaders read zeros from it and writes are discarded.
+    /// Requires the `null_descriptor` device feature.
+    pub fn null() -> Self {
+        Self {
+            buffer: None,
+            offset: 0,
+            range: 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ResourceImageDescriptor<'a> {
+    pub(crate) image_view: Option<&'a ImageView>,
+    pub(crate) layout: ImageLayout,
+    pub(crate) sampler: Option<&'a Sampler>,
+}
+
+impl<'a> ResourceImageDescriptor<'a> {
+    /// Create an image write.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_view` - View to bind.
+    /// * `layout` - Layout the image is in when the shaders access it.
+    pub fn new(image_view: &'a ImageView, layout: ImageLayout) -> Self {
+        Self {
+            image_view: Some(image_view),
+            layout,
+            sampler: None,
+        }
+    }
+
+    /// Create a write of a storage image, which shaders access in `ImageLayout::General`.
+    /// The image must be transitioned to that layout, for example with
+    /// `ResourceState::compute_storage`, before the shaders run.
+    pub fn storage(image_view: &'a ImageView) -> Self {
+        Self::new(image_view, ImageLayout::General)
+    }
+
+    /// A write of no image. Shaders read zeros from it and writes are discarded.
+    /// Requires the `null_descriptor` device feature.
+    pub fn null() -> Self {
+        Self {
+            image_view: None,
+            layout: ImageLayout::Undefined,
+            sampler: None,
+        }
+    }
+
+    /// Sampler to bind with the view.
+    /// Not needed for bindings that have immutable samplers.
+    pub fn sampler(mut self, sampler: &'a Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
 }
 
 pub struct ResourceUpdateDescriptor<'a> {
@@ -289,6 +661,7 @@ pub struct ResourceUpdateDescriptor<'a> {
     pub(crate) array_element: u32,
     pub(crate) resource_type: ResourceType,
     pub(crate) buffer_desc: &'a [ResourceBufferDescriptor<'a>],
+    pub(crate) image_desc: &'a [ResourceImageDescriptor<'a>],
 }
 
 impl<'a> ResourceUpdateDescriptor<'a> {
@@ -299,13 +672,284 @@ impl<'a> ResourceUpdateDescriptor<'a> {
             array_element: 0,
             resource_type: ResourceType::UniformBuffer,
             buffer_desc: &[],
+            image_desc: &[],
         }
     }
 
+    pub fn binding(mut self, binding: u32) -> Self {
+        self.binding = binding;
+        self
+    }
+
+    pub fn array_element(mut self, array_element: u32) -> Self {
+        self.array_element = array_element;
+        self
+    }
+
+    pub fn resource_type(mut self, resource_type: ResourceType) -> Self {
+        self.resource_type = resource_type;
+        self
+    }
+
     pub fn buffer_desc(mut self, buffer_desc: &'a [ResourceBufferDescriptor]) -> Self {
         self.buffer_desc = buffer_desc;
         self
     }
+
+    /// Images to write, for sampler and image resource types.
+    pub fn image_desc(mut self, image_desc: &'a [ResourceImageDescriptor]) -> Self {
+        self.image_desc = image_desc;
+        self
+    }
+}
+
+/// Stores information about a write pushed by `CommandRecorder::push_resources`.
+pub struct ResourcePushDescriptor<'a> {
+    binding: u32,
+    array_element: u32,
+    resource_type: ResourceType,
+    buffer_desc: &'a [ResourceBufferDescriptor<'a>],
+    image_desc: &'a [ResourceImageDescriptor<'a>],
+}
+
+impl<'a> ResourcePushDescriptor<'a> {
+    /// Initializes a write of a uniform buffer to `binding`.
+    pub fn new(binding: u32) -> Self {
+        Self {
+            binding,
+            array_element: 0,
+            resource_type: ResourceType::UniformBuffer,
+            buffer_desc: &[],
+            image_desc: &[],
+        }
+    }
+
+    /// First array element of the binding to write. Defaults to 0.
+    pub fn array_element(mut self, array_element: u32) -> Self {
+        self.array_element = array_element;
+        self
+    }
+
+    /// Type of the binding, which must match the layout. Defaults to `UniformBuffer`.
+    pub fn resource_type(mut self, resource_type: ResourceType) -> Self {
+        self.resource_type = resource_type;
+        self
+    }
+
+    /// Buffers to write, for buffer resource types.
+    pub fn buffer_desc(mut self, buffer_desc: &'a [ResourceBufferDescriptor]) -> Self {
+        self.buffer_desc = buffer_desc;
+        self
+    }
+
+    /// Images to write, for sampler and image resource types.
+    pub fn image_desc(mut self, image_desc: &'a [ResourceImageDescriptor]) -> Self {
+        self.image_desc = image_desc;
+        self
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn pending_write(&self) -> PendingWrite {
+        PendingWrite::new(
+            DescriptorSet::null(),
+            self.binding,
+            self.array_element,
+            self.resource_type,
+            self.buffer_desc,
+            self.image_desc,
+        )
+    }
+}
+
+/// Stores information needed to copy descriptors from one Resource to another.
+pub struct ResourceCopyDescriptor<'a> {
+    src: &'a Resource,
+    dst: &'a Resource,
+    src_binding: u32,
+    src_array_element: u32,
+    dst_binding: u32,
+    dst_array_element: u32,
+    count: u32,
+}
+
+impl<'a> ResourceCopyDescriptor<'a> {
+    /// Copies one descriptor from binding 0 of `src` to binding 0 of `dst`.
+    pub fn new(src: &'a Resource, dst: &'a Resource) -> Self {
+        Self {
+            src,
+            dst,
+            src_binding: 0,
+            src_array_element: 0,
+            dst_binding: 0,
+            dst_array_element: 0,
+            count: 1,
+        }
+    }
+
+    /// Binding and first array element to copy from.
+    pub fn src_binding(mut self, binding: u32, array_element: u32) -> Self {
+        self.src_binding = binding;
+        self.src_array_element = array_element;
+        self
+    }
+
+    /// Binding and first array element to copy to.
+    pub fn dst_binding(mut self, binding: u32, array_element: u32) -> Self {
+        self.dst_binding = binding;
+        self.dst_array_element = array_element;
+        self
+    }
+
+    /// Number of descriptors to copy.
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+}
+
+/// A write recorded by `DescriptorWriteBatch` or pushed by `CommandRecorder::push_resources`,
+/// owning the infos it points to.
+pub(crate) struct PendingWrite {
+    set: DescriptorSet,
+    binding: u32,
+    array_element: u32,
+    descriptor_type: DescriptorType,
+    buffers: Vec<DescriptorBufferInfo>,
+    images: Vec<DescriptorImageInfo>,
+}
+
+impl PendingWrite {
+    fn new(
+        set: DescriptorSet,
+        binding: u32,
+        array_element: u32,
+        resource_type: ResourceType,
+        buffer_desc: &[ResourceBufferDescriptor],
+        image_desc: &[ResourceImageDescriptor],
+    ) -> Self {
+        let buffers = buffer_desc
+            .iter()
+            .map(|x| match x.buffer {
+                Some(buffer) => DescriptorBufferInfo::builder()
+                    .buffer(buffer.buffer)
+                    .offset(x.offset)
+                    .range(x.range as u64)
+                    .build(),
+                None => DescriptorBufferInfo::builder()
+                    .buffer(ash::vk::Buffer::null())
+                    .range(ash::vk::WHOLE_SIZE)
+                    .build(),
+            })
+            .collect::<Vec<DescriptorBufferInfo>>();
+        let images = image_desc
+            .iter()
+            .map(|x| {
+                DescriptorImageInfo::builder()
+                    .image_view(
+                        x.image_view
+                            .map_or(ash::vk::ImageView::null(), |x| x.image_view),
+                    )
+                    .image_layout(x.layout.into())
+                    .sampler(x.sampler.map_or(ash::vk::Sampler::null(), |x| x.sampler))
+                    .build()
+            })
+            .collect::<Vec<DescriptorImageInfo>>();
+        Self {
+            set,
+            binding,
+            array_element,
+            descriptor_type: resource_type.into(),
+            buffers,
+            images,
+        }
+    }
+
+    /// The write pointing to the infos, which must outlive it.
+    pub(crate) fn to_vk(&self) -> WriteDescriptorSet {
+        let write = WriteDescriptorSet::builder()
+            .dst_set(self.set)
+            .dst_binding(self.binding)
+            .dst_array_element(self.array_element)
+            .descriptor_type(self.descriptor_type);
+        if self.images.is_empty() {
+            write.buffer_info(&self.buffers)
+        } else {
+            write.image_info(&self.images)
+        }
+        .build()
+    }
+}
+
+/// Collects resource writes and copies, possibly to many Resources,
+/// and applies them with a single `vkUpdateDescriptorSets` call.
+/// Useful when initializing many material resources at load time.
+#[derive(Default)]
+pub struct DescriptorWriteBatch {
+    writes: Vec<PendingWrite>,
+    copies: Vec<CopyDescriptorSet>,
+}
+
+impl DescriptorWriteBatch {
+    /// Initializes an empty batch.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the write described by `descriptor`.
+    pub fn write(&mut self, descriptor: &ResourceUpdateDescriptor) -> &mut Self {
+        self.writes.push(PendingWrite::new(
+            descriptor.resource.descriptor_set,
+            descriptor.binding,
+            descriptor.array_element,
+            descriptor.resource_type,
+            descriptor.buffer_desc,
+            descriptor.image_desc,
+        ));
+        self
+    }
+
+    /// Adds the copy described by `descriptor`.
+    pub fn copy(&mut self, descriptor: &ResourceCopyDescriptor) -> &mut Self {
+        self.copies.push(
+            CopyDescriptorSet::builder()
+                .src_set(descriptor.src.descriptor_set)
+                .src_binding(descriptor.src_binding)
+                .src_array_element(descriptor.src_array_element)
+                .dst_set(descriptor.dst.descriptor_set)
+                .dst_binding(descriptor.dst_binding)
+                .dst_array_element(descriptor.dst_array_element)
+                .descriptor_count(descriptor.count)
+                .build(),
+        );
+        self
+    }
+
+    /// Get the number of writes and copies added since the last `flush`.
+    pub fn len(&self) -> usize {
+        self.writes.len() + self.copies.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Applies every write, then every copy, and empties the batch.
+    /// The Resources must not be in use by the GPU.
+    pub fn flush(&mut self, device: &Device) {
+        if self.is_empty() {
+            return;
+        }
+        let writes = self
+            .writes
+            .iter()
+            .map(PendingWrite::to_vk)
+            .collect::<Vec<WriteDescriptorSet>>();
+        unsafe {
+            device.device.update_descriptor_sets(&writes, &self.copies);
+        }
+        self.writes.clear();
+        self.copies.clear();
+    }
 }
 
 pub struct Resource {
@@ -314,20 +958,56 @@ pub struct Resource {
 }
 
 impl Resource {
-    pub fn allocate(device: &Device, pool: &ResourcePool, layout: &ResourceLayout) -> Vec<Self> {
+    /// Allocate a resource with `layout` from `pool`.
+    /// Returns `NxError::InvalidDescriptor` for push descriptor layouts,
+    /// whose resources are written with `CommandRecorder::push_resources` instead.
+    pub fn allocate(
+        device: &Device,
+        pool: &ResourcePool,
+        layout: &ResourceLayout,
+    ) -> NxResult<Vec<Self>> {
+        layout.validate_allocatable()?;
         let alloc_info = DescriptorSetAllocateInfo::builder()
             .set_layouts(&[layout.inner])
             .descriptor_pool(pool.pool)
             .build();
-        let descriptor_set =
-            unsafe { device.device.allocate_descriptor_sets(&alloc_info) }.unwrap();
-        descriptor_set
+        let descriptor_set = unsafe { device.device.allocate_descriptor_sets(&alloc_info) }?;
+        Ok(descriptor_set
             .iter()
             .map(|x| Self {
                 descriptor_set: *x,
                 pool: pool.pool,
             })
-            .collect()
+            .collect())
+    }
+
+    /// Writes `image_view` to a `ResourceType::StorageImage` binding.
+    /// The image must have been created with `ImageUsage::Storage` and be transitioned to
+    /// `ImageLayout::General`, for example with `ResourceState::compute_storage`,
+    /// before the shaders run.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `binding` - Binding of the storage image.
+    /// * `image_view` - View of the image.
+    pub fn bind_storage_image(
+        &self,
+        device: &Device,
+        binding: u32,
+        image_view: &ImageView,
+    ) -> NxResult<()> {
+        if !image_view.usage.contains(ImageUsageFlags::STORAGE) {
+            return Err(NxError::InvalidDescriptor(
+                "the image was not created with ImageUsage::Storage".to_string(),
+            ));
+        }
+        device.update_resource(
+            &ResourceUpdateDescriptor::new(self)
+                .binding(binding)
+                .resource_type(ResourceType::StorageImage)
+                .image_desc(&[ResourceImageDescriptor::storage(image_view)]),
+        );
+        Ok(())
     }
 }
 
@@ -344,11 +1024,129 @@ impl Destroy for Resource {
     }
 }
 
+/// A resource pool that creates another `ResourcePool` of the same size
+/// whenever the current one runs out, instead of failing.
+///
+/// Resources allocated from it must not be destroyed individually.
+/// They are all recycled at once by `reset`, typically at the end of a frame.
+pub struct GrowableResourcePool {
+    pool_sizes: Vec<ResourcePoolSize>,
+    max_sets: u32,
+    update_after_bind: bool,
+    pools: Vec<ResourcePool>,
+    current: usize,
+}
+
+impl GrowableResourcePool {
+    /// Create a growable pool.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - Device to create the pools on.
+    /// * `descriptor` - Size of each backing pool.
+    pub fn new(device: &Device, descriptor: &ResourcePoolDescriptor) -> NxResult<Self> {
+        let pool = ResourcePool::create(
+            device,
+            descriptor.pool_sizes,
+            descriptor.max_sets,
+            descriptor.update_after_bind,
+        )?;
+        Ok(Self {
+            pool_sizes: descriptor.pool_sizes.to_vec(),
+            max_sets: descriptor.max_sets,
+            update_after_bind: descriptor.update_after_bind,
+            pools: vec![pool],
+            current: 0,
+        })
+    }
+
+    /// Allocate a resource with `layout`, adding a backing pool if the current ones are full.
+    pub fn allocate(&mut self, device: &Device, layout: &ResourceLayout) -> NxResult<Resource> {
+        layout.validate_allocatable()?;
+        loop {
+            let created = self.current == self.pools.len();
+            if created {
+                let pool = ResourcePool::create(
+                    device,
+                    &self.pool_sizes,
+                    self.max_sets,
+                    self.update_after_bind,
+                )?;
+                self.pools.push(pool);
+            }
+            let pool = self.pools[self.current].pool;
+            let alloc_info = DescriptorSetAllocateInfo::builder()
+                .set_layouts(&[layout.inner])
+                .descriptor_pool(pool)
+                .build();
+            match unsafe { device.device.allocate_descriptor_sets(&alloc_info) } {
+                Ok(x) => {
+                    return Ok(Resource {
+                        descriptor_set: x[0],
+                        pool,
+                    })
+                }
+                Err(
+                    ash::vk::Result::ERROR_OUT_OF_POOL_MEMORY
+                    | ash::vk::Result::ERROR_FRAGMENTED_POOL,
+                ) => {
+                    // A fresh pool that cannot hold the layout will never be able to.
+                    if created {
+                        return Err(NxError::InvalidDescriptor(
+                            "the layout needs more resources than a single pool holds"
+                                .to_string(),
+                        ));
+                    }
+                    self.current += 1;
+                }
+                Err(ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY) => {
+                    return Err(NxError::OutOfDeviceMemory)
+                }
+                Err(ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY) => {
+                    return Err(NxError::OutOfHostMemory)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Recycle every resource allocated from the pool.
+    /// The resources must no longer be in use by the device.
+    pub fn reset(&mut self, device: &Device) -> NxResult<()> {
+        for pool in &self.pools {
+            unsafe {
+                device
+                    .device
+                    .reset_descriptor_pool(pool.pool, ash::vk::DescriptorPoolResetFlags::empty())
+            }?;
+        }
+        self.current = 0;
+        Ok(())
+    }
+
+    /// Number of backing pools created so far.
+    pub fn pool_count(&self) -> usize {
+        self.pools.len()
+    }
+}
+
+impl Destroy for GrowableResourcePool {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        for pool in &self.pools {
+            pool.device(device);
+        }
+    }
+}
+
 pub struct ResourceLayoutBinding {
-    binding: u32,
-    desc_type: ResourceType,
-    count: u32,
-    flags: ShaderStage,
+    pub(crate) binding: u32,
+    pub(crate) desc_type: ResourceType,
+    pub(crate) count: u32,
+    pub(crate) flags: ShaderStageFlags,
+    pub(crate) immutable_samplers: Vec<ash::vk::Sampler>,
+    pub(crate) binding_flags: DescriptorBindingFlags,
 }
 
 impl ResourceLayoutBinding {
@@ -357,7 +1155,9 @@ impl ResourceLayoutBinding {
             binding: 0,
             desc_type: ResourceType::UniformBuffer,
             count: 0,
-            flags: ShaderStage::Vertex,
+            flags: ShaderStageFlags::VERTEX,
+            immutable_samplers: vec![],
+            binding_flags: DescriptorBindingFlags::empty(),
         }
     }
 
@@ -377,7 +1177,48 @@ impl ResourceLayoutBinding {
     }
 
     pub fn shader_stage(mut self, stage: ShaderStage) -> Self {
-        self.flags = stage;
+        self.flags = stage.into();
+        self
+    }
+
+    /// Makes the binding visible to an additional shader stage.
+    pub fn add_shader_stage(mut self, stage: ShaderStage) -> Self {
+        self.flags |= stage.into();
+        self
+    }
+
+    /// Bakes `samplers` into the layout, one per array element, and sets the count to match.
+    /// The count must not be changed afterwards.
+    /// Only used by `Sampler` and `CombinedImageSampler` bindings,
+    /// whose writes then no longer need a sampler.
+    pub fn immutable_samplers(mut self, samplers: &[&Sampler]) -> Self {
+        self.immutable_samplers = samplers.iter().map(|x| x.sampler).collect();
+        self.count = samplers.len() as u32;
+        self
+    }
+
+    /// Allows array elements that shaders do not access to be left unwritten,
+    /// as in bindless texture arrays.
+    /// Requires the `descriptor_binding_partially_bound` device feature.
+    pub fn partially_bound(mut self, partially_bound: bool) -> Self {
+        if partially_bound {
+            self.binding_flags |= DescriptorBindingFlags::PARTIALLY_BOUND;
+        } else {
+            self.binding_flags &= !DescriptorBindingFlags::PARTIALLY_BOUND;
+        }
+        self
+    }
+
+    /// Allows writing the binding after the resource was bound, while it is in use.
+    /// Not allowed for uniform buffers. The resource must come from a pool created with
+    /// `ResourcePoolDescriptor::update_after_bind`.
+    /// Requires the `descriptor_update_after_bind` device feature.
+    pub fn update_after_bind(mut self, update_after_bind: bool) -> Self {
+        if update_after_bind {
+            self.binding_flags |= DescriptorBindingFlags::UPDATE_AFTER_BIND;
+        } else {
+            self.binding_flags &= !DescriptorBindingFlags::UPDATE_AFTER_BIND;
+        }
         self
     }
 }
@@ -385,31 +1226,169 @@ impl ResourceLayoutBinding {
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub struct ResourceLayout {
     inner: ash::vk::DescriptorSetLayout,
+    /// Number of resources of each type in `ResourceType::ALL` order.
+    counts: [u32; ResourceType::ALL.len()],
+    update_after_bind: bool,
+    pub(crate) push_descriptor: bool,
 }
 
 impl ResourceLayout {
-    pub fn new(device: &Device, descriptor: &[ResourceLayoutBinding]) -> Self {
+    /// Create a resource layout.
+    /// Returns `NxError::UnsupportedFeature` if a binding uses a flag
+    /// whose device feature is not enabled.
+    pub fn new(device: &Device, descriptor: &[ResourceLayoutBinding]) -> NxResult<Self> {
+        Self::create(device, descriptor, false)
+    }
+
+    /// Create a layout whose resources are written with `CommandRecorder::push_resources`
+    /// instead of being allocated from a pool.
+    /// Requires the `push_descriptor` device feature, and the bindings may hold
+    /// at most `maxPushDescriptors` resources in total.
+    pub fn push_descriptor(
+        device: &Device,
+        descriptor: &[ResourceLayoutBinding],
+    ) -> NxResult<Self> {
+        if device.push_descriptor.is_none() {
+            return Err(NxError::UnsupportedFeature(
+                "push_descriptor is not enabled on the device".to_string(),
+            ));
+        }
+        if descriptor.iter().any(|x| {
+            x.binding_flags
+                .contains(DescriptorBindingFlags::UPDATE_AFTER_BIND)
+        }) {
+            return Err(NxError::InvalidDescriptor(
+                "push descriptor layouts cannot have update_after_bind bindings".to_string(),
+            ));
+        }
+        let count = descriptor
+            .iter()
+            .try_fold(0u32, |count, x| count.checked_add(x.count));
+        if count.map_or(true, |x| x > device.max_push_descriptors) {
+            return Err(NxError::InvalidDescriptor(format!(
+                "push descriptor layouts hold at most {} resources (maxPushDescriptors)",
+                device.max_push_descriptors
+            )));
+        }
+        Self::create(device, descriptor, true)
+    }
+
+    #[doc(hidden)]
+    /// Push descriptor layouts cannot be allocated from pools.
+    fn validate_allocatable(&self) -> NxResult<()> {
+        if self.push_descriptor {
+            return Err(NxError::InvalidDescriptor(
+                "push descriptor layouts are written with push_resources, not allocated"
+                    .to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    fn create(
+        device: &Device,
+        descriptor: &[ResourceLayoutBinding],
+        push_descriptor: bool,
+    ) -> NxResult<Self> {
+        Self::validate_binding_flags(device, descriptor)?;
+        if let Some(x) = descriptor.iter().find(|x| {
+            !x.immutable_samplers.is_empty() && x.immutable_samplers.len() != x.count as usize
+        }) {
+            return Err(NxError::InvalidDescriptor(format!(
+                "binding {} has {} immutable samplers but a count of {}",
+                x.binding,
+                x.immutable_samplers.len(),
+                x.count
+            )));
+        }
         let mut bindings = vec![];
         for descriptor in descriptor {
-            bindings.push(
-                ash::vk::DescriptorSetLayoutBinding::builder()
-                    .binding(descriptor.binding)
-                    .descriptor_type(descriptor.desc_type.into())
-                    .descriptor_count(descriptor.count)
-                    .stage_flags(descriptor.flags.into())
-                    .build(),
+            let mut binding = ash::vk::DescriptorSetLayoutBinding::builder()
+                .binding(descriptor.binding)
+                .descriptor_type(descriptor.desc_type.into())
+                .descriptor_count(descriptor.count)
+                .stage_flags(descriptor.flags)
+                .build();
+            let takes_sampler = matches!(
+                descriptor.desc_type,
+                ResourceType::Sampler | ResourceType::CombinedImageSampler
             );
+            if takes_sampler && !descriptor.immutable_samplers.is_empty() {
+                binding.p_immutable_samplers = descriptor.immutable_samplers.as_ptr();
+            }
+            bindings.push(binding);
+        }
+        let mut counts = [0u32; ResourceType::ALL.len()];
+        for descriptor in descriptor {
+            let index = ResourceType::ALL
+                .iter()
+                .position(|x| *x == descriptor.desc_type)
+                .unwrap();
+            counts[index] += descriptor.count;
+        }
+        let binding_flags = descriptor
+            .iter()
+            .map(|x| x.binding_flags)
+            .collect::<Vec<DescriptorBindingFlags>>();
+        let mut binding_flags_info =
+            DescriptorSetLayoutBindingFlagsCreateInfo::builder().binding_flags(&binding_flags);
+        let mut create_info = DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+        if binding_flags.iter().any(|x| !x.is_empty()) {
+            create_info = create_info.push_next(&mut binding_flags_info);
+        }
+        let update_after_bind = binding_flags
+            .iter()
+            .any(|x| x.contains(DescriptorBindingFlags::UPDATE_AFTER_BIND));
+        if update_after_bind {
+            create_info = create_info.flags(DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL);
+        }
+        if push_descriptor {
+            create_info = create_info.flags(DescriptorSetLayoutCreateFlags::PUSH_DESCRIPTOR_KHR);
         }
-        let create_info = DescriptorSetLayoutCreateInfo::builder()
-            .bindings(&bindings)
-            .build();
         let inner = unsafe {
             device
                 .device
                 .create_descriptor_set_layout(&create_info, None)
+        }?;
+        Ok(Self {
+            inner,
+            counts,
+            update_after_bind,
+            push_descriptor,
+        })
+    }
+
+    /// Checks that the device features needed by the binding flags are enabled.
+    fn validate_binding_flags(device: &Device, bindings: &[ResourceLayoutBinding]) -> NxResult<()> {
+        for binding in bindings {
+            if binding
+                .binding_flags
+                .contains(DescriptorBindingFlags::PARTIALLY_BOUND)
+                && !device.features.descriptor_binding_partially_bound
+            {
+                return Err(NxError::UnsupportedFeature(
+                    "descriptor_binding_partially_bound is not enabled on the device".to_string(),
+                ));
+            }
+            if binding
+                .binding_flags
+                .contains(DescriptorBindingFlags::UPDATE_AFTER_BIND)
+            {
+                if !device.features.descriptor_update_after_bind {
+                    return Err(NxError::UnsupportedFeature(
+                        "descriptor_update_after_bind is not enabled on the device".to_string(),
+                    ));
+                }
+                if binding.desc_type == ResourceType::UniformBuffer {
+                    return Err(NxError::UnsupportedFeature(format!(
+                        "binding {} is a uniform buffer, which cannot be updated after bind",
+                        binding.binding
+                    )));
+                }
+            }
         }
-        .unwrap();
-        Self { inner }
+        Ok(())
     }
 }
 
@@ -433,6 +1412,16 @@ pub struct PipelineDescriptor<'a> {
     topology: PrimitiveTopology,
     shader_stages: &'a [ShaderStageDescriptor<'a>],
     input_descriptor: Option<&'a PipelineVertexInputDescriptor<'a>>,
+    line_width: f32,
+    dynamic_line_width: bool,
+    alpha_blend: bool,
+    cull_mode: CullMode,
+    front_face: FrontFace,
+    dynamic_states: &'a [PipelineDynamicState],
+    subpass: u32,
+    depth_test: bool,
+    depth_write: bool,
+    depth_compare_op: CompareOp,
 }
 
 impl<'a> PipelineDescriptor<'a> {
@@ -445,6 +1434,16 @@ impl<'a> PipelineDescriptor<'a> {
             topology: PrimitiveTopology::TriangleList,
             shader_stages: &[],
             input_descriptor: None,
+            line_width: 1.0,
+            dynamic_line_width: false,
+            alpha_blend: false,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::Clockwise,
+            dynamic_states: &[],
+            subpass: 0,
+            depth_test: false,
+            depth_write: false,
+            depth_compare_op: CompareOp::Less,
         }
     }
 
@@ -480,25 +1479,323 @@ impl<'a> PipelineDescriptor<'a> {
         self.shader_stages = shader_stages;
         self
     }
+
+    /// Specifies the width of rasterized lines.
+    /// Widths other than 1.0 require the `wide_lines` device feature.
+    #[inline]
+    pub const fn line_width(mut self, line_width: f32) -> Self {
+        self.line_width = line_width;
+        self
+    }
+
+    /// If true, the line width is set with `CommandRecorder::set_line_width` instead.
+    #[inline]
+    pub const fn dynamic_line_width(mut self, dynamic_line_width: bool) -> Self {
+        self.dynamic_line_width = dynamic_line_width;
+        self
+    }
+
+    /// If true, colors are blended over the attachment using their alpha.
+    #[inline]
+    pub const fn alpha_blend(mut self, alpha_blend: bool) -> Self {
+        self.alpha_blend = alpha_blend;
+        self
+    }
+
+    /// Specifies which triangles are culled. Defaults to `CullMode::Back`.
+    #[inline]
+    pub const fn cull_mode(mut self, cull_mode: CullMode) -> Self {
+        self.cull_mode = cull_mode;
+        self
+    }
+
+    /// Specifies the winding order of front-facing triangles. Defaults to `FrontFace::Clockwise`.
+    #[inline]
+    pub const fn front_face(mut self, front_face: FrontFace) -> Self {
+        self.front_face = front_face;
+        self
+    }
+
+    /// Specifies the state set while recording instead, so that one pipeline covers
+    /// several combinations. The values given to the descriptor are used until they are set.
+    /// Requires the `extended_dynamic_state` device feature.
+    #[inline]
+    pub const fn dynamic_states(mut self, dynamic_states: &'a [PipelineDynamicState]) -> Self {
+        self.dynamic_states = dynamic_states;
+        self
+    }
+
+    /// Enables the depth test against the depth attachment. Defaults to false.
+    #[inline]
+    pub const fn depth_test(mut self, depth_test: bool) -> Self {
+        self.depth_test = depth_test;
+        self
+    }
+
+    /// Enables writing the depth of fragments that pass the depth test. Defaults to false.
+    #[inline]
+    pub const fn depth_write(mut self, depth_write: bool) -> Self {
+        self.depth_write = depth_write;
+        self
+    }
+
+    /// Specifies the comparison of the depth test. Defaults to `CompareOp::Less`.
+    #[inline]
+    pub const fn depth_compare_op(mut self, depth_compare_op: CompareOp) -> Self {
+        self.depth_compare_op = depth_compare_op;
+        self
+    }
+
+    /// Index of the subpass of the render pass the pipeline is used in. Defaults to 0.
+    /// Ignored when the pipeline is created for dynamic rendering.
+    #[inline]
+    pub const fn subpass(mut self, subpass: u32) -> Self {
+        self.subpass = subpass;
+        self
+    }
+}
+
+/// A problem found by `PipelineDescriptor::validate`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum PipelineIssue {
+    /// The render pass has no subpass with the index given to `PipelineDescriptor::subpass`.
+    NoSubpass,
+    /// `DepthWriteEnable` or `DepthCompareOp` is declared, but the depth test is disabled
+    /// and `DepthTestEnable` is not declared, so they would have no effect.
+    DepthTestDisabled,
+    /// The same vertex binding is declared more than once.
+    DuplicateVertexBinding(u32),
+    /// The same vertex location is used by more than one attribute.
+    DuplicateVertexLocation(u32),
+    /// An attribute reads from a binding that is not declared.
+    UndeclaredVertexBinding { location: u32, binding: u32 },
+    /// An attribute does not fit in the stride of its binding.
+    AttributeOutsideStride {
+        location: u32,
+        offset: usize,
+        size: usize,
+        stride: usize,
+    },
+    /// The shaders use a descriptor set the pipeline layout does not have.
+    MissingSet(u32),
+    /// The shaders use a binding that is missing from the resource layout of its set.
+    MissingResource { set: u32, binding: u32 },
+    /// The resource layout declares a binding with a different type than the shaders.
+    ResourceTypeMismatch {
+        set: u32,
+        binding: u32,
+        shader: ResourceType,
+        layout: ResourceType,
+    },
+    /// The resource layout declares fewer array elements than the shaders use.
+    ResourceCountTooSmall {
+        set: u32,
+        binding: u32,
+        shader: u32,
+        layout: u32,
+    },
+    /// The resource layout does not make a binding visible to every stage that uses it.
+    ResourceStageMissing { set: u32, binding: u32 },
+    /// No push constant range of the layout covers a range used by the shaders.
+    PushConstantNotCovered { offset: u32, size: u32 },
+}
+
+impl std::fmt::Display for PipelineIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PipelineIssue::NoSubpass => write!(f, "the render pass has no such subpass"),
+            PipelineIssue::DepthTestDisabled => write!(
+                f,
+                "depth write and compare op are dynamic but the depth test is disabled"
+            ),
+            PipelineIssue::DuplicateVertexBinding(binding) => {
+                write!(f, "vertex binding {} is declared more than once", binding)
+            }
+            PipelineIssue::DuplicateVertexLocation(location) => {
+                write!(f, "vertex location {} is used more than once", location)
+            }
+            PipelineIssue::UndeclaredVertexBinding { location, binding } => write!(
+                f,
+                "the attribute at location {} reads from undeclared binding {}",
+                location, binding
+            ),
+            PipelineIssue::AttributeOutsideStride {
+                location,
+                offset,
+                size,
+                stride,
+            } => write!(
+                f,
+                "the attribute at location {} ({} bytes at offset {}) exceeds the stride of {}",
+                location, size, offset, stride
+            ),
+            PipelineIssue::MissingSet(set) => {
+                write!(f, "set {} is missing from the pipeline layout", set)
+            }
+            PipelineIssue::MissingResource { set, binding } => write!(
+                f,
+                "set {} binding {} is missing from the resource layout",
+                set, binding
+            ),
+            PipelineIssue::ResourceTypeMismatch {
+                set,
+                binding,
+                shader,
+                layout,
+            } => write!(
+                f,
+                "set {} binding {} is {:?} in the shaders but {:?} in the layout",
+                set, binding, shader, layout
+            ),
+            PipelineIssue::ResourceCountTooSmall {
+                set,
+                binding,
+                shader,
+                layout,
+            } => write!(
+                f,
+                "set {} binding {} has {} elements in the shaders but {} in the layout",
+                set, binding, shader, layout
+            ),
+            PipelineIssue::ResourceStageMissing { set, binding } => write!(
+                f,
+                "set {} binding {} is not visible to every stage that uses it",
+                set, binding
+            ),
+            PipelineIssue::PushConstantNotCovered { offset, size } => write!(
+                f,
+                "no push constant range covers {} bytes at offset {}",
+                size, offset
+            ),
+        }
+    }
 }
 
+impl<'a> PipelineDescriptor<'a> {
+    /// Cross-checks the vertex input against itself and the descriptor against `render_pass`.
+    /// Returns `NxError::PipelineValidation` listing every problem found.
+    pub fn validate(&self, render_pass: &RenderPass) -> NxResult<()> {
+        PipelineIssue::into_result(self.issues(render_pass))
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn issues(&self, render_pass: &RenderPass) -> Vec<PipelineIssue> {
+        let mut issues = vec![];
+        if render_pass
+            .depth_attachments
+            .get(self.subpass as usize)
+            .is_none()
+        {
+            issues.push(PipelineIssue::NoSubpass);
+        }
+        if self.depth_test_disabled() {
+            issues.push(PipelineIssue::DepthTestDisabled);
+        }
+        let input = match self.input_descriptor {
+            Some(x) => x,
+            None => return issues,
+        };
+        for (i, binding) in input.binding_desc.iter().enumerate() {
+            if input.binding_desc[..i].iter().any(|x| x.binding == binding.binding) {
+                issues.push(PipelineIssue::DuplicateVertexBinding(binding.binding));
+            }
+        }
+        for (i, attribute) in input.attribute_desc.iter().enumerate() {
+            if input.attribute_desc[..i]
+                .iter()
+                .any(|x| x.location == attribute.location)
+            {
+                issues.push(PipelineIssue::DuplicateVertexLocation(attribute.location));
+            }
+            match input
+                .binding_desc
+                .iter()
+                .find(|x| x.binding == attribute.binding)
+            {
+                None => issues.push(PipelineIssue::UndeclaredVertexBinding {
+                    location: attribute.location,
+                    binding: attribute.binding,
+                }),
+                Some(binding) => {
+                    let size = attribute.format.size();
+                    if attribute.offset + size > binding.stride {
+                        issues.push(PipelineIssue::AttributeOutsideStride {
+                            location: attribute.location,
+                            offset: attribute.offset,
+                            size,
+                            stride: binding.stride,
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+
+    #[doc(hidden)]
+    /// Whether depth dynamic states are declared while the depth test can never be enabled.
+    fn depth_test_disabled(&self) -> bool {
+        self.dynamic_states.iter().any(|x| x.is_depth())
+            && !self.depth_test
+            && !self
+                .dynamic_states
+                .contains(&PipelineDynamicState::DepthTestEnable)
+    }
+}
+
+impl PipelineIssue {
+    #[doc(hidden)]
+    pub(crate) fn into_result(issues: Vec<PipelineIssue>) -> NxResult<()> {
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(NxError::PipelineValidation(issues))
+        }
+    }
+}
+
+/// The descriptor sets and push constants available to pipelines.
+///
+/// One layout can be shared by several pipelines, so that resources bound with it stay
+/// bound across pipeline changes. Destroy it after every pipeline created with it.
 #[derive(Clone, Copy)]
 pub struct PipelineLayout {
     pub(crate) layout: ash::vk::PipelineLayout,
+    /// Set whose layout was created with `ResourceLayout::push_descriptor`.
+    pub(crate) push_descriptor_set: Option<u32>,
 }
 
 impl PipelineLayout {
     #[inline]
     pub fn new(device: &Device, descriptor: &PipelineLayoutDescriptor) -> NxResult<Self> {
-        let layout_info = PipelineLayoutCreateInfo::builder().set_layouts(&[]);
-        let mut layouts = vec![];
-        match descriptor.set_layout_descriptor {
-            None => {}
-            Some(x) => {
-                layouts.push(x.inner);
-            }
+        let mut push_descriptor_sets = descriptor
+            .set_layouts
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.push_descriptor)
+            .map(|(i, _)| i as u32);
+        let push_descriptor_set = push_descriptor_sets.next();
+        if push_descriptor_sets.next().is_some() {
+            return Err(NxError::InvalidDescriptor(
+                "only one resource layout of a pipeline layout can use push descriptors"
+                    .to_string(),
+            ));
         }
-        let layout_info = layout_info.set_layouts(&layouts).build();
+        let layout_info = PipelineLayoutCreateInfo::builder().set_layouts(&[]);
+        let layouts = descriptor
+            .set_layouts
+            .iter()
+            .map(|x| x.inner)
+            .collect::<Vec<ash::vk::DescriptorSetLayout>>();
+        let push_constant_ranges = descriptor
+            .push_constant_ranges
+            .iter()
+            .map(|x| (*x).into())
+            .collect::<Vec<ash::vk::PushConstantRange>>();
+        let layout_info = layout_info
+            .set_layouts(&layouts)
+            .push_constant_ranges(&push_constant_ranges)
+            .build();
         let layout = match unsafe { device.device.create_pipeline_layout(&layout_info, None) } {
             Ok(x) => x,
             Err(e) => match e {
@@ -508,7 +1805,10 @@ impl PipelineLayout {
             }?,
         };
 
-        Ok(Self { layout })
+        Ok(Self {
+            layout,
+            push_descriptor_set,
+        })
     }
 }
 
@@ -522,18 +1822,133 @@ impl Destroy for PipelineLayout {
     }
 }
 
+/// Vertex shader of `Pipeline::fullscreen`, see `shader/fullscreen.vert`.
+const FULLSCREEN_VERTEX_SHADER: &[u8] = include_bytes!("shader/fullscreen.vert.spv");
+
+/// Attachments a graphics pipeline renders to.
+#[derive(Clone, Copy)]
+enum PipelineTarget<'a> {
+    RenderPass(&'a RenderPass),
+    Rendering {
+        color_formats: &'a [ImageFormat],
+        depth_format: Option<ImageFormat>,
+    },
+}
+
 pub struct Pipeline {
     pub(crate) pipeline: ash::vk::Pipeline,
+    /// Mask of the `PipelineDynamicState`s declared by the pipeline.
+    pub(crate) dynamic_states: u32,
 }
 
 impl Pipeline {
     #[inline]
     pub fn new(
         device: &Device,
-        pipeline_layout: PipelineLayout,
+        pipeline_layout: &PipelineLayout,
         renderpass: &RenderPass,
         descriptor: &PipelineDescriptor,
     ) -> NxResult<Vec<Self>> {
+        Self::create(
+            device,
+            pipeline_layout,
+            PipelineTarget::RenderPass(renderpass),
+            descriptor,
+        )
+    }
+
+    /// Create a pipeline used with `CommandRecorder::begin_rendering` instead of a render pass.
+    /// Requires the `dynamic_rendering` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `pipeline_layout` - Layout of the resources used by the pipeline.
+    /// * `color_formats` - Formats of the color attachments, in the order of the shader outputs.
+    /// * `depth_format` - Format of the depth/stencil attachment, if any.
+    /// * `descriptor` - Appropriate PipelineDescriptor.
+    pub fn for_rendering(
+        device: &Device,
+        pipeline_layout: &PipelineLayout,
+        color_formats: &[ImageFormat],
+        depth_format: Option<ImageFormat>,
+        descriptor: &PipelineDescriptor,
+    ) -> NxResult<Vec<Self>> {
+        if !device.features.dynamic_rendering {
+            return Err(NxError::UnsupportedFeature(
+                "pipelines without a render pass require the dynamic_rendering device feature"
+                    .to_string(),
+            ));
+        }
+        Self::create(
+            device,
+            pipeline_layout,
+            PipelineTarget::Rendering {
+                color_formats,
+                depth_format,
+            },
+            descriptor,
+        )
+    }
+
+    /// Create a pipeline that draws a triangle covering the whole render target,
+    /// for post-processing passes such as tonemapping or blur.
+    /// The vertex shader is built in and needs no vertex buffer.
+    /// It passes texture coordinates from (0, 0) to (1, 1) over the target to `location = 0`
+    /// of the fragment shader. Draw with `CommandRecorder::draw_fullscreen`.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `pipeline_layout` - Layout of the resources used by the fragment shader.
+    /// * `fragment_shader` - Fragment shader with a `main` entry point.
+    /// * `render_pass` - RenderPass the pipeline is used in.
+    /// * `extent` - Size of the render target.
+    pub fn fullscreen(
+        device: &Device,
+        pipeline_layout: &PipelineLayout,
+        fragment_shader: &Shader,
+        render_pass: &RenderPass,
+        extent: Extent2d,
+    ) -> NxResult<Self> {
+        let vertex_shader = Shader::new(device, &Spirv::from_raw(FULLSCREEN_VERTEX_SHADER)?);
+        let shader_stages = [
+            ShaderStageDescriptor::empty()
+                .entry_point("main")
+                .stage(ShaderStage::Vertex)
+                .shaders(&vertex_shader),
+            ShaderStageDescriptor::empty()
+                .entry_point("main")
+                .stage(ShaderStage::Fragment)
+                .shaders(fragment_shader),
+        ];
+        let descriptor = PipelineDescriptor::empty()
+            .shader_stages(&shader_stages)
+            .topology(PrimitiveTopology::TriangleList)
+            .width(extent.width())
+            .height(extent.height());
+        let pipelines = Self::new(device, pipeline_layout, render_pass, &descriptor);
+        // The shader module is not needed once the pipeline is created.
+        device.destroy(&vertex_shader);
+        Ok(pipelines?.remove(0))
+    }
+
+    #[doc(hidden)]
+    fn create(
+        device: &Device,
+        pipeline_layout: &PipelineLayout,
+        target: PipelineTarget,
+        descriptor: &PipelineDescriptor,
+    ) -> NxResult<Vec<Self>> {
+        if !descriptor.dynamic_states.is_empty() && !device.features.extended_dynamic_state {
+            return Err(NxError::UnsupportedFeature(
+                "dynamic pipeline states require the extended_dynamic_state device feature"
+                    .to_string(),
+            ));
+        }
+        if descriptor.depth_test_disabled() {
+            return Err(NxError::InvalidDescriptor(
+                PipelineIssue::DepthTestDisabled.to_string(),
+            ));
+        }
         let mut stages = vec![];
         let name = CString::new("main").unwrap();
         for i in descriptor.shader_stages {
@@ -573,29 +1988,55 @@ impl Pipeline {
             .topology(descriptor.topology.into())
             .primitive_restart_enable(false)
             .build();
+        let line_width = device.validate_line_width(descriptor.line_width)?;
         let rasterizer = PipelineRasterizationStateCreateInfo::builder()
             .depth_clamp_enable(false)
             .rasterizer_discard_enable(false)
             .polygon_mode(PolygonMode::FILL)
-            .line_width(1.0)
-            .cull_mode(CullModeFlags::BACK)
-            .front_face(FrontFace::CLOCKWISE)
+            .line_width(line_width)
+            .cull_mode(descriptor.cull_mode.into())
+            .front_face(descriptor.front_face.into())
             .depth_bias_enable(false)
             .build();
         let multi_sample = PipelineMultisampleStateCreateInfo::builder()
             .sample_shading_enable(false)
             .rasterization_samples(SampleCountFlags::TYPE_1)
             .build();
-        let blend_attachments = vec![PipelineColorBlendAttachmentState::builder()
-            .color_write_mask(
-                ColorComponentFlags::A
-                    | ColorComponentFlags::R
-                    | ColorComponentFlags::G
-                    | ColorComponentFlags::B,
-            )
-            .blend_enable(false)
-            .build()];
-        let render_pass = renderpass;
+        let color_attachment_count = match target {
+            PipelineTarget::RenderPass(renderpass) => {
+                match renderpass
+                    .color_attachment_counts
+                    .get(descriptor.subpass as usize)
+                {
+                    Some(x) => *x,
+                    None => {
+                        return Err(NxError::InvalidDescriptor(
+                            PipelineIssue::NoSubpass.to_string(),
+                        ))
+                    }
+                }
+            }
+            PipelineTarget::Rendering { color_formats, .. } => color_formats.len(),
+        };
+        // One blend state is needed for each color attachment of the subpass.
+        let blend_attachments = vec![
+            PipelineColorBlendAttachmentState::builder()
+                .color_write_mask(
+                    ColorComponentFlags::A
+                        | ColorComponentFlags::R
+                        | ColorComponentFlags::G
+                        | ColorComponentFlags::B,
+                )
+                .blend_enable(descriptor.alpha_blend)
+                .src_color_blend_factor(BlendFactor::SRC_ALPHA)
+                .dst_color_blend_factor(BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .color_blend_op(BlendOp::ADD)
+                .src_alpha_blend_factor(BlendFactor::ONE)
+                .dst_alpha_blend_factor(BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .alpha_blend_op(BlendOp::ADD)
+                .build();
+            color_attachment_count
+        ];
         let blend = PipelineColorBlendStateCreateInfo::builder()
             .logic_op_enable(false)
             .attachments(&blend_attachments)
@@ -603,6 +2044,15 @@ impl Pipeline {
 
         let layout = pipeline_layout.layout;
 
+        let mut dynamic_states = vec![];
+        if descriptor.dynamic_line_width {
+            dynamic_states.push(DynamicState::LINE_WIDTH);
+        }
+        dynamic_states.extend(descriptor.dynamic_states.iter().map(|x| DynamicState::from(*x)));
+        let dynamic_state = PipelineDynamicStateCreateInfo::builder()
+            .dynamic_states(&dynamic_states)
+            .build();
+
         let vertex_input_state = match descriptor.input_descriptor {
             None => PipelineVertexInputStateCreateInfo::builder().build(),
             Some(desc) => {
@@ -635,19 +2085,65 @@ impl Pipeline {
             }
         };
 
-        let create_info = GraphicsPipelineCreateInfo::builder()
+        let color_formats = match target {
+            PipelineTarget::Rendering { color_formats, .. } => color_formats
+                .iter()
+                .map(|x| (*x).into())
+                .collect::<Vec<Format>>(),
+            PipelineTarget::RenderPass(_) => vec![],
+        };
+        let mut rendering = PipelineRenderingCreateInfo::builder()
+            .color_attachment_formats(&color_formats)
+            .build();
+        if let PipelineTarget::Rendering {
+            depth_format: Some(format),
+            ..
+        } = target
+        {
+            let aspect = format.aspect();
+            if aspect.contains(ImageAspectFlags::DEPTH) {
+                rendering.depth_attachment_format = format.into();
+            }
+            if aspect.contains(ImageAspectFlags::STENCIL) {
+                rendering.stencil_attachment_format = format.into();
+            }
+        }
+
+        let mut create_info = GraphicsPipelineCreateInfo::builder()
             .viewport_state(&viewport_state)
             .vertex_input_state(&vertex_input_info)
             .input_assembly_state(&input_assembly)
             .rasterization_state(&rasterizer)
             .multisample_state(&multi_sample)
             .color_blend_state(&blend)
+            .dynamic_state(&dynamic_state)
             .layout(layout)
             .stages(&stages)
-            .render_pass(render_pass.render_pass)
-            .subpass(0)
-            .vertex_input_state(&vertex_input_state)
+            .vertex_input_state(&vertex_input_state);
+        create_info = match target {
+            PipelineTarget::RenderPass(renderpass) => create_info
+                .render_pass(renderpass.render_pass)
+                .subpass(descriptor.subpass),
+            PipelineTarget::Rendering { .. } => create_info.push_next(&mut rendering),
+        };
+        // Pipelines rendering to depth attachments need depth/stencil state,
+        // and dynamic depth state is ignored without it.
+        let has_depth = match target {
+            PipelineTarget::RenderPass(renderpass) => renderpass
+                .depth_attachments
+                .get(descriptor.subpass as usize)
+                .is_some_and(|x| *x),
+            PipelineTarget::Rendering { depth_format, .. } => depth_format.is_some(),
+        };
+        let depth_stencil = PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(descriptor.depth_test)
+            .depth_write_enable(descriptor.depth_write)
+            .depth_compare_op(descriptor.depth_compare_op.into())
             .build();
+        if has_depth || descriptor.dynamic_states.iter().any(|x| x.is_depth()) {
+            create_info = create_info.depth_stencil_state(&depth_stencil);
+        }
+        let create_info = create_info.build();
 
         let pipelines = unsafe {
             device
@@ -657,7 +2153,13 @@ impl Pipeline {
         .unwrap();
         Ok(pipelines
             .iter()
-            .map(|x| Self { pipeline: *x })
+            .map(|x| Self {
+                pipeline: *x,
+                dynamic_states: descriptor
+                    .dynamic_states
+                    .iter()
+                    .fold(0, |mask, x| mask | x.mask()),
+            })
             .collect::<Vec<Pipeline>>())
     }
 }
@@ -0,0 +1,101 @@
+use crate::{ImageFormat, NxError, NxResult};
+
+/// Indicates the layout of 8-bit pixel data on the host.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PixelFormat {
+    /// Grayscale.
+    L8,
+    /// Grayscale with alpha.
+    La8,
+    Rgb8,
+    Rgba8,
+    Bgr8,
+    Bgra8,
+}
+
+impl PixelFormat {
+    /// Number of bytes per pixel.
+    pub const fn bytes_per_pixel(&self) -> usize {
+        match self {
+            PixelFormat::L8 => 1,
+            PixelFormat::La8 => 2,
+            PixelFormat::Rgb8 | PixelFormat::Bgr8 => 3,
+            PixelFormat::Rgba8 | PixelFormat::Bgra8 => 4,
+        }
+    }
+
+    /// Get the pixel layout of an image format, if it has 8-bit channels.
+    pub fn from_image_format(format: ImageFormat) -> Option<Self> {
+        match format {
+            ImageFormat::R8G8B8A8Unorm
+            | ImageFormat::R8G8B8A8Srgb
+            | ImageFormat::R8G8B8A8Sscaled
+            | ImageFormat::R8G8B8A8Sint
+            | ImageFormat::R8G8B8A8Snorm
+            | ImageFormat::R8G8B8A8Uint => Some(PixelFormat::Rgba8),
+            ImageFormat::B8G8R8A8Unorm => Some(PixelFormat::Bgra8),
+            ImageFormat::B8G8R8Sscaled
+            | ImageFormat::B8G8R8Srgb
+            | ImageFormat::B8G8R8Snorm
+            | ImageFormat::B8G8R8Sint => Some(PixelFormat::Bgr8),
+            _ => None,
+        }
+    }
+
+    fn decode(&self, pixel: &[u8]) -> [u8; 4] {
+        match self {
+            PixelFormat::L8 => [pixel[0], pixel[0], pixel[0], 255],
+            PixelFormat::La8 => [pixel[0], pixel[0], pixel[0], pixel[1]],
+            PixelFormat::Rgb8 => [pixel[0], pixel[1], pixel[2], 255],
+            PixelFormat::Rgba8 => [pixel[0], pixel[1], pixel[2], pixel[3]],
+            PixelFormat::Bgr8 => [pixel[2], pixel[1], pixel[0], 255],
+            PixelFormat::Bgra8 => [pixel[2], pixel[1], pixel[0], pixel[3]],
+        }
+    }
+
+    fn encode(&self, [r, g, b, a]: [u8; 4], dst: &mut Vec<u8>) {
+        match self {
+            PixelFormat::L8 => dst.push(luminance(r, g, b)),
+            PixelFormat::La8 => dst.extend_from_slice(&[luminance(r, g, b), a]),
+            PixelFormat::Rgb8 => dst.extend_from_slice(&[r, g, b]),
+            PixelFormat::Rgba8 => dst.extend_from_slice(&[r, g, b, a]),
+            PixelFormat::Bgr8 => dst.extend_from_slice(&[b, g, r]),
+            PixelFormat::Bgra8 => dst.extend_from_slice(&[b, g, r, a]),
+        }
+    }
+}
+
+// BT.601 weights in 8-bit fixed point.
+fn luminance(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 * 77 + g as u32 * 150 + b as u32 * 29) >> 8) as u8
+}
+
+/// Convert pixel data between host pixel layouts.
+/// Missing alpha is filled with 255 and grayscale is expanded to every color channel.
+/// # Arguments
+///
+/// * `src_format` - Layout of `data`.
+/// * `dst_format` - Layout of the returned data.
+/// * `data` - Pixels to convert. Its length must be a multiple of the source pixel size.
+pub fn convert_pixels(
+    src_format: PixelFormat,
+    dst_format: PixelFormat,
+    data: &[u8],
+) -> NxResult<Vec<u8>> {
+    let src_size = src_format.bytes_per_pixel();
+    if data.len() % src_size != 0 {
+        return Err(NxError::InvalidDescriptor(format!(
+            "{} bytes is not a whole number of {:?} pixels",
+            data.len(),
+            src_format
+        )));
+    }
+    if src_format == dst_format {
+        return Ok(data.to_vec());
+    }
+    let mut converted = Vec::with_capacity(data.len() / src_size * dst_format.bytes_per_pixel());
+    for pixel in data.chunks_exact(src_size) {
+        dst_format.encode(src_format.decode(pixel), &mut converted);
+    }
+    Ok(converted)
+}
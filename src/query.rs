@@ -0,0 +1,453 @@
+use crate::{CommandRecorder, Destroy, Device, Instance, NxError, NxResult};
+use ash::vk::{QueryPipelineStatisticFlags, QueryPoolCreateInfo, QueryResultFlags};
+
+/// Represents the type of queries in a QueryPool.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QueryType {
+    /// Counts the samples that pass the depth and stencil tests.
+    Occlusion,
+    /// Counts the statistics specified by `QueryPoolDescriptor::add_statistic`.
+    PipelineStatistics,
+}
+
+impl From<QueryType> for ash::vk::QueryType {
+    fn from(value: QueryType) -> Self {
+        match value {
+            QueryType::Occlusion => ash::vk::QueryType::OCCLUSION,
+            QueryType::PipelineStatistics => ash::vk::QueryType::PIPELINE_STATISTICS,
+        }
+    }
+}
+
+/// How `QueryPool::results_with_availability` treats queries that have not finished.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QueryWait {
+    /// Blocks until every query has finished. Every result is available.
+    Wait,
+    /// Returns immediately. Unavailable queries have a value of 0.
+    NoWait,
+    /// Returns immediately. Unavailable queries have a value between 0 and their final value.
+    Partial,
+}
+
+/// Represents a counter of a pipeline statistics query.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PipelineStatistic {
+    InputAssemblyVertices,
+    InputAssemblyPrimitives,
+    VertexShaderInvocations,
+    GeometryShaderInvocations,
+    GeometryShaderPrimitives,
+    ClippingInvocations,
+    ClippingPrimitives,
+    FragmentShaderInvocations,
+    TessellationControlShaderPatches,
+    TessellationEvaluationShaderInvocations,
+    ComputeShaderInvocations,
+}
+
+impl PipelineStatistic {
+    /// All statistics, in the order in which the results are written.
+    #[doc(hidden)]
+    pub(crate) const ALL: [PipelineStatistic; 11] = [
+        PipelineStatistic::InputAssemblyVertices,
+        PipelineStatistic::InputAssemblyPrimitives,
+        PipelineStatistic::VertexShaderInvocations,
+        PipelineStatistic::GeometryShaderInvocations,
+        PipelineStatistic::GeometryShaderPrimitives,
+        PipelineStatistic::ClippingInvocations,
+        PipelineStatistic::ClippingPrimitives,
+        PipelineStatistic::FragmentShaderInvocations,
+        PipelineStatistic::TessellationControlShaderPatches,
+        PipelineStatistic::TessellationEvaluationShaderInvocations,
+        PipelineStatistic::ComputeShaderInvocations,
+    ];
+}
+
+impl From<PipelineStatistic> for QueryPipelineStatisticFlags {
+    fn from(value: PipelineStatistic) -> Self {
+        match value {
+            PipelineStatistic::InputAssemblyVertices => {
+                QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES
+            }
+            PipelineStatistic::InputAssemblyPrimitives => {
+                QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES
+            }
+            PipelineStatistic::VertexShaderInvocations => {
+                QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+            }
+            PipelineStatistic::GeometryShaderInvocations => {
+                QueryPipelineStatisticFlags::GEOMETRY_SHADER_INVOCATIONS
+            }
+            PipelineStatistic::GeometryShaderPrimitives => {
+                QueryPipelineStatisticFlags::GEOMETRY_SHADER_PRIMITIVES
+            }
+            PipelineStatistic::ClippingInvocations => {
+                QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS
+            }
+            PipelineStatistic::ClippingPrimitives => {
+                QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+            }
+            PipelineStatistic::FragmentShaderInvocations => {
+                QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS
+            }
+            PipelineStatistic::TessellationControlShaderPatches => {
+                QueryPipelineStatisticFlags::TESSELLATION_CONTROL_SHADER_PATCHES
+            }
+            PipelineStatistic::TessellationEvaluationShaderInvocations => {
+                QueryPipelineStatisticFlags::TESSELLATION_EVALUATION_SHADER_INVOCATIONS
+            }
+            PipelineStatistic::ComputeShaderInvocations => {
+                QueryPipelineStatisticFlags::COMPUTE_SHADER_INVOCATIONS
+            }
+        }
+    }
+}
+
+/// Results of a pipeline statistics query.
+/// Statistics that were not requested are `None`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct PipelineStatistics {
+    pub input_assembly_vertices: Option<u64>,
+    pub input_assembly_primitives: Option<u64>,
+    pub vertex_shader_invocations: Option<u64>,
+    pub geometry_shader_invocations: Option<u64>,
+    pub geometry_shader_primitives: Option<u64>,
+    pub clipping_invocations: Option<u64>,
+    pub clipping_primitives: Option<u64>,
+    pub fragment_shader_invocations: Option<u64>,
+    pub tessellation_control_shader_patches: Option<u64>,
+    pub tessellation_evaluation_shader_invocations: Option<u64>,
+    pub compute_shader_invocations: Option<u64>,
+}
+
+impl PipelineStatistics {
+    fn set(&mut self, statistic: PipelineStatistic, value: u64) {
+        let field = match statistic {
+            PipelineStatistic::InputAssemblyVertices => &mut self.input_assembly_vertices,
+            PipelineStatistic::InputAssemblyPrimitives => &mut self.input_assembly_primitives,
+            PipelineStatistic::VertexShaderInvocations => &mut self.vertex_shader_invocations,
+            PipelineStatistic::GeometryShaderInvocations => &mut self.geometry_shader_invocations,
+            PipelineStatistic::GeometryShaderPrimitives => &mut self.geometry_shader_primitives,
+            PipelineStatistic::ClippingInvocations => &mut self.clipping_invocations,
+            PipelineStatistic::ClippingPrimitives => &mut self.clipping_primitives,
+            PipelineStatistic::FragmentShaderInvocations => &mut self.fragment_shader_invocations,
+            PipelineStatistic::TessellationControlShaderPatches => {
+                &mut self.tessellation_control_shader_patches
+            }
+            PipelineStatistic::TessellationEvaluationShaderInvocations => {
+                &mut self.tessellation_evaluation_shader_invocations
+            }
+            PipelineStatistic::ComputeShaderInvocations => &mut self.compute_shader_invocations,
+        };
+        *field = Some(value);
+    }
+}
+
+/// Stores information needed to create a QueryPool.
+pub struct QueryPoolDescriptor {
+    query_type: QueryType,
+    query_count: u32,
+    statistics: QueryPipelineStatisticFlags,
+}
+
+impl QueryPoolDescriptor {
+    /// Initializes a new descriptor with default values.
+    #[inline]
+    pub fn empty() -> Self {
+        Self {
+            query_type: QueryType::Occlusion,
+            query_count: 1,
+            statistics: QueryPipelineStatisticFlags::empty(),
+        }
+    }
+
+    #[inline]
+    pub fn query_type(mut self, query_type: QueryType) -> Self {
+        self.query_type = query_type;
+        self
+    }
+
+    /// Specifies the number of queries in the pool.
+    #[inline]
+    pub fn query_count(mut self, query_count: u32) -> Self {
+        self.query_count = query_count;
+        self
+    }
+
+    /// Adds a statistic counted by pipeline statistics queries.
+    #[inline]
+    pub fn add_statistic(mut self, statistic: PipelineStatistic) -> Self {
+        self.statistics |= statistic.into();
+        self
+    }
+}
+
+pub struct QueryPool {
+    pub(crate) pool: ash::vk::QueryPool,
+    query_type: QueryType,
+    query_count: u32,
+    statistics: QueryPipelineStatisticFlags,
+}
+
+impl QueryPool {
+    /// Create a new QueryPool.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `descriptor` - Appropriate QueryPoolDescriptor.
+    pub fn new(device: &Device, descriptor: &QueryPoolDescriptor) -> NxResult<Self> {
+        let statistics = if descriptor.query_type == QueryType::PipelineStatistics {
+            if !device.features.pipeline_statistics_query {
+                return Err(NxError::UnsupportedFeature(
+                    "pipelineStatisticsQuery is not enabled on the device".to_string(),
+                ));
+            }
+            if descriptor.statistics.is_empty() {
+                return Err(NxError::InvalidDescriptor(
+                    "no statistics were specified for a pipeline statistics query pool".to_string(),
+                ));
+            }
+            descriptor.statistics
+        } else {
+            QueryPipelineStatisticFlags::empty()
+        };
+        let create_info = QueryPoolCreateInfo::builder()
+            .query_type(descriptor.query_type.into())
+            .query_count(descriptor.query_count)
+            .pipeline_statistics(statistics)
+            .build();
+        let pool = match unsafe { device.device.create_query_pool(&create_info, None) } {
+            Ok(x) => x,
+            Err(e) => {
+                return match e {
+                    ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                    ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                    _ => Err(NxError::Unknown),
+                }
+            }
+        };
+        Ok(Self {
+            pool,
+            query_type: descriptor.query_type,
+            query_count: descriptor.query_count,
+            statistics,
+        })
+    }
+
+    pub fn query_type(&self) -> QueryType {
+        self.query_type
+    }
+
+    pub fn query_count(&self) -> u32 {
+        self.query_count
+    }
+
+    /// Resets queries from the host so that they can be used again,
+    /// without recording `CommandRecorder::reset_query_pool`.
+    /// Requires the `host_query_reset` device feature.
+    /// The queries must not be in use by pending commands.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `first_query` - Index of the first query.
+    /// * `query_count` - Number of queries to reset.
+    pub fn reset_host(&self, device: &Device, first_query: u32, query_count: u32) -> NxResult<()> {
+        if !device.features.host_query_reset {
+            return Err(NxError::UnsupportedFeature(
+                "hostQueryReset is not enabled on the device".to_string(),
+            ));
+        }
+        self.check_range(first_query, query_count)?;
+        unsafe {
+            device
+                .device
+                .reset_query_pool(self.pool, first_query, query_count);
+        }
+        Ok(())
+    }
+
+    /// Resets queries so that they can be used again.
+    /// The queries are reset from the host immediately if the `host_query_reset` device feature
+    /// is enabled, otherwise the reset is recorded into `recorder`.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `recorder` - CommandRecorder that records the reset when the feature is not enabled.
+    /// * `first_query` - Index of the first query.
+    /// * `query_count` - Number of queries to reset.
+    pub fn reset(
+        &self,
+        device: &Device,
+        recorder: &CommandRecorder,
+        first_query: u32,
+        query_count: u32,
+    ) -> NxResult<()> {
+        if device.features.host_query_reset {
+            return self.reset_host(device, first_query, query_count);
+        }
+        self.check_range(first_query, query_count)?;
+        recorder.reset_query_pool(device, self, first_query, query_count);
+        Ok(())
+    }
+
+    /// Wait for the queries to finish and get their results.
+    /// For occlusion queries, each result is the number of samples that passed.
+    /// Use `pipeline_statistics` for pipeline statistics queries.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `first_query` - Index of the first query.
+    /// * `query_count` - Number of queries to read.
+    pub fn results(
+        &self,
+        device: &Device,
+        first_query: u32,
+        query_count: u32,
+    ) -> NxResult<Vec<u64>> {
+        if self.query_type == QueryType::PipelineStatistics {
+            return Err(NxError::InvalidDescriptor(
+                "use pipeline_statistics to read pipeline statistics queries".to_string(),
+            ));
+        }
+        self.raw_results(device, first_query, query_count, 1, QueryResultFlags::WAIT)
+    }
+
+    /// Get the results of the queries together with whether each one is available,
+    /// so that a frame can skip results that are not ready instead of stalling.
+    /// For occlusion queries, each value is the number of samples that passed.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `first_query` - Index of the first query.
+    /// * `query_count` - Number of queries to read.
+    /// * `wait` - How queries that have not finished are treated.
+    pub fn results_with_availability(
+        &self,
+        device: &Device,
+        first_query: u32,
+        query_count: u32,
+        wait: QueryWait,
+    ) -> NxResult<Vec<(u64, bool)>> {
+        if self.query_type == QueryType::PipelineStatistics {
+            return Err(NxError::InvalidDescriptor(
+                "use pipeline_statistics to read pipeline statistics queries".to_string(),
+            ));
+        }
+        let flags = QueryResultFlags::WITH_AVAILABILITY
+            | match wait {
+                QueryWait::Wait => QueryResultFlags::WAIT,
+                QueryWait::NoWait => QueryResultFlags::empty(),
+                QueryWait::Partial => QueryResultFlags::PARTIAL,
+            };
+        // The availability is written after the value of each query.
+        let data = self.raw_results(device, first_query, query_count, 2, flags)?;
+        Ok(data
+            .chunks(2)
+            .map(|x| {
+                let available = x[1] != 0;
+                match wait {
+                    // The value of an unavailable query is not written without PARTIAL.
+                    QueryWait::NoWait if !available => (0, false),
+                    _ => (x[0], available),
+                }
+            })
+            .collect())
+    }
+
+    /// Wait for pipeline statistics queries to finish and get their results.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `first_query` - Index of the first query.
+    /// * `query_count` - Number of queries to read.
+    pub fn pipeline_statistics(
+        &self,
+        device: &Device,
+        first_query: u32,
+        query_count: u32,
+    ) -> NxResult<Vec<PipelineStatistics>> {
+        if self.query_type != QueryType::PipelineStatistics {
+            return Err(NxError::InvalidDescriptor(
+                "the pool does not contain pipeline statistics queries".to_string(),
+            ));
+        }
+        let statistics = PipelineStatistic::ALL
+            .iter()
+            .filter(|x| self.statistics.contains((**x).into()))
+            .copied()
+            .collect::<Vec<PipelineStatistic>>();
+        let data = self.raw_results(
+            device,
+            first_query,
+            query_count,
+            statistics.len(),
+            QueryResultFlags::WAIT,
+        )?;
+        Ok(data
+            .chunks(statistics.len())
+            .map(|values| {
+                let mut result = PipelineStatistics::default();
+                for (statistic, value) in statistics.iter().zip(values) {
+                    result.set(*statistic, *value);
+                }
+                result
+            })
+            .collect())
+    }
+
+    fn raw_results(
+        &self,
+        device: &Device,
+        first_query: u32,
+        query_count: u32,
+        values_per_query: usize,
+        flags: QueryResultFlags,
+    ) -> NxResult<Vec<u64>> {
+        self.check_range(first_query, query_count)?;
+        let mut data = vec![0u64; query_count as usize * values_per_query];
+        let stride = (std::mem::size_of::<u64>() * values_per_query) as u64;
+        match unsafe {
+            (device.device.fp_v1_0().get_query_pool_results)(
+                device.device.handle(),
+                self.pool,
+                first_query,
+                query_count,
+                std::mem::size_of_val(data.as_slice()),
+                data.as_mut_ptr().cast(),
+                stride,
+                QueryResultFlags::TYPE_64 | flags,
+            )
+        }
+        .result()
+        {
+            Ok(_) => Ok(data),
+            // Some queries have not finished, which is expected without WAIT.
+            Err(ash::vk::Result::NOT_READY) if !flags.contains(QueryResultFlags::WAIT) => Ok(data),
+            Err(ash::vk::Result::ERROR_DEVICE_LOST) => Err(NxError::DeviceLost),
+            Err(e) => Err(NxError::InternalError(e)),
+        }
+    }
+
+    fn check_range(&self, first_query: u32, query_count: u32) -> NxResult<()> {
+        if first_query as u64 + query_count as u64 > self.query_count as u64 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "queries {}..{} are out of range of a pool of {} queries",
+                first_query,
+                first_query as u64 + query_count as u64,
+                self.query_count
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Destroy for QueryPool {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        unsafe {
+            device.device.destroy_query_pool(self.pool, None);
+        }
+    }
+}
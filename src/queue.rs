@@ -1,10 +1,15 @@
-use crate::{CommandRecorder, Device, NxError, NxResult, Semaphore};
-use ash::vk::{CommandBuffer, Fence, SubmitInfo};
+use crate::{
+    Buffer, CommandRecorder, Destroy, Device, FenceDescriptor, Instance, NxError, NxResult,
+    PipelineStage, Semaphore, SemaphoreDescriptor, SparseBufferBind,
+};
+use ash::vk::{
+    BindSparseInfo, CommandBuffer, Fence, PipelineStageFlags, SparseBufferMemoryBindInfo,
+    SparseMemoryBind, SubmitInfo,
+};
 
 pub struct QueuePresentDescriptor<'a> {
     pub(crate) wait_semaphores: &'a [Semaphore],
     pub(crate) signal_semaphores: &'a [Semaphore],
-    pub(crate) queue: Option<&'a Queue>,
 }
 
 impl<'a> QueuePresentDescriptor<'a> {
@@ -14,7 +19,6 @@ impl<'a> QueuePresentDescriptor<'a> {
         Self {
             wait_semaphores: &[],
             signal_semaphores: &[],
-            queue: None,
         }
     }
 
@@ -29,17 +33,55 @@ impl<'a> QueuePresentDescriptor<'a> {
         self.signal_semaphores = semaphore;
         self
     }
+}
 
-    /// Queue to present.
-    pub fn queue(mut self, queue: &'a Queue) -> Self {
-        self.queue = Some(queue);
-        self
+/// Orders a submission on one queue after a submission on another,
+/// for example compute work that consumes the output of graphics work.
+///
+/// The first submission signals the dependency with `QueueSubmitDescriptor::signal_dependency`
+/// and the second waits on it with `QueueSubmitDescriptor::wait_dependency`.
+/// Each signal must be waited on exactly once before the dependency is signaled again,
+/// so use one dependency per direction and frame in flight.
+/// Resources used on both queues also need `CommandRecorder::release_buffer` and
+/// `acquire_buffer` (or the image equivalents) unless they are shared concurrently.
+pub struct QueueDependency {
+    semaphore: Semaphore,
+    wait_stage: PipelineStage,
+}
+
+impl QueueDependency {
+    /// Create a dependency.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `wait_stage` - Stage of the waiting submission that must not start before
+    ///   the signaling submission finishes, e.g. `PipelineStage::ComputeShader`.
+    pub fn new(device: &Device, wait_stage: PipelineStage) -> NxResult<Self> {
+        Ok(Self {
+            semaphore: Semaphore::new(device, &SemaphoreDescriptor::empty())?,
+            wait_stage,
+        })
+    }
+
+    /// Get the semaphore behind the dependency.
+    pub fn semaphore(&self) -> &Semaphore {
+        &self.semaphore
+    }
+}
+
+impl Destroy for QueueDependency {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        self.semaphore.device(device);
     }
 }
 
 pub struct QueueSubmitDescriptor<'a> {
     wait_semaphores: &'a [Semaphore],
     signal_semaphores: &'a [Semaphore],
+    wait_dependencies: Vec<&'a QueueDependency>,
+    signal_dependencies: Vec<&'a QueueDependency>,
     fence: Option<&'a crate::Fence>,
 }
 
@@ -50,10 +92,24 @@ impl<'a> QueueSubmitDescriptor<'a> {
         Self {
             wait_semaphores: &[],
             signal_semaphores: &[],
+            wait_dependencies: vec![],
+            signal_dependencies: vec![],
             fence: None,
         }
     }
 
+    /// Waits for `dependency` to be signaled by a submission on another queue.
+    pub fn wait_dependency(mut self, dependency: &'a QueueDependency) -> Self {
+        self.wait_dependencies.push(dependency);
+        self
+    }
+
+    /// Signals `dependency` when the submission finishes.
+    pub fn signal_dependency(mut self, dependency: &'a QueueDependency) -> Self {
+        self.signal_dependencies.push(dependency);
+        self
+    }
+
     /// Waiting fence.
     pub fn fence(mut self, fence: &'a crate::Fence) -> Self {
         self.fence = Some(fence);
@@ -73,11 +129,27 @@ impl<'a> QueueSubmitDescriptor<'a> {
     }
 }
 
+/// Selects one of the queues created with the device.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum QueueSelection {
+    /// The queue of the family the device was created with.
+    Graphics,
+    /// A queue of a dedicated transfer family, if the device has one.
+    Transfer,
+    /// A queue of a compute family without graphics support, if the device has one.
+    /// Work submitted to it can overlap with graphics work; see `QueueDependency`.
+    Compute,
+    /// The queue used for presentation, see `DeviceConnecter::create_device_for_surface`.
+    /// Differs from the graphics queue only on devices whose graphics family cannot present.
+    Present,
+}
+
 #[derive(Clone)]
 pub struct Queue(pub(crate) ash::vk::Queue);
 
 impl Queue {
     /// Submits the queue.
+    /// Returns `NxError::DeviceLost` if the device was lost.
     #[inline]
     pub fn submit(
         &self,
@@ -85,18 +157,30 @@ impl Queue {
         descriptor: &QueueSubmitDescriptor,
         recorders: &[CommandRecorder],
     ) -> NxResult<()> {
+        if recorders.iter().any(|x| x.is_failed()) {
+            return Err(NxError::RecordingFailed);
+        }
         let buffers = recorders
             .iter()
             .map(|x| x.buffer)
             .collect::<Vec<CommandBuffer>>();
-        let w_semaphores = descriptor
-            .wait_semaphores
-            .iter()
-            .map(|x| x.semaphore)
-            .collect::<Vec<ash::vk::Semaphore>>();
+        // Plain wait semaphores block every stage.
+        let (w_semaphores, w_stages): (Vec<ash::vk::Semaphore>, Vec<PipelineStageFlags>) =
+            descriptor
+                .wait_semaphores
+                .iter()
+                .map(|x| (x.semaphore, PipelineStageFlags::ALL_COMMANDS))
+                .chain(
+                    descriptor
+                        .wait_dependencies
+                        .iter()
+                        .map(|x| (x.semaphore.semaphore, x.wait_stage.into())),
+                )
+                .unzip();
         let s_semaphores = descriptor
             .signal_semaphores
             .iter()
+            .chain(descriptor.signal_dependencies.iter().map(|x| &x.semaphore))
             .map(|x| x.semaphore)
             .collect::<Vec<ash::vk::Semaphore>>();
         let fence = match descriptor.fence {
@@ -105,6 +189,7 @@ impl Queue {
         };
         let submit_info = SubmitInfo::builder()
             .wait_semaphores(&w_semaphores)
+            .wait_dst_stage_mask(&w_stages)
             .signal_semaphores(&s_semaphores)
             .command_buffers(&buffers)
             .build();
@@ -114,9 +199,141 @@ impl Queue {
                 Err(e) => match e {
                     ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
                     ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                    ash::vk::Result::ERROR_DEVICE_LOST => Err(NxError::DeviceLost),
                     _ => Err(NxError::Unknown),
                 }?,
             }
         }
     }
+
+    /// Submits `recorders` and blocks until the GPU has finished executing them.
+    /// Returns `NxError::Timeout` if they did not finish within `timeout` nanoseconds
+    /// and `NxError::DeviceLost` if the device was lost.
+    pub fn submit_and_wait(
+        &self,
+        device: &Device,
+        recorders: &[CommandRecorder],
+        timeout: u64,
+    ) -> NxResult<()> {
+        let fence = crate::Fence::new(device, &FenceDescriptor::empty())?;
+        let result = self
+            .submit(
+                device,
+                &QueueSubmitDescriptor::empty().fence(&fence),
+                recorders,
+            )
+            .and_then(|_| fence.wait(device, timeout));
+        // The fence can only be destroyed once the GPU no longer uses it.
+        if matches!(result, Err(NxError::Timeout)) {
+            let _ = unsafe { device.device.queue_wait_idle(self.0) };
+        }
+        device.destroy(&fence);
+        result
+    }
+
+    /// Binds memory to ranges of a sparse buffer, or unbinds it.
+    /// The binding happens asynchronously in queue order; use the semaphores and fence of
+    /// `descriptor` to synchronize with it. Requires the `sparse_buffer` device feature
+    /// and a queue family with sparse binding support.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `buffer` - Buffer created with `BufferDescriptor::sparse`.
+    /// * `binds` - Ranges to bind or unbind.
+    /// * `descriptor` - Semaphores and fence of the operation. Wait stages are ignored.
+    pub fn bind_sparse(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        binds: &[SparseBufferBind],
+        descriptor: &QueueSubmitDescriptor,
+    ) -> NxResult<()> {
+        let requirements = buffer
+            .sparse_requirements
+            .ok_or_else(|| NxError::InvalidDescriptor("the buffer is not sparse".to_string()))?;
+        let alignment = requirements.alignment.max(1);
+        let mut memory_binds = Vec::with_capacity(binds.len());
+        for bind in binds {
+            if bind.offset % alignment != 0 || bind.size % alignment != 0 {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "offset {} and size {} must be multiples of the sparse alignment {}",
+                    bind.offset, bind.size, alignment
+                )));
+            }
+            if bind
+                .offset
+                .checked_add(bind.size)
+                .map_or(true, |end| end > requirements.size)
+            {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "range {}..{} is outside of the buffer",
+                    bind.offset,
+                    bind.offset.saturating_add(bind.size)
+                )));
+            }
+            let (memory, memory_offset) = match bind.memory {
+                Some((memory, memory_offset)) => {
+                    if requirements.memory_type_bits & (1 << memory.memory_type_index) == 0 {
+                        return Err(NxError::InvalidDescriptor(
+                            "the memory type cannot be bound to the buffer".to_string(),
+                        ));
+                    }
+                    if memory_offset % alignment != 0
+                        || memory_offset
+                            .checked_add(bind.size)
+                            .map_or(true, |end| end > memory.allocation_size)
+                    {
+                        return Err(NxError::InvalidDescriptor(format!(
+                            "memory range at {} is unaligned or outside of the memory",
+                            memory_offset
+                        )));
+                    }
+                    (memory.memory, memory_offset)
+                }
+                None => (ash::vk::DeviceMemory::null(), 0),
+            };
+            memory_binds.push(
+                SparseMemoryBind::builder()
+                    .resource_offset(bind.offset)
+                    .size(bind.size)
+                    .memory(memory)
+                    .memory_offset(memory_offset)
+                    .build(),
+            );
+        }
+        let buffer_binds = [SparseBufferMemoryBindInfo::builder()
+            .buffer(buffer.buffer)
+            .binds(&memory_binds)
+            .build()];
+        let w_semaphores = descriptor
+            .wait_semaphores
+            .iter()
+            .chain(descriptor.wait_dependencies.iter().map(|x| &x.semaphore))
+            .map(|x| x.semaphore)
+            .collect::<Vec<ash::vk::Semaphore>>();
+        let s_semaphores = descriptor
+            .signal_semaphores
+            .iter()
+            .chain(descriptor.signal_dependencies.iter().map(|x| &x.semaphore))
+            .map(|x| x.semaphore)
+            .collect::<Vec<ash::vk::Semaphore>>();
+        let fence = match descriptor.fence {
+            Some(x) => x.fence,
+            None => Fence::null(),
+        };
+        let bind_info = BindSparseInfo::builder()
+            .wait_semaphores(&w_semaphores)
+            .buffer_binds(&buffer_binds)
+            .signal_semaphores(&s_semaphores)
+            .build();
+        match unsafe { device.device.queue_bind_sparse(self.0, &[bind_info], fence) } {
+            Ok(_) => Ok(()),
+            Err(e) => match e {
+                ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                ash::vk::Result::ERROR_DEVICE_LOST => Err(NxError::DeviceLost),
+                _ => Err(NxError::Unknown),
+            },
+        }
+    }
 }
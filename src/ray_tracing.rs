@@ -0,0 +1,174 @@
+use crate::{
+    Buffer, BufferDescriptor, BufferUsage, Destroy, Device, DeviceConnecter, Instance, NxError,
+    NxResult,
+};
+use ash::vk::StridedDeviceAddressRegionKHR;
+use std::ffi::c_void;
+
+/// Stores information needed to create a ShaderBindingTable.
+///
+/// The shader groups of the pipeline must be ordered as one ray generation group,
+/// then the miss groups, then the hit groups, then the callable groups.
+pub struct ShaderBindingTableDescriptor {
+    miss_count: u32,
+    hit_count: u32,
+    callable_count: u32,
+}
+
+impl ShaderBindingTableDescriptor {
+    /// Initializes a new descriptor with a single ray generation group.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            miss_count: 0,
+            hit_count: 0,
+            callable_count: 0,
+        }
+    }
+
+    /// Specifies the number of miss groups.
+    #[inline]
+    pub const fn miss_count(mut self, count: u32) -> Self {
+        self.miss_count = count;
+        self
+    }
+
+    /// Specifies the number of hit groups.
+    #[inline]
+    pub const fn hit_count(mut self, count: u32) -> Self {
+        self.hit_count = count;
+        self
+    }
+
+    /// Specifies the number of callable groups.
+    #[inline]
+    pub const fn callable_count(mut self, count: u32) -> Self {
+        self.callable_count = count;
+        self
+    }
+}
+
+/// A buffer holding the shader group handles of a ray tracing pipeline,
+/// laid out as required by `trace_rays`.
+pub struct ShaderBindingTable {
+    buffer: Buffer,
+    pub(crate) raygen: StridedDeviceAddressRegionKHR,
+    pub(crate) miss: StridedDeviceAddressRegionKHR,
+    pub(crate) hit: StridedDeviceAddressRegionKHR,
+    pub(crate) callable: StridedDeviceAddressRegionKHR,
+}
+
+impl ShaderBindingTable {
+    /// Create a shader binding table from the group handles of `pipeline`.
+    /// Requires the `ray_tracing_pipeline` device feature.
+    /// # Arguments
+    ///
+    /// * `instance` - appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `device` - Reference to the appropriate device.
+    /// * `pipeline` - Raw handle of a ray tracing pipeline.
+    /// * `descriptor` - Appropriate ShaderBindingTableDescriptor.
+    pub fn new(
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        device: &Device,
+        pipeline: ash::vk::Pipeline,
+        descriptor: &ShaderBindingTableDescriptor,
+    ) -> NxResult<Self> {
+        let ray_tracing = device.ray_tracing.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature(
+                "ray_tracing_pipeline is not enabled on the device".to_string(),
+            )
+        })?;
+        let handle_size = ray_tracing.shader_group_handle_size as u64;
+        let base_alignment = ray_tracing.shader_group_base_alignment as u64;
+        let handle_stride =
+            handle_size.next_multiple_of(ray_tracing.shader_group_handle_alignment as u64);
+
+        // Each region starts at a multiple of the base alignment. The ray generation region
+        // holds a single record and its size must equal its stride.
+        let raygen_stride = handle_stride.next_multiple_of(base_alignment);
+        let counts = [
+            1,
+            descriptor.miss_count,
+            descriptor.hit_count,
+            descriptor.callable_count,
+        ];
+        let mut regions = [(0u64, 0u64, raygen_stride); 4];
+        let mut size = raygen_stride;
+        regions[0].1 = raygen_stride;
+        for (region, count) in regions.iter_mut().zip(counts).skip(1) {
+            let region_size = (count as u64 * handle_stride).next_multiple_of(base_alignment);
+            *region = (size, region_size, handle_stride);
+            size += region_size;
+        }
+
+        let group_count: u32 = counts.iter().sum();
+        let handles = unsafe {
+            ray_tracing.loader.get_ray_tracing_shader_group_handles(
+                pipeline,
+                0,
+                group_count,
+                (group_count as u64 * handle_size) as usize,
+            )
+        }?;
+
+        // The address of the buffer is only guaranteed to be aligned to the memory
+        // requirements, so reserve room to move the table to the base alignment.
+        let desc = BufferDescriptor::empty()
+            .size((size + base_alignment) as usize)
+            .usage(BufferUsage::ShaderBindingTable);
+        let buffer = Buffer::new(instance, connecter, device, &desc)?;
+        let address = buffer.device_address(device)?;
+        let start = address.next_multiple_of(base_alignment) - address;
+
+        let mut data = vec![0u8; (size + base_alignment) as usize];
+        let mut group = 0;
+        for (region, count) in regions.iter().zip(counts) {
+            for i in 0..count as u64 {
+                let src = (group * handle_size) as usize;
+                let dst = (start + region.0 + i * region.2) as usize;
+                data[dst..dst + handle_size as usize]
+                    .copy_from_slice(&handles[src..src + handle_size as usize]);
+                group += 1;
+            }
+        }
+        if let Err(e) = buffer.write(device, data.as_ptr() as *const c_void) {
+            device.destroy(&buffer);
+            return Err(e);
+        }
+        buffer.lock(device);
+
+        let region = |i: usize| {
+            let (offset, size, stride) = regions[i];
+            if counts[i] == 0 {
+                return StridedDeviceAddressRegionKHR::default();
+            }
+            StridedDeviceAddressRegionKHR {
+                device_address: address + start + offset,
+                stride,
+                size,
+            }
+        };
+        Ok(Self {
+            raygen: region(0),
+            miss: region(1),
+            hit: region(2),
+            callable: region(3),
+            buffer,
+        })
+    }
+
+    /// Get the buffer holding the table.
+    pub fn buffer(&self) -> &Buffer {
+        &self.buffer
+    }
+}
+
+impl Destroy for ShaderBindingTable {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        device.destroy(&self.buffer);
+    }
+}
@@ -1,12 +1,20 @@
 use crate::{
-    Buffer, Destroy, Device, Instance, NxError, NxResult, Pipeline, PipelineLayout,
-    RenderPassBeginDescriptor, Resource,
+    BarrierBatch, Buffer, BufferCopyRegion, BufferSlice, CompareOp, CullMode, Destroy, Device,
+    FrontFace, Image, ImageFormat, ImageLayout, IndexFormat, Instance, NxError, NxResult, Pipeline,
+    PipelineDynamicState, PipelineLayout, PrimitiveTopology, QueryPool, QueryType, QueueSelection,
+    RenderPassBeginDescriptor, RenderingInfo, Resource, ResourcePushDescriptor, ResourceState,
+    ShaderBindingTable, ShaderStage, SubresourceRange,
 };
 use ash::vk::{
-    ClearValue, CommandBuffer, CommandBufferAllocateInfo, CommandBufferBeginInfo,
-    CommandBufferLevel, CommandBufferResetFlags, CommandPoolCreateFlags, CommandPoolCreateInfo,
-    Extent2D, IndexType, Offset2D, PipelineBindPoint, Rect2D, RenderPassBeginInfo, SubpassContents,
+    AccessFlags, BufferCopy, BufferUsageFlags, ClearColorValue, ClearDepthStencilValue, ClearValue,
+    CommandBuffer, CommandBufferAllocateInfo, CommandBufferBeginInfo, CommandBufferLevel,
+    CommandBufferResetFlags, CommandPoolCreateFlags, CommandPoolCreateInfo,
+    ConditionalRenderingBeginInfoEXT, DependencyFlags, DrawIndexedIndirectCommand,
+    DrawIndirectCommand, Extent2D, ImageAspectFlags, ImageMemoryBarrier, ImageSubresourceRange,
+    ImageUsageFlags, Offset2D, PipelineBindPoint, PipelineStageFlags, QueryControlFlags, Rect2D,
+    RenderPassBeginInfo, RenderingAttachmentInfo, SubpassContents,
 };
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 
 /// Stores information needed to create a CommandPool.
 pub struct CommandPoolDescriptor {
@@ -67,6 +75,40 @@ impl Destroy for CommandPool {
     }
 }
 
+/// Value a color image is cleared to by `CommandRecorder::clear_color_image`.
+/// The variant must match the numeric class of the image format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearColor {
+    /// For floating point, normalized and sRGB formats.
+    Float([f32; 4]),
+    /// For signed integer formats.
+    Int([i32; 4]),
+    /// For unsigned integer formats.
+    Uint([u32; 4]),
+}
+
+impl ClearColor {
+    #[doc(hidden)]
+    /// Whether the value can clear an image of `format`.
+    pub(crate) fn matches(&self, format: ImageFormat) -> bool {
+        match self {
+            Self::Float(_) => !format.is_integer(),
+            Self::Int(_) => format.is_signed_integer(),
+            Self::Uint(_) => format.is_integer() && !format.is_signed_integer(),
+        }
+    }
+}
+
+impl From<ClearColor> for ClearColorValue {
+    fn from(value: ClearColor) -> Self {
+        match value {
+            ClearColor::Float(float32) => ClearColorValue { float32 },
+            ClearColor::Int(int32) => ClearColorValue { int32 },
+            ClearColor::Uint(uint32) => ClearColorValue { uint32 },
+        }
+    }
+}
+
 /// Stores information needed to create a CommandRecorder.
 pub struct CommandRecorderDescriptor {
     recorder_count: u32,
@@ -89,6 +131,10 @@ impl CommandRecorderDescriptor {
 
 pub struct CommandRecorder {
     pub(crate) buffer: CommandBuffer,
+    /// Mask of the `PipelineDynamicState`s declared by the last bound pipeline.
+    pub(crate) dynamic_states: AtomicU32,
+    /// Set when recording failed, until recording begins again.
+    pub(crate) failed: AtomicBool,
 }
 
 impl CommandRecorder {
@@ -116,23 +162,131 @@ impl CommandRecorder {
         assert_eq!(descriptor.recorder_count, buffers.len() as u32);
         Ok(buffers
             .iter()
-            .map(|x| Self { buffer: *x })
+            .map(|x| Self {
+                buffer: *x,
+                dynamic_states: AtomicU32::new(0),
+                failed: AtomicBool::new(false),
+            })
             .collect::<Vec<Self>>())
     }
 
-    /// Starts recording commands.
+    /// Starts recording commands and begins the render pass.
     #[inline]
     pub fn begin(&self, device: &Device, descriptor: RenderPassBeginDescriptor) -> NxResult<()> {
+        self.begin_recording(device)?;
+        self.begin_render_pass(device, descriptor);
+        Ok(())
+    }
+
+    /// Ends the render pass and recording commands.
+    #[inline]
+    pub fn end(&self, device: &Device) -> NxResult<()> {
+        self.end_render_pass(device);
+        self.end_recording(device)
+    }
+
+    /// Starts recording commands without beginning a render pass.
+    /// Commands that must be recorded outside a render pass, such as `reset_query_pool`,
+    /// can be recorded before `begin_render_pass`.
+    #[inline]
+    pub fn begin_recording(&self, device: &Device) -> NxResult<()> {
         let create_info = CommandBufferBeginInfo::builder().build();
-        let mut clear = ClearValue::default();
-        unsafe {
-            clear.color.float32[0] = descriptor.r;
-            clear.color.float32[1] = descriptor.g;
-            clear.color.float32[2] = descriptor.b;
-            clear.color.float32[3] = descriptor.a;
+        let result = unsafe {
+            match device
+                .device
+                .begin_command_buffer(self.buffer, &create_info)
+            {
+                Ok(_) => Ok(()),
+                Err(e) => match e {
+                    ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                    ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                    _ => Err(NxError::Unknown),
+                },
+            }
+        };
+        self.failed.store(result.is_err(), Ordering::Relaxed);
+        // No pipeline is bound in a new recording.
+        self.dynamic_states.store(0, Ordering::Relaxed);
+        result
+    }
+
+    /// End recording commands.
+    /// If this fails, the recorder cannot be submitted until it is recorded again.
+    #[inline]
+    pub fn end_recording(&self, device: &Device) -> NxResult<()> {
+        let result = unsafe {
+            match device.device.end_command_buffer(self.buffer) {
+                Ok(_) => Ok(()),
+                Err(e) => match e {
+                    ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                    ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                    _ => Err(NxError::Unknown),
+                },
+            }
+        };
+        if result.is_err() {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Records commands with `record` between `begin_recording` and `end_recording`.
+    /// If any step fails, the command buffer is reset and the recorder is marked as failed,
+    /// so that `Queue::submit` returns `NxError::RecordingFailed` instead of submitting
+    /// an invalid command buffer. The error of the failed step is returned.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `record` - Records the commands. The recorder is already recording.
+    pub fn record<F>(&self, device: &Device, record: F) -> NxResult<()>
+    where
+        F: FnOnce(&CommandRecorder) -> NxResult<()>,
+    {
+        let result = self
+            .begin_recording(device)
+            .and_then(|_| record(self))
+            .and_then(|_| self.end_recording(device));
+        if result.is_err() {
+            self.failed.store(true, Ordering::Relaxed);
+            // Release what was recorded so far. The recorder stays marked as failed.
+            unsafe {
+                let _ = device
+                    .device
+                    .reset_command_buffer(self.buffer, CommandBufferResetFlags::empty());
+            }
         }
+        result
+    }
+
+    /// Whether the last recording failed, see `record`.
+    /// A failed recorder cannot be submitted until it is recorded again.
+    #[inline]
+    pub fn is_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Begins the render pass.
+    #[inline]
+    pub fn begin_render_pass(&self, device: &Device, descriptor: RenderPassBeginDescriptor) {
+        let color = ClearValue {
+            color: ClearColorValue {
+                float32: [descriptor.r, descriptor.g, descriptor.b, descriptor.a],
+            },
+        };
+        let depth_stencil = ClearValue {
+            depth_stencil: ClearDepthStencilValue {
+                depth: descriptor.depth,
+                stencil: descriptor.stencil,
+            },
+        };
+        let render_pass = descriptor.render_pass.unwrap();
+        let clear_values = render_pass
+            .depth_stencil_attachments
+            .iter()
+            .map(|x| if *x { depth_stencil } else { color })
+            .collect::<Vec<ClearValue>>();
         let begin_info = RenderPassBeginInfo::builder()
-            .render_pass(descriptor.render_pass.unwrap().render_pass)
+            .render_pass(render_pass.render_pass)
             .framebuffer(descriptor.frame_buffer.unwrap().frame_buffer)
             .render_area(
                 Rect2D::builder()
@@ -150,46 +304,139 @@ impl CommandRecorder {
                     )
                     .build(),
             )
-            .clear_values(&[clear])
+            .clear_values(&clear_values)
             .build();
         unsafe {
-            match device
-                .device
-                .begin_command_buffer(self.buffer, &create_info)
-            {
-                Ok(_) => {}
-                Err(e) => match e {
-                    ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
-                    ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
-                    _ => Err(NxError::Unknown),
-                }?,
-            }
             device
                 .device
                 .cmd_begin_render_pass(self.buffer, &begin_info, SubpassContents::INLINE);
         }
-        Ok(())
     }
 
-    /// End recording commands.
+    /// Ends the render pass.
     #[inline]
-    pub fn end(&self, device: &Device) -> NxResult<()> {
+    pub fn end_render_pass(&self, device: &Device) {
         unsafe {
             device.device.cmd_end_render_pass(self.buffer);
-            match device.device.end_command_buffer(self.buffer) {
-                Ok(_) => Ok(()),
-                Err(e) => match e {
-                    ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
-                    ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
-                    _ => Err(NxError::Unknown),
-                },
+        }
+    }
+
+    /// Begins rendering to the attachments of `info` without a render pass.
+    /// Requires the `dynamic_rendering` device feature.
+    /// Pipelines used until `end_rendering` must be created with `Pipeline::for_rendering`.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `info` - Attachments and area rendered to.
+    pub fn begin_rendering(&self, device: &Device, info: &RenderingInfo) -> NxResult<()> {
+        if !device.features.dynamic_rendering {
+            return Err(NxError::UnsupportedFeature(
+                "begin_rendering requires the dynamic_rendering device feature".to_string(),
+            ));
+        }
+        info.validate()?;
+        let color_attachments = info
+            .color_attachments
+            .iter()
+            .map(|x| x.to_vk(false))
+            .collect::<Vec<RenderingAttachmentInfo>>();
+        let depth_attachment = info.depth_attachment.map(|x| x.to_vk(true));
+        let stencil_attachment = info.stencil_attachment.map(|x| x.to_vk(true));
+        let mut rendering_info = ash::vk::RenderingInfo::builder()
+            .render_area(info.render_area())
+            .layer_count(info.layer_count)
+            .view_mask(info.view_mask)
+            .color_attachments(&color_attachments);
+        if let Some(attachment) = &depth_attachment {
+            rendering_info = rendering_info.depth_attachment(attachment);
+        }
+        if let Some(attachment) = &stencil_attachment {
+            rendering_info = rendering_info.stencil_attachment(attachment);
+        }
+        unsafe {
+            device
+                .device
+                .cmd_begin_rendering(self.buffer, &rendering_info);
+        }
+        Ok(())
+    }
+
+    /// Ends rendering started with `begin_rendering`.
+    #[inline]
+    pub fn end_rendering(&self, device: &Device) {
+        unsafe {
+            device.device.cmd_end_rendering(self.buffer);
+        }
+    }
+
+    /// Resets queries so that they can be used again.
+    /// Must be recorded outside a render pass, see also `QueryPool::reset_host`.
+    #[inline]
+    pub fn reset_query_pool(
+        &self,
+        device: &Device,
+        pool: &QueryPool,
+        first_query: u32,
+        query_count: u32,
+    ) {
+        unsafe {
+            device
+                .device
+                .cmd_reset_query_pool(self.buffer, pool.pool, first_query, query_count);
+        }
+    }
+
+    /// Begins a query.
+    /// Precise occlusion queries require the `occlusion_query_precise` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `pool` - QueryPool containing the query.
+    /// * `index` - Index of the query in the pool.
+    /// * `precise` - Whether to count the exact number of samples.
+    #[inline]
+    pub fn begin_query(
+        &self,
+        device: &Device,
+        pool: &QueryPool,
+        index: u32,
+        precise: bool,
+    ) -> NxResult<()> {
+        let mut flags = QueryControlFlags::empty();
+        if precise {
+            if pool.query_type() != QueryType::Occlusion {
+                return Err(NxError::InvalidDescriptor(
+                    "only occlusion queries can be precise".to_string(),
+                ));
             }
+            if !device.features.occlusion_query_precise {
+                return Err(NxError::UnsupportedFeature(
+                    "occlusionQueryPrecise is not enabled on the device".to_string(),
+                ));
+            }
+            flags |= QueryControlFlags::PRECISE;
+        }
+        unsafe {
+            device
+                .device
+                .cmd_begin_query(self.buffer, pool.pool, index, flags);
+        }
+        Ok(())
+    }
+
+    /// Ends a query.
+    #[inline]
+    pub fn end_query(&self, device: &Device, pool: &QueryPool, index: u32) {
+        unsafe {
+            device.device.cmd_end_query(self.buffer, pool.pool, index);
         }
     }
 
     /// Bind the pipeline.
     #[inline]
     pub fn bind_pipeline(&self, device: &Device, pipeline: &Pipeline) {
+        self.dynamic_states
+            .store(pipeline.dynamic_states, Ordering::Relaxed);
         unsafe {
             device.device.cmd_bind_pipeline(
                 self.buffer,
@@ -209,32 +456,832 @@ impl CommandRecorder {
         }
     }
 
+    /// Binds `slices` to consecutive vertex input bindings starting at `first_binding`.
+    /// Each binding reads from the offset of its slice.
+    pub fn bind_vertex_buffers(&self, device: &Device, first_binding: u32, slices: &[BufferSlice]) {
+        let buffers = slices.iter().map(|x| x.buffer.buffer).collect::<Vec<_>>();
+        let offsets = slices.iter().map(|x| x.offset).collect::<Vec<u64>>();
+        unsafe {
+            device
+                .device
+                .cmd_bind_vertex_buffers(self.buffer, first_binding, &buffers, &offsets)
+        }
+    }
+
+    /// Binds a range of a buffer as the index buffer.
+    /// The index format recorded on the buffer is used, falling back to 16-bit indices.
+    #[inline]
+    pub fn bind_index_buffer_slice(&self, device: &Device, slice: BufferSlice) {
+        let index_type = slice.buffer.index_format.unwrap_or(IndexFormat::U16).into();
+        unsafe {
+            device.device.cmd_bind_index_buffer(
+                self.buffer,
+                slice.buffer.buffer,
+                slice.offset,
+                index_type,
+            );
+        }
+    }
+
     /// Binds the index buffer.
+    /// The index format recorded on the buffer is used, falling back to 16-bit indices.
 
     #[inline]
     pub fn bind_index_buffer(&self, device: &Device, buffer: &Buffer) {
+        let index_type = buffer.index_format.unwrap_or(IndexFormat::U16).into();
         unsafe {
             device
                 .device
-                .cmd_bind_index_buffer(self.buffer, buffer.buffer, 0, IndexType::UINT16);
+                .cmd_bind_index_buffer(self.buffer, buffer.buffer, 0, index_type);
         }
     }
 
-    /// Binds the resource.
+    /// Binds the resource to descriptor set 0.
     #[inline]
     pub fn bind_resource(&self, device: &Device, resource: &Resource, layout: &PipelineLayout) {
+        self.bind_resources(device, 0, &[resource], layout);
+    }
+
+    /// Binds `resources` to consecutive descriptor sets starting at `first_set`.
+    /// Sets bound with a layout stay bound when switching to another pipeline of the layout.
+    pub fn bind_resources(
+        &self,
+        device: &Device,
+        first_set: u32,
+        resources: &[&Resource],
+        layout: &PipelineLayout,
+    ) {
+        let sets = resources
+            .iter()
+            .map(|x| x.descriptor_set)
+            .collect::<Vec<ash::vk::DescriptorSet>>();
         unsafe {
             device.device.cmd_bind_descriptor_sets(
                 self.buffer,
                 PipelineBindPoint::GRAPHICS,
                 layout.layout,
-                0,
-                &[resource.descriptor_set],
+                first_set,
+                &sets,
                 &[],
             );
         }
     }
 
+    /// Writes resources directly into the command buffer and binds them to `set`,
+    /// without allocating a Resource. Requires the `push_descriptor` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `layout` - Pipeline layout whose layout for `set` was created with
+    ///   `ResourceLayout::push_descriptor`.
+    /// * `set` - Set number the resources are bound to.
+    /// * `writes` - Resources to write.
+    pub fn push_resources(
+        &self,
+        device: &Device,
+        layout: &PipelineLayout,
+        set: u32,
+        writes: &[ResourcePushDescriptor],
+    ) -> NxResult<()> {
+        let Some(push_descriptor) = &device.push_descriptor else {
+            return Err(NxError::UnsupportedFeature(
+                "push_descriptor is not enabled on the device".to_string(),
+            ));
+        };
+        if layout.push_descriptor_set != Some(set) {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the layout of set {} was not created with ResourceLayout::push_descriptor",
+                set
+            )));
+        }
+        let writes = writes
+            .iter()
+            .map(ResourcePushDescriptor::pending_write)
+            .collect::<Vec<_>>();
+        let writes = writes.iter().map(|x| x.to_vk()).collect::<Vec<_>>();
+        unsafe {
+            push_descriptor.cmd_push_descriptor_set(
+                self.buffer,
+                PipelineBindPoint::GRAPHICS,
+                layout.layout,
+                set,
+                &writes,
+            );
+        }
+        Ok(())
+    }
+
+    /// Updates the values of push constants.
+    #[inline]
+    pub fn push_constants(
+        &self,
+        device: &Device,
+        layout: &PipelineLayout,
+        stage: ShaderStage,
+        offset: u32,
+        data: &[u8],
+    ) {
+        unsafe {
+            device.device.cmd_push_constants(
+                self.buffer,
+                layout.layout,
+                stage.into(),
+                offset,
+                data,
+            );
+        }
+    }
+
+    /// Copies the contents of `src` to the start of `dst`.
+    /// Copies as many bytes as the smaller of the two buffers holds.
+    /// Must be recorded outside a render pass.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `src` - Buffer created with `BufferUsage::TransferSrc`.
+    /// * `dst` - Buffer created with `BufferUsage::TransferDst`.
+    pub fn copy_buffer(&self, device: &Device, src: &Buffer, dst: &Buffer) -> NxResult<()> {
+        let region = BufferCopyRegion::new(0, 0, src.size.min(dst.size) as u64);
+        self.copy_buffer_regions(device, src, dst, &[region])
+    }
+
+    /// Copies several ranges from `src` to `dst` with a single command.
+    /// Must be recorded outside a render pass.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `src` - Buffer created with `BufferUsage::TransferSrc`.
+    /// * `dst` - Buffer created with `BufferUsage::TransferDst`.
+    /// * `regions` - Ranges to copy. Each must lie within both buffers.
+    pub fn copy_buffer_regions(
+        &self,
+        device: &Device,
+        src: &Buffer,
+        dst: &Buffer,
+        regions: &[BufferCopyRegion],
+    ) -> NxResult<()> {
+        if !src.usage.contains(BufferUsageFlags::TRANSFER_SRC) {
+            return Err(NxError::InvalidDescriptor(
+                "the source buffer was not created with TransferSrc usage".to_string(),
+            ));
+        }
+        if !dst.usage.contains(BufferUsageFlags::TRANSFER_DST) {
+            return Err(NxError::InvalidDescriptor(
+                "the destination buffer was not created with TransferDst usage".to_string(),
+            ));
+        }
+        if regions.is_empty() {
+            return Err(NxError::InvalidDescriptor(
+                "at least one region must be copied".to_string(),
+            ));
+        }
+        for (i, region) in regions.iter().enumerate() {
+            if region.size == 0 {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "region {} has a size of 0",
+                    i
+                )));
+            }
+            let in_bounds = |offset: u64, buffer: &Buffer| {
+                offset
+                    .checked_add(region.size)
+                    .is_some_and(|end| end <= buffer.size as u64)
+            };
+            if !in_bounds(region.src_offset, src) || !in_bounds(region.dst_offset, dst) {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "region {} copies {} bytes from offset {} to offset {}, \
+                     outside of the source of {} bytes or the destination of {} bytes",
+                    i, region.size, region.src_offset, region.dst_offset, src.size, dst.size
+                )));
+            }
+        }
+        let regions = regions
+            .iter()
+            .map(|&x| BufferCopy::from(x))
+            .collect::<Vec<_>>();
+        unsafe {
+            device
+                .device
+                .cmd_copy_buffer(self.buffer, src.buffer, dst.buffer, &regions);
+        }
+        Ok(())
+    }
+
+    /// Fills `size` bytes of `buffer` starting at `offset` with repetitions of `value`.
+    /// Useful for resetting counters. Must be recorded outside a render pass.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `buffer` - Buffer created with `BufferUsage::TransferDst`.
+    /// * `offset` - Offset in bytes. Must be a multiple of 4.
+    /// * `size` - Number of bytes to fill. Must be a nonzero multiple of 4.
+    /// * `value` - Value written to every 4 bytes of the range.
+    pub fn fill_buffer(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        offset: u64,
+        size: u64,
+        value: u32,
+    ) -> NxResult<()> {
+        Self::validate_transfer_range(buffer.usage, buffer.size as u64, offset, size)?;
+        unsafe {
+            device
+                .device
+                .cmd_fill_buffer(self.buffer, buffer.buffer, offset, size, value);
+        }
+        Ok(())
+    }
+
+    /// Writes `data` to `buffer` at `offset` without a staging buffer.
+    /// Intended for small updates. Must be recorded outside a render pass.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `buffer` - Buffer created with `BufferUsage::TransferDst`.
+    /// * `offset` - Offset in bytes. Must be a multiple of 4.
+    /// * `data` - At most 65536 bytes. The length must be a nonzero multiple of 4.
+    pub fn update_buffer(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        offset: u64,
+        data: &[u8],
+    ) -> NxResult<()> {
+        if data.len() > 65536 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "{} bytes exceed the limit of 65536 bytes of update_buffer",
+                data.len()
+            )));
+        }
+        Self::validate_transfer_range(buffer.usage, buffer.size as u64, offset, data.len() as u64)?;
+        unsafe {
+            device
+                .device
+                .cmd_update_buffer(self.buffer, buffer.buffer, offset, data);
+        }
+        Ok(())
+    }
+
+    /// Checks the usage, size and alignment required by `fill_buffer` and `update_buffer`.
+    fn validate_transfer_range(
+        usage: BufferUsageFlags,
+        buffer_size: u64,
+        offset: u64,
+        size: u64,
+    ) -> NxResult<()> {
+        if !usage.contains(BufferUsageFlags::TRANSFER_DST) {
+            return Err(NxError::InvalidDescriptor(
+                "the buffer was not created with TransferDst usage".to_string(),
+            ));
+        }
+        if size == 0 {
+            return Err(NxError::InvalidDescriptor(
+                "the range must not be empty".to_string(),
+            ));
+        }
+        if offset % 4 != 0 || size % 4 != 0 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "offset {} and size {} must be multiples of 4",
+                offset, size
+            )));
+        }
+        if offset
+            .checked_add(size)
+            .map_or(true, |end| end > buffer_size)
+        {
+            return Err(NxError::InvalidDescriptor(format!(
+                "range {}..{} is outside of the buffer of {} bytes",
+                offset,
+                offset.saturating_add(size),
+                buffer_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// Clears `range` of a color image to `color` outside a render pass,
+    /// e.g. to reset a storage image or an accumulation buffer.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `image` - Image created with `ImageUsage::TransferDst`, in `ImageLayout::TransferDst`.
+    ///   The layout is checked if the state of the image is tracked.
+    /// * `color` - RGBA value the range is cleared to.
+    ///   Must be `ClearColor::Int` or `ClearColor::Uint` for images of integer formats.
+    /// * `range` - Mip levels and array layers to clear.
+    pub fn clear_color_image(
+        &self,
+        device: &Device,
+        image: &Image,
+        color: ClearColor,
+        range: SubresourceRange,
+    ) -> NxResult<()> {
+        let aspect = image.format().aspect();
+        if aspect != ImageAspectFlags::COLOR || image.format().is_block_compressed() {
+            return Err(NxError::InvalidDescriptor(format!(
+                "images of format {:?} cannot be cleared as color images",
+                image.format()
+            )));
+        }
+        if !color.matches(image.format()) {
+            return Err(NxError::InvalidDescriptor(format!(
+                "{:?} cannot clear images of format {:?}",
+                color,
+                image.format()
+            )));
+        }
+        Self::validate_clear_target(image)?;
+        let value = ClearColorValue::from(color);
+        unsafe {
+            device.device.cmd_clear_color_image(
+                self.buffer,
+                image.image,
+                ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &value,
+                &[range.to_vk(aspect)],
+            );
+        }
+        Ok(())
+    }
+
+    /// Clears `range` of a depth/stencil image outside a render pass.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `image` - Image created with `ImageUsage::TransferDst`, in `ImageLayout::TransferDst`.
+    ///   The layout is checked if the state of the image is tracked.
+    /// * `depth` - Depth value, between 0.0 and 1.0.
+    /// * `stencil` - Stencil value. Ignored for formats without stencil.
+    /// * `range` - Mip levels and array layers to clear.
+    pub fn clear_depth_stencil_image(
+        &self,
+        device: &Device,
+        image: &Image,
+        depth: f32,
+        stencil: u32,
+        range: SubresourceRange,
+    ) -> NxResult<()> {
+        let aspect = image.format().aspect();
+        if aspect.contains(ImageAspectFlags::COLOR) {
+            return Err(NxError::InvalidDescriptor(format!(
+                "images of format {:?} have no depth or stencil",
+                image.format()
+            )));
+        }
+        Self::validate_clear_target(image)?;
+        let value = ClearDepthStencilValue { depth, stencil };
+        unsafe {
+            device.device.cmd_clear_depth_stencil_image(
+                self.buffer,
+                image.image,
+                ash::vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &value,
+                &[range.to_vk(aspect)],
+            );
+        }
+        Ok(())
+    }
+
+    fn validate_clear_target(image: &Image) -> NxResult<()> {
+        if !image.usage.contains(ImageUsageFlags::TRANSFER_DST) {
+            return Err(NxError::InvalidDescriptor(
+                "the image was not created with TransferDst usage".to_string(),
+            ));
+        }
+        match image.state() {
+            Some(state) if state.layout != ImageLayout::TransferDst => {
+                Err(NxError::InvalidDescriptor(format!(
+                    "the image is in {:?} but must be in TransferDst to be cleared",
+                    state.layout
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Transitions a rendered swapchain image to the layout required for presentation.
+    /// Not needed if the attachment's final layout is already `ImageLayout::PresentSrc`.
+    /// Must be recorded outside a render pass.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `image` - Swapchain image to present.
+    /// * `old_layout` - Current layout of the image, usually the final layout of the attachment.
+    #[inline]
+    pub fn transition_for_present(&self, device: &Device, image: &Image, old_layout: ImageLayout) {
+        let barrier = ImageMemoryBarrier::builder()
+            .src_access_mask(AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_access_mask(AccessFlags::empty())
+            .old_layout(old_layout.into())
+            .new_layout(ImageLayout::PresentSrc.into())
+            .src_queue_family_index(ash::vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(ash::vk::QUEUE_FAMILY_IGNORED)
+            .image(image.image)
+            .subresource_range(
+                ImageSubresourceRange::builder()
+                    .aspect_mask(ImageAspectFlags::COLOR)
+                    .base_mip_level(0)
+                    .level_count(1)
+                    .base_array_layer(0)
+                    .layer_count(1)
+                    .build(),
+            )
+            .build();
+        unsafe {
+            device.device.cmd_pipeline_barrier(
+                self.buffer,
+                PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                PipelineStageFlags::BOTTOM_OF_PIPE,
+                DependencyFlags::empty(),
+                &[],
+                &[],
+                &[barrier],
+            );
+        }
+    }
+
+    /// Opens a labeled region of commands shown by debugging tools.
+    /// Must be closed with `end_label`. Does nothing if no debug extension is available.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `name` - Name of the region.
+    /// * `color` - RGBA color of the region, or all zeros to let the tool choose.
+    pub fn begin_label(&self, device: &Device, name: &str, color: [f32; 4]) {
+        device.debug.begin_label(self.buffer, name, color);
+    }
+
+    /// Closes the region opened by the last `begin_label`.
+    pub fn end_label(&self, device: &Device) {
+        device.debug.end_label(self.buffer);
+    }
+
+    /// Inserts a single label between commands.
+    pub fn insert_label(&self, device: &Device, name: &str, color: [f32; 4]) {
+        device.debug.insert_label(self.buffer, name, color);
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn image_barrier(
+        &self,
+        device: &Device,
+        image: ash::vk::Image,
+        range: ImageSubresourceRange,
+        old: &ResourceState,
+        new: &ResourceState,
+    ) {
+        let ignored = ash::vk::QUEUE_FAMILY_IGNORED;
+        self.image_barrier_between(device, image, range, old, new, (ignored, ignored));
+    }
+
+    fn image_barrier_between(
+        &self,
+        device: &Device,
+        image: ash::vk::Image,
+        range: ImageSubresourceRange,
+        old: &ResourceState,
+        new: &ResourceState,
+        queue_families: (u32, u32),
+    ) {
+        let mut batch = BarrierBatch::new();
+        batch.push_image(image, range, old, new, queue_families);
+        batch.flush(device, self);
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn buffer_barrier(
+        &self,
+        device: &Device,
+        buffer: ash::vk::Buffer,
+        old: &ResourceState,
+        new: &ResourceState,
+    ) {
+        let ignored = ash::vk::QUEUE_FAMILY_IGNORED;
+        self.buffer_barrier_between(device, buffer, old, new, (ignored, ignored));
+    }
+
+    fn buffer_barrier_between(
+        &self,
+        device: &Device,
+        buffer: ash::vk::Buffer,
+        old: &ResourceState,
+        new: &ResourceState,
+        queue_families: (u32, u32),
+    ) {
+        let mut batch = BarrierBatch::new();
+        batch.push_buffer(buffer, old, new, queue_families);
+        batch.flush(device, self);
+    }
+
+    /// Releases `image` from the queue `from` so that it can be acquired on the queue `to`.
+    /// Must be recorded on `from`, and matched by `acquire_image` with the same states on `to`.
+    /// When both queues are in the same family, a regular barrier is recorded instead.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `image` - Image to transfer.
+    /// * `old` - How the image was last used on `from`.
+    /// * `new` - How the image will be used on `to`.
+    /// * `from` - Queue the image is released from.
+    /// * `to` - Queue the image is transferred to.
+    pub fn release_image(
+        &self,
+        device: &Device,
+        image: &Image,
+        old: ResourceState,
+        new: ResourceState,
+        from: QueueSelection,
+        to: QueueSelection,
+    ) {
+        let range = SubresourceRange::all().to_vk(image.format().aspect());
+        match Self::queue_families(device, from, to) {
+            Some(families) => {
+                let release = ResourceState {
+                    stages: PipelineStageFlags::empty(),
+                    access: AccessFlags::empty(),
+                    ..new
+                };
+                self.image_barrier_between(device, image.image, range, &old, &release, families)
+            }
+            None => self.image_barrier(device, image.image, range, &old, &new),
+        }
+    }
+
+    /// Acquires `image` on the queue `to` after `release_image` was recorded on `from`.
+    /// Records nothing when both queues are in the same family.
+    pub fn acquire_image(
+        &self,
+        device: &Device,
+        image: &Image,
+        old: ResourceState,
+        new: ResourceState,
+        from: QueueSelection,
+        to: QueueSelection,
+    ) {
+        if let Some(families) = Self::queue_families(device, from, to) {
+            let acquire = ResourceState {
+                stages: PipelineStageFlags::empty(),
+                access: AccessFlags::empty(),
+                ..old
+            };
+            let range = SubresourceRange::all().to_vk(image.format().aspect());
+            self.image_barrier_between(device, image.image, range, &acquire, &new, families);
+        }
+    }
+
+    /// Releases `buffer` from the queue `from` so that it can be acquired on the queue `to`.
+    /// Must be recorded on `from`, and matched by `acquire_buffer` on `to`.
+    /// When both queues are in the same family, a regular barrier is recorded instead.
+    pub fn release_buffer(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        old: ResourceState,
+        new: ResourceState,
+        from: QueueSelection,
+        to: QueueSelection,
+    ) {
+        match Self::queue_families(device, from, to) {
+            Some(families) => self.buffer_barrier_between(
+                device,
+                buffer.buffer,
+                &old,
+                &ResourceState::new(new.layout),
+                families,
+            ),
+            None => self.buffer_barrier(device, buffer.buffer, &old, &new),
+        }
+    }
+
+    /// Acquires `buffer` on the queue `to` after `release_buffer` was recorded on `from`.
+    /// Records nothing when both queues are in the same family.
+    pub fn acquire_buffer(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        old: ResourceState,
+        new: ResourceState,
+        from: QueueSelection,
+        to: QueueSelection,
+    ) {
+        if let Some(families) = Self::queue_families(device, from, to) {
+            let acquire = ResourceState::new(old.layout);
+            self.buffer_barrier_between(device, buffer.buffer, &acquire, &new, families);
+        }
+    }
+
+    /// Queue family indices for an ownership transfer, or None if no transfer is needed.
+    fn queue_families(
+        device: &Device,
+        from: QueueSelection,
+        to: QueueSelection,
+    ) -> Option<(u32, u32)> {
+        let from = device.queue_family_index(from) as u32;
+        let to = device.queue_family_index(to) as u32;
+        if from == to {
+            None
+        } else {
+            Some((from, to))
+        }
+    }
+
+    /// Dispatches rays using the shader binding table of the bound ray tracing pipeline.
+    /// Requires the `ray_tracing_pipeline` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `table` - Shader binding table of the bound pipeline.
+    /// * `width` - Number of rays in the x direction.
+    /// * `height` - Number of rays in the y direction.
+    /// * `depth` - Number of rays in the z direction.
+    pub fn trace_rays(
+        &self,
+        device: &Device,
+        table: &ShaderBindingTable,
+        width: u32,
+        height: u32,
+        depth: u32,
+    ) -> NxResult<()> {
+        let ray_tracing = device.ray_tracing.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature(
+                "ray_tracing_pipeline is not enabled on the device".to_string(),
+            )
+        })?;
+        unsafe {
+            ray_tracing.loader.cmd_trace_rays(
+                self.buffer,
+                &table.raygen,
+                &table.miss,
+                &table.hit,
+                &table.callable,
+                width,
+                height,
+                depth,
+            );
+        }
+        Ok(())
+    }
+
+    /// Begins conditional rendering.
+    /// Subsequent draws are discarded if the 32-bit value at `offset` in `buffer` is zero.
+    /// Requires the `conditional_rendering` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `buffer` - Buffer created with `BufferUsage::ConditionalRendering`.
+    /// * `offset` - Offset of the predicate in bytes. Must be a multiple of 4.
+    #[inline]
+    pub fn begin_conditional_rendering(
+        &self,
+        device: &Device,
+        buffer: &Buffer,
+        offset: u64,
+    ) -> NxResult<()> {
+        let conditional_rendering = device.conditional_rendering.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature(
+                "VK_EXT_conditional_rendering is not enabled on the device".to_string(),
+            )
+        })?;
+        if !buffer
+            .usage
+            .contains(BufferUsageFlags::CONDITIONAL_RENDERING_EXT)
+        {
+            return Err(NxError::InvalidDescriptor(
+                "the buffer was not created with BufferUsage::ConditionalRendering".to_string(),
+            ));
+        }
+        if offset % 4 != 0 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "conditional rendering offset {} is not a multiple of 4",
+                offset
+            )));
+        }
+        let begin_info = ConditionalRenderingBeginInfoEXT::builder()
+            .buffer(buffer.buffer)
+            .offset(offset)
+            .build();
+        unsafe {
+            (conditional_rendering.cmd_begin_conditional_rendering_ext)(self.buffer, &begin_info);
+        }
+        Ok(())
+    }
+
+    /// Ends conditional rendering.
+    #[inline]
+    pub fn end_conditional_rendering(&self, device: &Device) -> NxResult<()> {
+        let conditional_rendering = device.conditional_rendering.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature(
+                "VK_EXT_conditional_rendering is not enabled on the device".to_string(),
+            )
+        })?;
+        unsafe {
+            (conditional_rendering.cmd_end_conditional_rendering_ext)(self.buffer);
+        }
+        Ok(())
+    }
+
+    /// Sets the line width of a pipeline created with `dynamic_line_width`.
+    /// The width is clamped to the range supported by the device.
+    /// Widths other than 1.0 require the `wide_lines` device feature.
+    #[inline]
+    pub fn set_line_width(&self, device: &Device, line_width: f32) -> NxResult<()> {
+        let line_width = device.validate_line_width(line_width)?;
+        unsafe {
+            device.device.cmd_set_line_width(self.buffer, line_width);
+        }
+        Ok(())
+    }
+
+    /// Sets the cull mode of a pipeline declaring `PipelineDynamicState::CullMode`.
+    #[inline]
+    pub fn set_cull_mode(&self, device: &Device, cull_mode: CullMode) -> NxResult<()> {
+        self.validate_dynamic_state(PipelineDynamicState::CullMode)?;
+        unsafe {
+            device
+                .device
+                .cmd_set_cull_mode(self.buffer, cull_mode.into());
+        }
+        Ok(())
+    }
+
+    /// Sets the front face of a pipeline declaring `PipelineDynamicState::FrontFace`.
+    #[inline]
+    pub fn set_front_face(&self, device: &Device, front_face: FrontFace) -> NxResult<()> {
+        self.validate_dynamic_state(PipelineDynamicState::FrontFace)?;
+        unsafe {
+            device
+                .device
+                .cmd_set_front_face(self.buffer, front_face.into());
+        }
+        Ok(())
+    }
+
+    /// Sets the topology of a pipeline declaring `PipelineDynamicState::PrimitiveTopology`.
+    /// Lines and triangles cannot be swapped for each other.
+    #[inline]
+    pub fn set_primitive_topology(
+        &self,
+        device: &Device,
+        topology: PrimitiveTopology,
+    ) -> NxResult<()> {
+        self.validate_dynamic_state(PipelineDynamicState::PrimitiveTopology)?;
+        unsafe {
+            device
+                .device
+                .cmd_set_primitive_topology(self.buffer, topology.into());
+        }
+        Ok(())
+    }
+
+    /// Enables or disables the depth test of a pipeline declaring
+    /// `PipelineDynamicState::DepthTestEnable`.
+    #[inline]
+    pub fn set_depth_test_enable(&self, device: &Device, enable: bool) -> NxResult<()> {
+        self.validate_dynamic_state(PipelineDynamicState::DepthTestEnable)?;
+        unsafe {
+            device.device.cmd_set_depth_test_enable(self.buffer, enable);
+        }
+        Ok(())
+    }
+
+    /// Enables or disables depth writes of a pipeline declaring
+    /// `PipelineDynamicState::DepthWriteEnable`.
+    #[inline]
+    pub fn set_depth_write_enable(&self, device: &Device, enable: bool) -> NxResult<()> {
+        self.validate_dynamic_state(PipelineDynamicState::DepthWriteEnable)?;
+        unsafe {
+            device
+                .device
+                .cmd_set_depth_write_enable(self.buffer, enable);
+        }
+        Ok(())
+    }
+
+    /// Sets the depth comparison of a pipeline declaring `PipelineDynamicState::DepthCompareOp`.
+    #[inline]
+    pub fn set_depth_compare_op(&self, device: &Device, compare_op: CompareOp) -> NxResult<()> {
+        self.validate_dynamic_state(PipelineDynamicState::DepthCompareOp)?;
+        unsafe {
+            device
+                .device
+                .cmd_set_depth_compare_op(self.buffer, compare_op.into());
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    /// Checks that the bound pipeline declares `state` as dynamic.
+    fn validate_dynamic_state(&self, state: PipelineDynamicState) -> NxResult<()> {
+        if self.dynamic_states.load(Ordering::Relaxed) & state.mask() == 0 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the bound pipeline does not declare {:?} as a dynamic state",
+                state
+            )));
+        }
+        Ok(())
+    }
+
     /// Drawing
     #[inline]
     pub fn draw(
@@ -256,6 +1303,12 @@ impl CommandRecorder {
         }
     }
 
+    /// Draws the triangle of a pipeline created with `Pipeline::fullscreen`.
+    #[inline]
+    pub fn draw_fullscreen(&self, device: &Device) {
+        self.draw(device, 3, 1, 0, 0);
+    }
+
     /// Used when drawing with an index buffer.
     #[inline]
     pub fn draw_indexed(
@@ -279,6 +1332,128 @@ impl CommandRecorder {
         }
     }
 
+    /// Draws with the parameters in `draws`, reading the number of draws from `count`.
+    /// Requires the `draw_indirect_count` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `draws` - `DrawIndirectCommand`s, in a buffer created with `BufferUsage::Indirect`.
+    /// * `count` - 32-bit draw count, in a buffer created with `BufferUsage::Indirect`.
+    /// * `max_draw_count` - Upper bound of the draw count.
+    /// * `stride` - Distance in bytes between the draw commands.
+    pub fn draw_indirect_count(
+        &self,
+        device: &Device,
+        draws: BufferSlice,
+        count: BufferSlice,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> NxResult<()> {
+        Self::validate_indirect_count(
+            device,
+            draws,
+            count,
+            stride,
+            std::mem::size_of::<DrawIndirectCommand>(),
+        )?;
+        unsafe {
+            device.device.cmd_draw_indirect_count(
+                self.buffer,
+                draws.buffer.buffer,
+                draws.offset,
+                count.buffer.buffer,
+                count.offset,
+                max_draw_count,
+                stride,
+            );
+        }
+        Ok(())
+    }
+
+    /// Draws with an index buffer and the parameters in `draws`,
+    /// reading the number of draws from `count`.
+    /// Requires the `draw_indirect_count` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `draws` - `DrawIndexedIndirectCommand`s, in a buffer created with `BufferUsage::Indirect`.
+    /// * `count` - 32-bit draw count, in a buffer created with `BufferUsage::Indirect`.
+    /// * `max_draw_count` - Upper bound of the draw count.
+    /// * `stride` - Distance in bytes between the draw commands.
+    pub fn draw_indexed_indirect_count(
+        &self,
+        device: &Device,
+        draws: BufferSlice,
+        count: BufferSlice,
+        max_draw_count: u32,
+        stride: u32,
+    ) -> NxResult<()> {
+        Self::validate_indirect_count(
+            device,
+            draws,
+            count,
+            stride,
+            std::mem::size_of::<DrawIndexedIndirectCommand>(),
+        )?;
+        unsafe {
+            device.device.cmd_draw_indexed_indirect_count(
+                self.buffer,
+                draws.buffer.buffer,
+                draws.offset,
+                count.buffer.buffer,
+                count.offset,
+                max_draw_count,
+                stride,
+            );
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    fn validate_indirect_count(
+        device: &Device,
+        draws: BufferSlice,
+        count: BufferSlice,
+        stride: u32,
+        command_size: usize,
+    ) -> NxResult<()> {
+        if !device.features.draw_indirect_count {
+            return Err(NxError::UnsupportedFeature(
+                "indirect count draws require the draw_indirect_count device feature".to_string(),
+            ));
+        }
+        for (name, slice) in [("draw", draws), ("count", count)] {
+            if !slice
+                .buffer
+                .usage
+                .contains(BufferUsageFlags::INDIRECT_BUFFER)
+            {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "the {} buffer was not created with BufferUsage::Indirect",
+                    name
+                )));
+            }
+            if slice.offset % 4 != 0 {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "{} buffer offset {} is not a multiple of 4",
+                    name, slice.offset
+                )));
+            }
+        }
+        if count.size < 4 {
+            return Err(NxError::InvalidDescriptor(
+                "the count buffer slice is smaller than 4 bytes".to_string(),
+            ));
+        }
+        if stride % 4 != 0 || (stride as usize) < command_size {
+            return Err(NxError::InvalidDescriptor(format!(
+                "stride {} must be a multiple of 4 and at least {}",
+                stride, command_size
+            )));
+        }
+        Ok(())
+    }
+
     /// Reset CommandRecorder.
     #[inline]
     pub fn reset(&self, device: &Device) -> NxResult<()> {
@@ -293,3 +1468,42 @@ impl CommandRecorder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{ClearColor, CommandRecorder};
+    use crate::ImageFormat;
+    use ash::vk::BufferUsageFlags;
+
+    const USAGE: BufferUsageFlags = BufferUsageFlags::TRANSFER_DST;
+
+    #[test]
+    fn accepts_transfer_range() {
+        assert!(CommandRecorder::validate_transfer_range(USAGE, 16, 4, 12).is_ok());
+    }
+
+    #[test]
+    fn rejects_invalid_transfer_range() {
+        let validate = CommandRecorder::validate_transfer_range;
+        assert!(validate(BufferUsageFlags::UNIFORM_BUFFER, 16, 0, 16).is_err());
+        assert!(validate(USAGE, 16, 2, 4).is_err());
+        assert!(validate(USAGE, 16, 8, 12).is_err());
+        assert!(validate(USAGE, 16, u64::MAX - 3, 4).is_err());
+    }
+
+    #[test]
+    fn rejects_empty_transfer_range() {
+        assert!(CommandRecorder::validate_transfer_range(USAGE, 16, 0, 0).is_err());
+    }
+
+    #[test]
+    fn clear_color_matches_format_class() {
+        assert!(ClearColor::Float([0.0; 4]).matches(ImageFormat::R8G8B8A8Unorm));
+        assert!(ClearColor::Int([-1; 4]).matches(ImageFormat::R8G8B8A8Sint));
+        assert!(ClearColor::Uint([u32::MAX; 4]).matches(ImageFormat::R8G8B8A8Uint));
+        assert!(!ClearColor::Float([0.0; 4]).matches(ImageFormat::R8G8B8A8Uint));
+        assert!(!ClearColor::Int([0; 4]).matches(ImageFormat::R8G8B8A8Uint));
+        assert!(!ClearColor::Uint([0; 4]).matches(ImageFormat::R8G8B8A8Sint));
+        assert!(!ClearColor::Uint([0; 4]).matches(ImageFormat::R8G8B8A8Unorm));
+    }
+}
@@ -0,0 +1,558 @@
+use std::collections::{HashMap, HashSet};
+
+use ash::vk::ShaderStageFlags;
+use rspirv::dr::{Instruction, Operand};
+use rspirv::spirv::{Decoration, Dim, ExecutionModel, Op, StorageClass, Word};
+
+use crate::{
+    NxError, NxResult, PipelineDescriptor, PipelineIssue, PushConstantRange, RenderPass,
+    ResourceLayoutBinding, ResourceType, ShaderStage, Spirv,
+};
+
+/// A resource binding declared by a shader module.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReflectedBinding {
+    set: u32,
+    binding: u32,
+    resource_type: ResourceType,
+    count: u32,
+    stage: ShaderStageFlags,
+}
+
+impl ReflectedBinding {
+    /// Index of the descriptor set the binding belongs to.
+    pub fn set(&self) -> u32 {
+        self.set
+    }
+
+    /// Binding number within the set.
+    pub fn binding(&self) -> u32 {
+        self.binding
+    }
+
+    /// Type of the resource.
+    pub fn resource_type(&self) -> ResourceType {
+        self.resource_type
+    }
+
+    /// Number of array elements of the binding.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+}
+
+/// Resource layout derived from one or more shader modules.
+///
+/// Layouts of several stages can be combined with [`ReflectedLayout::merge`].
+#[derive(Clone, Debug, Default)]
+pub struct ReflectedLayout {
+    bindings: Vec<ReflectedBinding>,
+    push_constant_ranges: Vec<PushConstantRange>,
+}
+
+impl ReflectedLayout {
+    /// All bindings of all descriptor sets.
+    pub fn reflected_bindings(&self) -> &[ReflectedBinding] {
+        &self.bindings
+    }
+
+    /// Indices of the descriptor sets used by the shaders, in ascending order.
+    pub fn sets(&self) -> Vec<u32> {
+        let mut sets = self.bindings.iter().map(|x| x.set).collect::<Vec<u32>>();
+        sets.sort_unstable();
+        sets.dedup();
+        sets
+    }
+
+    /// Bindings of the given descriptor set, ready to be passed to `ResourceLayout::new`.
+    pub fn bindings(&self, set: u32) -> Vec<ResourceLayoutBinding> {
+        self.bindings
+            .iter()
+            .filter(|x| x.set == set)
+            .map(|x| {
+                let mut binding = ResourceLayoutBinding::empty()
+                    .binding(x.binding)
+                    .resource_type(x.resource_type)
+                    .count(x.count);
+                binding.flags = x.stage;
+                binding
+            })
+            .collect()
+    }
+
+    /// Push constant ranges, ready to be passed to `PipelineLayoutDescriptor`.
+    pub fn push_constant_ranges(&self) -> &[PushConstantRange] {
+        &self.push_constant_ranges
+    }
+
+    /// Combines the layout of another stage into this one.
+    ///
+    /// Bindings that appear in both layouts become visible to both stages.
+    /// Fails if the same binding is declared with different resource types.
+    pub fn merge(mut self, other: ReflectedLayout) -> NxResult<Self> {
+        for binding in other.bindings {
+            match self
+                .bindings
+                .iter_mut()
+                .find(|x| x.set == binding.set && x.binding == binding.binding)
+            {
+                None => self.bindings.push(binding),
+                Some(x) => {
+                    if x.resource_type != binding.resource_type {
+                        return Err(NxError::ReflectionError(format!(
+                            "set {} binding {} is declared as both {:?} and {:?}",
+                            x.set, x.binding, x.resource_type, binding.resource_type
+                        )));
+                    }
+                    x.count = x.count.max(binding.count);
+                    x.stage |= binding.stage;
+                }
+            }
+        }
+        for range in other.push_constant_ranges {
+            match self
+                .push_constant_ranges
+                .iter_mut()
+                .find(|x| x.offset == range.offset && x.size == range.size)
+            {
+                None => self.push_constant_ranges.push(range),
+                Some(x) => x.stage |= range.stage,
+            }
+        }
+        Ok(self)
+    }
+}
+
+impl<'a> PipelineDescriptor<'a> {
+    /// Like `validate`, but also checks that `sets` and `push_constant_ranges`,
+    /// the layout the pipeline will be created with, provide every resource the shaders use.
+    /// `sets` holds the bindings of each resource layout,
+    /// in the order given to `PipelineLayoutDescriptor::resources`.
+    /// **"reflect" feature is required.**
+    pub fn validate_with_layout(
+        &self,
+        render_pass: &RenderPass,
+        shader_layout: &ReflectedLayout,
+        sets: &[&[ResourceLayoutBinding]],
+        push_constant_ranges: &[PushConstantRange],
+    ) -> NxResult<()> {
+        let mut issues = self.issues(render_pass);
+        for set in shader_layout.sets() {
+            if sets.get(set as usize).is_none() {
+                issues.push(PipelineIssue::MissingSet(set));
+            }
+        }
+        for shader in &shader_layout.bindings {
+            let bindings = match sets.get(shader.set as usize) {
+                None => continue,
+                Some(x) => x,
+            };
+            let layout = match bindings.iter().find(|x| x.binding == shader.binding) {
+                None => {
+                    issues.push(PipelineIssue::MissingResource {
+                        set: shader.set,
+                        binding: shader.binding,
+                    });
+                    continue;
+                }
+                Some(x) => x,
+            };
+            if layout.desc_type != shader.resource_type {
+                issues.push(PipelineIssue::ResourceTypeMismatch {
+                    set: shader.set,
+                    binding: shader.binding,
+                    shader: shader.resource_type,
+                    layout: layout.desc_type,
+                });
+            }
+            if layout.count < shader.count {
+                issues.push(PipelineIssue::ResourceCountTooSmall {
+                    set: shader.set,
+                    binding: shader.binding,
+                    shader: shader.count,
+                    layout: layout.count,
+                });
+            }
+            if !layout.flags.contains(shader.stage) {
+                issues.push(PipelineIssue::ResourceStageMissing {
+                    set: shader.set,
+                    binding: shader.binding,
+                });
+            }
+        }
+        for range in &shader_layout.push_constant_ranges {
+            let covered = push_constant_ranges.iter().any(|x| {
+                x.stage.contains(range.stage)
+                    && x.offset <= range.offset
+                    && range.offset + range.size <= x.offset + x.size
+            });
+            if !covered {
+                issues.push(PipelineIssue::PushConstantNotCovered {
+                    offset: range.offset,
+                    size: range.size,
+                });
+            }
+        }
+        PipelineIssue::into_result(issues)
+    }
+}
+
+impl Spirv {
+    /// Derives the resource bindings and push constant ranges declared by the module.
+    /// Only vertex and fragment shaders can be reflected,
+    /// modules with other entry points such as compute shaders are rejected.
+    /// Malformed modules return `NxError::ReflectionError`.
+    /// **"reflect" feature is required.**
+    pub fn reflect(&self) -> NxResult<ReflectedLayout> {
+        let module = match rspirv::dr::load_words(&self.data) {
+            Ok(x) => x,
+            Err(e) => return Err(NxError::ReflectionError(e.to_string())),
+        };
+
+        let mut stage = None;
+        for entry_point in &module.entry_points {
+            let entry_stage = match execution_model(entry_point, 0)? {
+                ExecutionModel::Vertex => ShaderStage::Vertex,
+                ExecutionModel::Fragment => ShaderStage::Fragment,
+                e => {
+                    return Err(NxError::ReflectionError(format!(
+                        "only vertex and fragment shaders can be reflected, not {:?}",
+                        e
+                    )))
+                }
+            };
+            match stage {
+                Some(x) if x != entry_stage => {
+                    return Err(NxError::ReflectionError(
+                        "module has entry points of several stages".to_string(),
+                    ))
+                }
+                _ => stage = Some(entry_stage),
+            }
+        }
+        let stage = match stage {
+            None => {
+                return Err(NxError::ReflectionError(
+                    "module has no entry point".to_string(),
+                ))
+            }
+            Some(x) => x,
+        };
+
+        let decorations = Decorations::new(&module.annotations)?;
+        let types = module
+            .types_global_values
+            .iter()
+            .filter_map(|x| x.result_id.map(|id| (id, x)))
+            .collect::<HashMap<Word, &Instruction>>();
+        let reflector = Reflector { types, decorations };
+
+        let mut layout = ReflectedLayout::default();
+        for variable in module
+            .types_global_values
+            .iter()
+            .filter(|x| x.class.opcode == Op::Variable)
+        {
+            let (id, result_type) = match (variable.result_id, variable.result_type) {
+                (Some(id), Some(result_type)) => (id, result_type),
+                _ => return Err(malformed(variable)),
+            };
+            let pointee = id_ref(reflector.get(result_type)?, 1)?;
+            match storage_class(variable, 0)? {
+                StorageClass::PushConstant => {
+                    let (offset, size) = reflector.block_range(pointee)?;
+                    layout.push_constant_ranges.push(
+                        PushConstantRange::empty()
+                            .stage(stage)
+                            .offset(offset)
+                            .size(size),
+                    );
+                }
+                StorageClass::Uniform
+                | StorageClass::UniformConstant
+                | StorageClass::StorageBuffer => {
+                    let binding = match reflector.decorations.binding.get(&id) {
+                        None => continue,
+                        Some(x) => *x,
+                    };
+                    let set = reflector.decorations.set.get(&id).copied().unwrap_or(0);
+                    let (element, count) = reflector.unwrap_array(pointee)?;
+                    let resource_type = reflector.resource_type(element)?;
+                    layout.bindings.push(ReflectedBinding {
+                        set,
+                        binding,
+                        resource_type,
+                        count,
+                        stage: stage.into(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Ok(layout)
+    }
+}
+
+#[doc(hidden)]
+#[derive(Default)]
+struct Decorations {
+    set: HashMap<Word, u32>,
+    binding: HashMap<Word, u32>,
+    block: HashSet<Word>,
+    buffer_block: HashSet<Word>,
+    array_stride: HashMap<Word, u32>,
+    member_offset: HashMap<(Word, u32), u32>,
+    matrix_stride: HashMap<(Word, u32), u32>,
+}
+
+impl Decorations {
+    fn new(annotations: &[Instruction]) -> NxResult<Self> {
+        let mut decorations = Self::default();
+        for i in annotations {
+            match i.class.opcode {
+                Op::Decorate => {
+                    let target = id_ref(i, 0)?;
+                    let literal = literal(i.operands.get(2));
+                    match decoration(i, 1)? {
+                        Decoration::DescriptorSet => {
+                            decorations.set.insert(target, literal.unwrap_or(0));
+                        }
+                        Decoration::Binding => {
+                            decorations.binding.insert(target, literal.unwrap_or(0));
+                        }
+                        Decoration::Block => {
+                            decorations.block.insert(target);
+                        }
+                        Decoration::BufferBlock => {
+                            decorations.buffer_block.insert(target);
+                        }
+                        Decoration::ArrayStride => {
+                            decorations
+                                .array_stride
+                                .insert(target, literal.unwrap_or(0));
+                        }
+                        _ => {}
+                    }
+                }
+                Op::MemberDecorate => {
+                    let target = id_ref(i, 0)?;
+                    let member = literal_bit32(i, 1)?;
+                    let literal = literal(i.operands.get(3));
+                    match decoration(i, 2)? {
+                        Decoration::Offset => {
+                            decorations
+                                .member_offset
+                                .insert((target, member), literal.unwrap_or(0));
+                        }
+                        Decoration::MatrixStride => {
+                            decorations
+                                .matrix_stride
+                                .insert((target, member), literal.unwrap_or(0));
+                        }
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(decorations)
+    }
+}
+
+#[doc(hidden)]
+fn literal(operand: Option<&Operand>) -> Option<u32> {
+    match operand {
+        Some(Operand::LiteralBit32(x)) => Some(*x),
+        _ => None,
+    }
+}
+
+#[doc(hidden)]
+fn malformed(instruction: &Instruction) -> NxError {
+    NxError::ReflectionError(format!(
+        "malformed {:?} instruction",
+        instruction.class.opcode
+    ))
+}
+
+/// Defines a function returning an operand of the given kind,
+/// in place of `Operand::unwrap_*` which panics on malformed modules.
+macro_rules! operand {
+    ($name:ident, $variant:ident, $ty:ty) => {
+        #[doc(hidden)]
+        fn $name(instruction: &Instruction, index: usize) -> NxResult<$ty> {
+            match instruction.operands.get(index) {
+                Some(Operand::$variant(x)) => Ok(*x),
+                _ => Err(malformed(instruction)),
+            }
+        }
+    };
+}
+
+operand!(id_ref, IdRef, Word);
+operand!(literal_bit32, LiteralBit32, u32);
+operand!(storage_class, StorageClass, StorageClass);
+operand!(execution_model, ExecutionModel, ExecutionModel);
+operand!(decoration, Decoration, Decoration);
+operand!(dim, Dim, Dim);
+
+#[doc(hidden)]
+fn overflow() -> NxError {
+    NxError::ReflectionError("type size overflows".to_string())
+}
+
+#[doc(hidden)]
+struct Reflector<'a> {
+    types: HashMap<Word, &'a Instruction>,
+    decorations: Decorations,
+}
+
+impl<'a> Reflector<'a> {
+    fn get(&self, id: Word) -> NxResult<&'a Instruction> {
+        match self.types.get(&id) {
+            Some(x) => Ok(x),
+            None => Err(NxError::ReflectionError(format!("undefined id %{}", id))),
+        }
+    }
+
+    fn constant(&self, id: Word) -> NxResult<u32> {
+        let constant = self.get(id)?;
+        match (constant.class.opcode, constant.operands.first()) {
+            (Op::Constant, Some(Operand::LiteralBit32(x))) => Ok(*x),
+            (Op::Constant, Some(Operand::LiteralBit64(x))) => Ok(*x as u32),
+            _ => Err(NxError::ReflectionError(format!(
+                "%{} is not an integer constant",
+                id
+            ))),
+        }
+    }
+
+    /// Strips an array type, returning the element type and the element count.
+    fn unwrap_array(&self, id: Word) -> NxResult<(Word, u32)> {
+        let ty = self.get(id)?;
+        match ty.class.opcode {
+            Op::TypeArray => Ok((id_ref(ty, 0)?, self.constant(id_ref(ty, 1)?)?)),
+            Op::TypeRuntimeArray => Err(NxError::ReflectionError(
+                "runtime-sized descriptor arrays are not supported".to_string(),
+            )),
+            _ => Ok((id, 1)),
+        }
+    }
+
+    fn resource_type(&self, id: Word) -> NxResult<ResourceType> {
+        let ty = self.get(id)?;
+        match ty.class.opcode {
+            Op::TypeStruct if self.decorations.buffer_block.contains(&id) => {
+                Ok(ResourceType::StorageBuffer)
+            }
+            Op::TypeStruct if self.decorations.block.contains(&id) => {
+                // Blocks in the StorageBuffer storage class are indistinguishable by type alone,
+                // so look at how the pointer to this block was declared.
+                let storage = self.types.values().any(|x| {
+                    x.class.opcode == Op::TypePointer
+                        && storage_class(x, 0).ok() == Some(StorageClass::StorageBuffer)
+                        && id_ref(x, 1).ok() == Some(id)
+                });
+                if storage {
+                    Ok(ResourceType::StorageBuffer)
+                } else {
+                    Ok(ResourceType::UniformBuffer)
+                }
+            }
+            Op::TypeSampledImage => Ok(ResourceType::CombinedImageSampler),
+            Op::TypeSampler => Ok(ResourceType::Sampler),
+            Op::TypeImage => {
+                if dim(ty, 1)? == Dim::DimBuffer {
+                    return Err(NxError::ReflectionError(
+                        "texel buffers are not supported".to_string(),
+                    ));
+                }
+                match literal_bit32(ty, 5)? {
+                    2 => Ok(ResourceType::StorageImage),
+                    _ => Ok(ResourceType::SampledImage),
+                }
+            }
+            op => Err(NxError::ReflectionError(format!(
+                "unsupported resource type {:?}",
+                op
+            ))),
+        }
+    }
+
+    /// Returns the offset of the first member and the byte size of a block.
+    fn block_range(&self, id: Word) -> NxResult<(u32, u32)> {
+        let ty = self.get(id)?;
+        if ty.class.opcode != Op::TypeStruct {
+            return Err(NxError::ReflectionError(format!(
+                "push constant %{} is not a block",
+                id
+            )));
+        }
+        let offset = (0..ty.operands.len() as u32)
+            .map(|m| {
+                self.decorations
+                    .member_offset
+                    .get(&(id, m))
+                    .copied()
+                    .unwrap_or(0)
+            })
+            .min()
+            .unwrap_or(0);
+        let size = self.size_of(id)?;
+        Ok((offset, size - offset))
+    }
+
+    fn size_of(&self, id: Word) -> NxResult<u32> {
+        let ty = self.get(id)?;
+        match ty.class.opcode {
+            Op::TypeBool => Ok(4),
+            Op::TypeInt | Op::TypeFloat => Ok(literal_bit32(ty, 0)? / 8),
+            Op::TypeVector | Op::TypeMatrix => {
+                let count = literal_bit32(ty, 1)?;
+                count
+                    .checked_mul(self.size_of(id_ref(ty, 0)?)?)
+                    .ok_or_else(overflow)
+            }
+            Op::TypeArray => {
+                let length = self.constant(id_ref(ty, 1)?)?;
+                let stride = match self.decorations.array_stride.get(&id) {
+                    Some(x) => *x,
+                    None => self.size_of(id_ref(ty, 0)?)?,
+                };
+                length.checked_mul(stride).ok_or_else(overflow)
+            }
+            Op::TypeStruct => {
+                let mut size = 0;
+                let mut offset = 0;
+                for m in 0..ty.operands.len() {
+                    let member = id_ref(ty, m)?;
+                    let key = (id, m as u32);
+                    offset = self
+                        .decorations
+                        .member_offset
+                        .get(&key)
+                        .copied()
+                        .unwrap_or(offset);
+                    let member_ty = self.get(member)?;
+                    let member_size = match (
+                        member_ty.class.opcode,
+                        self.decorations.matrix_stride.get(&key),
+                    ) {
+                        (Op::TypeMatrix, Some(stride)) => literal_bit32(member_ty, 1)?
+                            .checked_mul(*stride)
+                            .ok_or_else(overflow)?,
+                        _ => self.size_of(member)?,
+                    };
+                    offset = offset.checked_add(member_size).ok_or_else(overflow)?;
+                    size = size.max(offset);
+                }
+                Ok(size)
+            }
+            op => Err(NxError::ReflectionError(format!(
+                "cannot compute the size of {:?}",
+                op
+            ))),
+        }
+    }
+}
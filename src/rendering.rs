@@ -0,0 +1,324 @@
+use crate::{Extent2d, ImageLayout, ImageView, LoadOp, NxError, NxResult, SampleCount, StoreOp};
+use ash::vk::{
+    ClearColorValue, ClearDepthStencilValue, ClearValue, Offset2D, Rect2D, RenderingAttachmentInfo,
+    ResolveModeFlags,
+};
+
+/// Value an attachment is cleared to when its load operation is `LoadOp::Clear`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AttachmentClear {
+    Color([f32; 4]),
+    DepthStencil(f32, u32),
+}
+
+/// How the samples of a multisampled attachment are combined when it is resolved.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResolveMode {
+    /// Uses the value of sample 0. Required for color attachments with an integer format.
+    SampleZero,
+    /// Averages every sample. Required for color attachments with a non-integer format.
+    Average,
+    /// Uses the smallest sample. Only valid for depth and stencil attachments.
+    Min,
+    /// Uses the largest sample. Only valid for depth and stencil attachments.
+    Max,
+}
+
+impl From<ResolveMode> for ResolveModeFlags {
+    fn from(value: ResolveMode) -> Self {
+        match value {
+            ResolveMode::SampleZero => ResolveModeFlags::SAMPLE_ZERO,
+            ResolveMode::Average => ResolveModeFlags::AVERAGE,
+            ResolveMode::Min => ResolveModeFlags::MIN,
+            ResolveMode::Max => ResolveModeFlags::MAX,
+        }
+    }
+}
+
+/// Stores information about an attachment used by `CommandRecorder::begin_rendering`.
+#[derive(Clone, Copy)]
+pub struct RenderingAttachment<'a> {
+    view: &'a ImageView,
+    layout: ImageLayout,
+    load_op: LoadOp,
+    store_op: StoreOp,
+    clear: Option<AttachmentClear>,
+    resolve: Option<(&'a ImageView, ImageLayout)>,
+    resolve_mode: Option<ResolveMode>,
+}
+
+impl<'a> RenderingAttachment<'a> {
+    /// Initializes a new attachment that loads and stores its contents.
+    /// # Arguments
+    ///
+    /// * `view` - ImageView rendered to.
+    /// * `layout` - Layout of the image during rendering.
+    #[inline]
+    pub fn new(view: &'a ImageView, layout: ImageLayout) -> Self {
+        Self {
+            view,
+            layout,
+            load_op: LoadOp::Load,
+            store_op: StoreOp::Store,
+            clear: None,
+            resolve: None,
+            resolve_mode: None,
+        }
+    }
+
+    /// Specifies what happens to the contents at the start of rendering.
+    /// `LoadOp::Clear` requires a clear value.
+    #[inline]
+    pub fn load_op(mut self, load_op: LoadOp) -> Self {
+        self.load_op = load_op;
+        self
+    }
+
+    /// Specifies what happens to the contents at the end of rendering.
+    /// `StoreOp::DontCare` saves bandwidth on tiled GPUs when the contents are not read later.
+    #[inline]
+    pub fn store_op(mut self, store_op: StoreOp) -> Self {
+        self.store_op = store_op;
+        self
+    }
+
+    /// Specifies the color a color attachment is cleared to.
+    #[inline]
+    pub fn clear_color(mut self, color: [f32; 4]) -> Self {
+        self.clear = Some(AttachmentClear::Color(color));
+        self
+    }
+
+    /// Specifies the values a depth or stencil attachment is cleared to.
+    #[inline]
+    pub fn clear_depth_stencil(mut self, depth: f32, stencil: u32) -> Self {
+        self.clear = Some(AttachmentClear::DepthStencil(depth, stencil));
+        self
+    }
+
+    /// Resolves the multisampled contents into `view` at the end of rendering.
+    /// `view` must be single-sampled and have the same format as the attachment.
+    #[inline]
+    pub fn resolve(mut self, view: &'a ImageView, layout: ImageLayout) -> Self {
+        self.resolve = Some((view, layout));
+        self
+    }
+
+    /// Specifies how the samples are combined when resolving.
+    /// Defaults to `ResolveMode::Average` for color attachments with a non-integer format,
+    /// otherwise `ResolveMode::SampleZero`.
+    #[inline]
+    pub fn resolve_mode(mut self, mode: ResolveMode) -> Self {
+        self.resolve_mode = Some(mode);
+        self
+    }
+
+    #[doc(hidden)]
+    fn resolved_mode(&self, depth_stencil: bool) -> ResolveMode {
+        self.resolve_mode.unwrap_or_else(|| {
+            if depth_stencil || self.view.format.is_integer() {
+                ResolveMode::SampleZero
+            } else {
+                ResolveMode::Average
+            }
+        })
+    }
+
+    #[doc(hidden)]
+    fn validate_resolve(&self, name: &str, depth_stencil: bool) -> NxResult<()> {
+        let Some((view, _)) = self.resolve else {
+            return match self.resolve_mode {
+                Some(_) => Err(NxError::InvalidDescriptor(format!(
+                    "{} has a resolve mode but no resolve view",
+                    name
+                ))),
+                None => Ok(()),
+            };
+        };
+        if self.view.samples == SampleCount::X1 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "{} is resolved but is not multisampled",
+                name
+            )));
+        }
+        if view.samples != SampleCount::X1 {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the resolve view of {} is multisampled",
+                name
+            )));
+        }
+        if view.format != self.view.format {
+            return Err(NxError::InvalidDescriptor(format!(
+                "the resolve view of {} has format {:?} but the attachment has format {:?}",
+                name, view.format, self.view.format
+            )));
+        }
+        let mode = self.resolved_mode(depth_stencil);
+        if depth_stencil && mode == ResolveMode::Average {
+            return Err(NxError::InvalidDescriptor(format!(
+                "{} can not be resolved by averaging",
+                name
+            )));
+        }
+        // Color attachments are averaged, unless their values are integers.
+        let color_mode = match self.view.format.is_integer() {
+            true => ResolveMode::SampleZero,
+            false => ResolveMode::Average,
+        };
+        if !depth_stencil && mode != color_mode {
+            return Err(NxError::InvalidDescriptor(format!(
+                "{} must be resolved with {:?} but uses {:?}",
+                name, color_mode, mode
+            )));
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    fn validate(&self, name: &str, depth_stencil: bool) -> NxResult<()> {
+        self.validate_resolve(name, depth_stencil)?;
+        match self.clear {
+            None if self.load_op == LoadOp::Clear => Err(NxError::InvalidDescriptor(format!(
+                "{} uses LoadOp::Clear but has no clear value",
+                name
+            ))),
+            Some(AttachmentClear::Color(_)) if depth_stencil => Err(NxError::InvalidDescriptor(
+                format!("{} is cleared with a color", name),
+            )),
+            Some(AttachmentClear::DepthStencil(..)) if !depth_stencil => {
+                Err(NxError::InvalidDescriptor(format!(
+                    "{} is cleared with depth and stencil values",
+                    name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn to_vk(self, depth_stencil: bool) -> RenderingAttachmentInfo {
+        let clear_value = match self.clear {
+            Some(AttachmentClear::Color(color)) => ClearValue {
+                color: ClearColorValue { float32: color },
+            },
+            Some(AttachmentClear::DepthStencil(depth, stencil)) => ClearValue {
+                depth_stencil: ClearDepthStencilValue { depth, stencil },
+            },
+            None => ClearValue::default(),
+        };
+        let mut info = RenderingAttachmentInfo::builder()
+            .image_view(self.view.image_view)
+            .image_layout(self.layout.into())
+            .load_op(self.load_op.into())
+            .store_op(self.store_op.into())
+            .clear_value(clear_value);
+        if let Some((view, layout)) = self.resolve {
+            info = info
+                .resolve_mode(self.resolved_mode(depth_stencil).into())
+                .resolve_image_view(view.image_view)
+                .resolve_image_layout(layout.into());
+        }
+        info.build()
+    }
+}
+
+/// Stores information needed to start rendering with `CommandRecorder::begin_rendering`.
+#[derive(Clone)]
+pub struct RenderingInfo<'a> {
+    pub(crate) offset: [i32; 2],
+    pub(crate) extent: Extent2d,
+    pub(crate) layer_count: u32,
+    pub(crate) view_mask: u32,
+    pub(crate) color_attachments: &'a [RenderingAttachment<'a>],
+    pub(crate) depth_attachment: Option<RenderingAttachment<'a>>,
+    pub(crate) stencil_attachment: Option<RenderingAttachment<'a>>,
+}
+
+impl<'a> RenderingInfo<'a> {
+    /// Initializes a new descriptor with default values.
+    /// # Arguments
+    ///
+    /// * `extent` - Size of the area rendered to.
+    #[inline]
+    pub fn new(extent: Extent2d) -> Self {
+        Self {
+            offset: [0, 0],
+            extent,
+            layer_count: 1,
+            view_mask: 0,
+            color_attachments: &[],
+            depth_attachment: None,
+            stencil_attachment: None,
+        }
+    }
+
+    /// Specifies the offset of the area rendered to.
+    #[inline]
+    pub fn offset(mut self, x: i32, y: i32) -> Self {
+        self.offset = [x, y];
+        self
+    }
+
+    /// Specifies the number of layers rendered to when `view_mask` is 0.
+    #[inline]
+    pub fn layer_count(mut self, layer_count: u32) -> Self {
+        self.layer_count = layer_count;
+        self
+    }
+
+    /// Bit mask of the views rendered to. Requires the `multiview` device feature if not 0.
+    #[inline]
+    pub fn view_mask(mut self, view_mask: u32) -> Self {
+        self.view_mask = view_mask;
+        self
+    }
+
+    /// Specifies the color attachments, in the order of the fragment shader outputs.
+    #[inline]
+    pub fn color_attachments(mut self, attachments: &'a [RenderingAttachment<'a>]) -> Self {
+        self.color_attachments = attachments;
+        self
+    }
+
+    /// Specifies the depth attachment.
+    #[inline]
+    pub fn depth_attachment(mut self, attachment: RenderingAttachment<'a>) -> Self {
+        self.depth_attachment = Some(attachment);
+        self
+    }
+
+    /// Specifies the stencil attachment.
+    /// For a combined depth/stencil format it must use the same view as the depth attachment.
+    #[inline]
+    pub fn stencil_attachment(mut self, attachment: RenderingAttachment<'a>) -> Self {
+        self.stencil_attachment = Some(attachment);
+        self
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn validate(&self) -> NxResult<()> {
+        for (i, attachment) in self.color_attachments.iter().enumerate() {
+            attachment.validate(&format!("color attachment {}", i), false)?;
+        }
+        if let Some(attachment) = &self.depth_attachment {
+            attachment.validate("the depth attachment", true)?;
+        }
+        if let Some(attachment) = &self.stencil_attachment {
+            attachment.validate("the stencil attachment", true)?;
+        }
+        Ok(())
+    }
+
+    #[doc(hidden)]
+    pub(crate) fn render_area(&self) -> Rect2D {
+        Rect2D::builder()
+            .offset(
+                Offset2D::builder()
+                    .x(self.offset[0])
+                    .y(self.offset[1])
+                    .build(),
+            )
+            .extent(self.extent.into())
+            .build()
+    }
+}
@@ -1,10 +1,14 @@
 use ash::vk::{
-    AttachmentDescription, AttachmentLoadOp, AttachmentReference, AttachmentStoreOp, Format,
-    ImageLayout, RenderPassCreateInfo, SampleCountFlags, SubpassDescription,
+    AttachmentDescription, AttachmentDescription2, AttachmentDescriptionStencilLayout,
+    AttachmentLoadOp, AttachmentReference, AttachmentReference2, AttachmentReferenceStencilLayout,
+    AttachmentStoreOp, Format, ImageAspectFlags, ImageLayout, RenderPassCreateInfo,
+    RenderPassCreateInfo2, RenderPassMultiviewCreateInfo, SampleCountFlags, SubpassDescription,
+    SubpassDescription2,
 };
 
 use crate::{
-    BindPoint, Destroy, Device, DeviceConnecter, FrameBuffer, Instance, NxError, NxResult,
+    BindPoint, Destroy, Device, DeviceConnecter, FrameBuffer, ImageFormat, Instance, NxError,
+    NxResult,
 };
 
 /// Stores information needed to start a render pass.
@@ -20,6 +24,8 @@ pub struct RenderPassBeginDescriptor<'a> {
     pub(crate) g: f32,
     pub(crate) b: f32,
     pub(crate) a: f32,
+    pub(crate) depth: f32,
+    pub(crate) stencil: u32,
 }
 
 impl<'a> RenderPassBeginDescriptor<'a> {
@@ -36,6 +42,8 @@ impl<'a> RenderPassBeginDescriptor<'a> {
             g: 0.0,
             b: 0.0,
             a: 1.0,
+            depth: 1.0,
+            stencil: 0,
         }
     }
 
@@ -60,6 +68,13 @@ impl<'a> RenderPassBeginDescriptor<'a> {
         self
     }
 
+    /// Specify the values depth/stencil attachments are cleared to. Defaults to 1.0 and 0.
+    pub fn depth_stencil(mut self, depth: f32, stencil: u32) -> Self {
+        self.depth = depth;
+        self.stencil = stencil;
+        self
+    }
+
     #[must_use]
     pub fn frame_buffer(mut self, frame_buffer: &'a FrameBuffer) -> Self {
         self.frame_buffer = Some(frame_buffer);
@@ -105,42 +120,158 @@ impl From<StoreOp> for AttachmentStoreOp {
     }
 }
 
-/// Stores information needed to create a FrameBuffer.
-pub struct SubPassDescriptor {
+/// Stores information needed to create a SubPass.
+pub struct SubPassDescriptor<'a> {
     bind_point: BindPoint,
+    color_attachments: &'a [u32],
+    depth_stencil_attachment: Option<(u32, crate::ImageLayout)>,
+    stencil_layout: Option<crate::ImageLayout>,
 }
 
-impl SubPassDescriptor {
+impl<'a> SubPassDescriptor<'a> {
     /// Initializes a new descriptor with default values.
     #[inline]
     pub fn empty() -> Self {
         Self {
             bind_point: BindPoint::Graphics,
+            color_attachments: &[],
+            depth_stencil_attachment: None,
+            stencil_layout: None,
         }
     }
+
+    /// Indices of the render pass attachments written as color attachments.
+    /// If nothing is specified, attachment 0 is used.
+    #[inline]
+    pub fn color_attachments(mut self, color_attachments: &'a [u32]) -> Self {
+        self.color_attachments = color_attachments;
+        self
+    }
+
+    /// Index of the render pass attachment used as the depth/stencil attachment,
+    /// and the layout it is used in.
+    #[inline]
+    pub fn depth_stencil_attachment(mut self, attachment: u32, layout: crate::ImageLayout) -> Self {
+        self.depth_stencil_attachment = Some((attachment, layout));
+        self
+    }
+
+    /// Uses a different layout for the stencil aspect of the depth/stencil attachment.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    #[inline]
+    pub fn stencil_layout(mut self, layout: crate::ImageLayout) -> Self {
+        self.stencil_layout = Some(layout);
+        self
+    }
 }
 
 pub struct SubPass {
-    subpass_desc: SubpassDescription,
+    bind_point: BindPoint,
+    color_attachment_refs: Vec<AttachmentReference>,
+    depth_stencil_attachment_ref: Option<AttachmentReference>,
+    stencil_layout: Option<crate::ImageLayout>,
+    uses_separate_depth_stencil: bool,
 }
 
 impl SubPass {
-    /// Create a new Framebuffer.
+    /// Create a new SubPass.
     /// # Arguments
     ///
     /// * `connecter` - Appropriate DeviceConnecter.
     /// * `descriptor` - Appropriate SubPassDescriptor.
     #[inline]
     pub fn new(_connecter: DeviceConnecter, descriptor: &SubPassDescriptor) -> Self {
-        let attachment_refs = vec![AttachmentReference::builder()
-            .attachment(0)
-            .layout(ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
-            .build()];
-        let subpass_desc = SubpassDescription::builder()
-            .pipeline_bind_point(descriptor.bind_point.into())
-            .color_attachments(&attachment_refs)
-            .build();
-        Self { subpass_desc }
+        let color_attachments: &[u32] = if descriptor.color_attachments.is_empty() {
+            &[0]
+        } else {
+            descriptor.color_attachments
+        };
+        let color_attachment_refs = color_attachments
+            .iter()
+            .map(|x| {
+                AttachmentReference::builder()
+                    .attachment(*x)
+                    .layout(ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+                    .build()
+            })
+            .collect::<Vec<AttachmentReference>>();
+        let depth_stencil_attachment_ref =
+            descriptor.depth_stencil_attachment.map(|(i, layout)| {
+                AttachmentReference::builder()
+                    .attachment(i)
+                    .layout(layout.into())
+                    .build()
+            });
+        let uses_separate_depth_stencil = descriptor.stencil_layout.is_some()
+            || descriptor
+                .depth_stencil_attachment
+                .is_some_and(|(_, layout)| layout.is_separate_depth_stencil());
+        Self {
+            bind_point: descriptor.bind_point,
+            color_attachment_refs,
+            depth_stencil_attachment_ref,
+            stencil_layout: descriptor.stencil_layout,
+            uses_separate_depth_stencil,
+        }
+    }
+}
+
+/// Describes an attachment of a RenderPass.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AttachmentDescriptor {
+    format: ImageFormat,
+    load_op: LoadOp,
+    store_op: StoreOp,
+    final_layout: crate::ImageLayout,
+    stencil_final_layout: Option<crate::ImageLayout>,
+}
+
+impl AttachmentDescriptor {
+    /// Initializes a new descriptor with default values.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            format: ImageFormat::R8G8B8A8Unorm,
+            load_op: LoadOp::Clear,
+            store_op: StoreOp::Store,
+            final_layout: crate::ImageLayout::General,
+            stencil_final_layout: None,
+        }
+    }
+
+    /// Specifies the format of the attachment.
+    #[inline]
+    pub const fn format(mut self, format: ImageFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    #[inline]
+    pub const fn load_op(mut self, load_op: LoadOp) -> Self {
+        self.load_op = load_op;
+        self
+    }
+
+    #[inline]
+    pub const fn store_op(mut self, store_op: StoreOp) -> Self {
+        self.store_op = store_op;
+        self
+    }
+
+    /// Specifies the layout the attachment is transitioned to at the end of the RenderPass.
+    /// Use `ImageLayout::PresentSrc` for swapchain images so that no extra transition is needed.
+    #[inline]
+    pub const fn final_layout(mut self, final_layout: crate::ImageLayout) -> Self {
+        self.final_layout = final_layout;
+        self
+    }
+
+    /// Specifies a different final layout for the stencil aspect of a depth/stencil attachment.
+    /// Requires the `separate_depth_stencil_layouts` device feature.
+    #[inline]
+    pub const fn stencil_final_layout(mut self, layout: crate::ImageLayout) -> Self {
+        self.stencil_final_layout = Some(layout);
+        self
     }
 }
 
@@ -149,6 +280,8 @@ pub struct RenderPassDescriptor<'a> {
     load_op: LoadOp,
     store_op: StoreOp,
     subpasses: &'a [SubPass],
+    attachments: &'a [AttachmentDescriptor],
+    view_mask: u32,
 }
 
 impl<'a> RenderPassDescriptor<'a> {
@@ -159,6 +292,8 @@ impl<'a> RenderPassDescriptor<'a> {
             load_op: LoadOp::DontCare,
             store_op: StoreOp::Store,
             subpasses: &[],
+            attachments: &[],
+            view_mask: 0,
         }
     }
 
@@ -179,10 +314,34 @@ impl<'a> RenderPassDescriptor<'a> {
         self.subpasses = subpasses;
         self
     }
+
+    /// Specifies the attachments of the RenderPass.
+    /// If nothing is specified, a single R8G8B8A8Unorm attachment is used.
+    #[inline]
+    pub fn attachments(mut self, attachments: &'a [AttachmentDescriptor]) -> Self {
+        self.attachments = attachments;
+        self
+    }
+
+    /// Renders every subpass once per bit set in `view_mask`, e.g. `0b11` for both eyes.
+    /// Shaders tell the views apart with `gl_ViewIndex`, and the framebuffer attachments
+    /// must be array views with a layer per view.
+    /// Requires the `multiview` device feature.
+    #[inline]
+    pub fn view_mask(mut self, view_mask: u32) -> Self {
+        self.view_mask = view_mask;
+        self
+    }
 }
 
 pub struct RenderPass {
     pub(crate) render_pass: ash::vk::RenderPass,
+    pub(crate) attachment_count: usize,
+    /// Whether each attachment has a depth or stencil aspect.
+    pub(crate) depth_stencil_attachments: Vec<bool>,
+    pub(crate) color_attachment_counts: Vec<usize>,
+    pub(crate) depth_attachments: Vec<bool>,
+    pub(crate) view_mask: u32,
 }
 
 impl RenderPass {
@@ -193,27 +352,118 @@ impl RenderPass {
     /// * `descriptor` - Appropriate RenderPassDescriptor.
     #[inline]
     pub fn new(device: &Device, descriptor: &RenderPassDescriptor) -> NxResult<Self> {
-        let subpasses = descriptor
+        let attachments = if descriptor.attachments.is_empty() {
+            vec![AttachmentDescription::builder()
+                .format(Format::R8G8B8A8_UNORM)
+                .samples(SampleCountFlags::TYPE_1)
+                .load_op(AttachmentLoadOp::CLEAR)
+                .store_op(descriptor.store_op.into())
+                .stencil_load_op(AttachmentLoadOp::DONT_CARE)
+                .stencil_store_op(AttachmentStoreOp::DONT_CARE)
+                .initial_layout(ImageLayout::UNDEFINED)
+                .final_layout(ImageLayout::GENERAL)
+                .build()]
+        } else {
+            descriptor
+                .attachments
+                .iter()
+                .map(|x| {
+                    AttachmentDescription::builder()
+                        .format(x.format.into())
+                        .samples(SampleCountFlags::TYPE_1)
+                        .load_op(x.load_op.into())
+                        .store_op(x.store_op.into())
+                        .stencil_load_op(AttachmentLoadOp::DONT_CARE)
+                        .stencil_store_op(AttachmentStoreOp::DONT_CARE)
+                        .initial_layout(ImageLayout::UNDEFINED)
+                        .final_layout(x.final_layout.into())
+                        .build()
+                })
+                .collect::<Vec<AttachmentDescription>>()
+        };
+        let uses_separate_depth_stencil = descriptor
             .subpasses
             .iter()
-            .map(|x| x.subpass_desc)
-            .collect::<Vec<SubpassDescription>>();
-        let attachments = vec![AttachmentDescription::builder()
-            .format(Format::R8G8B8A8_UNORM)
-            .samples(SampleCountFlags::TYPE_1)
-            .load_op(AttachmentLoadOp::CLEAR)
-            .store_op(descriptor.store_op.into())
-            .stencil_load_op(AttachmentLoadOp::DONT_CARE)
-            .stencil_store_op(AttachmentStoreOp::DONT_CARE)
-            .initial_layout(ImageLayout::UNDEFINED)
-            .final_layout(ImageLayout::GENERAL)
-            .build()];
-        let create_info = RenderPassCreateInfo::builder()
-            .attachments(&attachments)
-            .subpasses(&subpasses)
-            .dependencies(&[])
-            .build();
-        let render_pass = match unsafe { device.device.create_render_pass(&create_info, None) } {
+            .any(|x| x.uses_separate_depth_stencil)
+            || descriptor.attachments.iter().any(|x| {
+                x.stencil_final_layout.is_some() || x.final_layout.is_separate_depth_stencil()
+            });
+        if uses_separate_depth_stencil && !device.features.separate_depth_stencil_layouts {
+            return Err(NxError::UnsupportedFeature(
+                "separateDepthStencilLayouts is not enabled on the device".to_string(),
+            ));
+        }
+        if descriptor.view_mask != 0 {
+            if !device.features.multiview {
+                return Err(NxError::UnsupportedFeature(
+                    "multiview is not enabled on the device".to_string(),
+                ));
+            }
+            let view_count = u32::BITS - descriptor.view_mask.leading_zeros();
+            if view_count > device.max_multiview_view_count {
+                return Err(NxError::InvalidDescriptor(format!(
+                    "the view mask uses {} views but maxMultiviewViewCount is {}",
+                    view_count, device.max_multiview_view_count
+                )));
+            }
+        }
+        for subpass in descriptor.subpasses {
+            for reference in subpass
+                .color_attachment_refs
+                .iter()
+                .chain(subpass.depth_stencil_attachment_ref.iter())
+            {
+                if reference.attachment as usize >= attachments.len() {
+                    return Err(NxError::InvalidDescriptor(format!(
+                        "subpass refers to attachment {} but the render pass has {} attachments",
+                        reference.attachment,
+                        attachments.len()
+                    )));
+                }
+            }
+        }
+        // Separate stencil layouts can only be expressed with the Vulkan 1.2 structures.
+        let separate_stencil = descriptor
+            .attachments
+            .iter()
+            .any(|x| x.stencil_final_layout.is_some())
+            || descriptor
+                .subpasses
+                .iter()
+                .any(|x| x.stencil_layout.is_some());
+        let result = if separate_stencil {
+            Self::create_with_stencil_layouts(device, descriptor, &attachments)
+        } else {
+            let subpasses = descriptor
+                .subpasses
+                .iter()
+                .map(|x| {
+                    let mut subpass = SubpassDescription::builder()
+                        .pipeline_bind_point(x.bind_point.into())
+                        .color_attachments(&x.color_attachment_refs);
+                    if let Some(reference) = x.depth_stencil_attachment_ref.as_ref() {
+                        subpass = subpass.depth_stencil_attachment(reference);
+                    }
+                    subpass.build()
+                })
+                .collect::<Vec<SubpassDescription>>();
+            let view_masks = vec![descriptor.view_mask; subpasses.len()];
+            let correlation_masks = [descriptor.view_mask];
+            let mut multiview = RenderPassMultiviewCreateInfo::builder()
+                .view_masks(&view_masks)
+                .correlation_masks(&correlation_masks)
+                .build();
+            let mut create_info = RenderPassCreateInfo::builder()
+                .attachments(&attachments)
+                .subpasses(&subpasses)
+                .dependencies(&[]);
+            if descriptor.view_mask != 0 {
+                create_info = create_info.push_next(&mut multiview);
+            }
+            let create_info = create_info.build();
+            unsafe { device.device.create_render_pass(&create_info, None) }
+        };
+        let render_pass = match result {
             Ok(x) => x,
             Err(e) => match e {
                 ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
@@ -221,7 +471,144 @@ impl RenderPass {
                 _ => Err(NxError::Unknown),
             }?,
         };
-        Ok(Self { render_pass })
+        Ok(Self {
+            render_pass,
+            attachment_count: attachments.len(),
+            depth_stencil_attachments: if descriptor.attachments.is_empty() {
+                vec![false]
+            } else {
+                descriptor
+                    .attachments
+                    .iter()
+                    .map(|x| x.format.aspect() != ImageAspectFlags::COLOR)
+                    .collect()
+            },
+            color_attachment_counts: descriptor
+                .subpasses
+                .iter()
+                .map(|x| x.color_attachment_refs.len())
+                .collect(),
+            depth_attachments: descriptor
+                .subpasses
+                .iter()
+                .map(|x| x.depth_stencil_attachment_ref.is_some())
+                .collect(),
+            view_mask: descriptor.view_mask,
+        })
+    }
+}
+
+impl RenderPass {
+    /// Creates the render pass with `vkCreateRenderPass2`, chaining the stencil layouts.
+    fn create_with_stencil_layouts(
+        device: &Device,
+        descriptor: &RenderPassDescriptor,
+        attachments: &[AttachmentDescription],
+    ) -> ash::prelude::VkResult<ash::vk::RenderPass> {
+        let mut stencil_layouts = attachments
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let stencil_final_layout = descriptor
+                    .attachments
+                    .get(i)
+                    .and_then(|x| x.stencil_final_layout)
+                    .map(|x| x.into())
+                    .unwrap_or(x.final_layout);
+                AttachmentDescriptionStencilLayout::builder()
+                    .stencil_initial_layout(x.initial_layout)
+                    .stencil_final_layout(stencil_final_layout)
+                    .build()
+            })
+            .collect::<Vec<AttachmentDescriptionStencilLayout>>();
+        let attachments = attachments
+            .iter()
+            .zip(stencil_layouts.iter_mut())
+            .map(|(x, stencil_layout)| {
+                AttachmentDescription2::builder()
+                    .format(x.format)
+                    .samples(x.samples)
+                    .load_op(x.load_op)
+                    .store_op(x.store_op)
+                    .stencil_load_op(x.stencil_load_op)
+                    .stencil_store_op(x.stencil_store_op)
+                    .initial_layout(x.initial_layout)
+                    .final_layout(x.final_layout)
+                    .push_next(stencil_layout)
+                    .build()
+            })
+            .collect::<Vec<AttachmentDescription2>>();
+
+        let color_refs = descriptor
+            .subpasses
+            .iter()
+            .map(|x| {
+                x.color_attachment_refs
+                    .iter()
+                    .map(|x| {
+                        AttachmentReference2::builder()
+                            .attachment(x.attachment)
+                            .layout(x.layout)
+                            .build()
+                    })
+                    .collect::<Vec<AttachmentReference2>>()
+            })
+            .collect::<Vec<_>>();
+        let mut stencil_refs = descriptor
+            .subpasses
+            .iter()
+            .map(|x| {
+                let reference = x.depth_stencil_attachment_ref?;
+                let stencil_layout = x
+                    .stencil_layout
+                    .map(|x| x.into())
+                    .unwrap_or(reference.layout);
+                Some((
+                    reference,
+                    AttachmentReferenceStencilLayout::builder()
+                        .stencil_layout(stencil_layout)
+                        .build(),
+                ))
+            })
+            .collect::<Vec<_>>();
+        let depth_stencil_refs = stencil_refs
+            .iter_mut()
+            .map(|x| {
+                x.as_mut().map(|(reference, stencil_layout)| {
+                    AttachmentReference2::builder()
+                        .attachment(reference.attachment)
+                        .layout(reference.layout)
+                        .push_next(stencil_layout)
+                        .build()
+                })
+            })
+            .collect::<Vec<Option<AttachmentReference2>>>();
+        let subpasses = descriptor
+            .subpasses
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                let mut subpass = SubpassDescription2::builder()
+                    .pipeline_bind_point(x.bind_point.into())
+                    .view_mask(descriptor.view_mask)
+                    .color_attachments(&color_refs[i]);
+                if let Some(reference) = depth_stencil_refs[i].as_ref() {
+                    subpass = subpass.depth_stencil_attachment(reference);
+                }
+                subpass.build()
+            })
+            .collect::<Vec<SubpassDescription2>>();
+        let correlated_view_masks = if descriptor.view_mask != 0 {
+            vec![descriptor.view_mask]
+        } else {
+            vec![]
+        };
+        let create_info = RenderPassCreateInfo2::builder()
+            .attachments(&attachments)
+            .subpasses(&subpasses)
+            .correlated_view_masks(&correlated_view_masks)
+            .build();
+        unsafe { device.device.create_render_pass2(&create_info, None) }
     }
 }
 
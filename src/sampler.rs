@@ -0,0 +1,192 @@
+use crate::{Destroy, Device, DeviceConnecter, ImageFormat, Instance, NxError, NxResult};
+use ash::vk::{SamplerAddressMode, SamplerCreateInfo, SamplerMipmapMode};
+
+/// Represents the filter used for texture lookups.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Filter {
+    Nearest,
+    Linear,
+}
+
+impl From<Filter> for ash::vk::Filter {
+    fn from(value: Filter) -> Self {
+        match value {
+            Filter::Nearest => ash::vk::Filter::NEAREST,
+            Filter::Linear => ash::vk::Filter::LINEAR,
+        }
+    }
+}
+
+impl From<Filter> for SamplerMipmapMode {
+    fn from(value: Filter) -> Self {
+        match value {
+            Filter::Nearest => SamplerMipmapMode::NEAREST,
+            Filter::Linear => SamplerMipmapMode::LINEAR,
+        }
+    }
+}
+
+/// Represents how texture coordinates outside [0, 1] are handled.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddressMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder,
+}
+
+impl From<AddressMode> for SamplerAddressMode {
+    fn from(value: AddressMode) -> Self {
+        match value {
+            AddressMode::Repeat => SamplerAddressMode::REPEAT,
+            AddressMode::MirroredRepeat => SamplerAddressMode::MIRRORED_REPEAT,
+            AddressMode::ClampToEdge => SamplerAddressMode::CLAMP_TO_EDGE,
+            AddressMode::ClampToBorder => SamplerAddressMode::CLAMP_TO_BORDER,
+        }
+    }
+}
+
+/// Stores information needed to create a Sampler.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerDescriptor {
+    mag_filter: Filter,
+    min_filter: Filter,
+    mipmap_filter: Filter,
+    address_mode: [AddressMode; 3],
+    max_anisotropy: f32,
+}
+
+impl SamplerDescriptor {
+    /// Initializes a new descriptor with default values.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_filter: Filter::Linear,
+            address_mode: [AddressMode::Repeat; 3],
+            max_anisotropy: 1.0,
+        }
+    }
+
+    #[inline]
+    pub const fn mag_filter(mut self, filter: Filter) -> Self {
+        self.mag_filter = filter;
+        self
+    }
+
+    #[inline]
+    pub const fn min_filter(mut self, filter: Filter) -> Self {
+        self.min_filter = filter;
+        self
+    }
+
+    #[inline]
+    pub const fn mipmap_filter(mut self, filter: Filter) -> Self {
+        self.mipmap_filter = filter;
+        self
+    }
+
+    /// Specifies the address mode of the u, v and w coordinates.
+    #[inline]
+    pub const fn address_mode(mut self, u: AddressMode, v: AddressMode, w: AddressMode) -> Self {
+        self.address_mode = [u, v, w];
+        self
+    }
+
+    /// Specifies the maximum anisotropy. 1.0 disables anisotropic filtering.
+    /// Values above 1.0 require the `sampler_anisotropy` device feature and are clamped to
+    /// `maxSamplerAnisotropy`. `Device::max_sampler_anisotropy` gives a value that is always valid.
+    #[inline]
+    pub const fn max_anisotropy(mut self, max_anisotropy: f32) -> Self {
+        self.max_anisotropy = max_anisotropy;
+        self
+    }
+
+    /// Replaces linear filters with nearest filtering if `format` cannot be filtered linearly.
+    /// Sampling an unfilterable format with a linear filter is undefined behavior.
+    /// # Arguments
+    ///
+    /// * `instance` - Appropriate Instance.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `format` - Format of the images sampled with this sampler.
+    pub fn fallback_for_format(
+        mut self,
+        instance: &Instance,
+        connecter: DeviceConnecter,
+        format: ImageFormat,
+    ) -> Self {
+        let linear =
+            [self.mag_filter, self.min_filter, self.mipmap_filter].contains(&Filter::Linear);
+        if linear && !connecter.supports_linear_filter(instance, format) {
+            warn!(
+                "format {:?} does not support linear filtering, falling back to nearest",
+                format
+            );
+            self.mag_filter = Filter::Nearest;
+            self.min_filter = Filter::Nearest;
+            self.mipmap_filter = Filter::Nearest;
+        }
+        self
+    }
+}
+
+pub struct Sampler {
+    pub(crate) sampler: ash::vk::Sampler,
+}
+
+impl Sampler {
+    /// Create a new Sampler.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `descriptor` - Appropriate SamplerDescriptor.
+    pub fn new(device: &Device, descriptor: &SamplerDescriptor) -> NxResult<Self> {
+        let anisotropy_enable = descriptor.max_anisotropy > 1.0;
+        if anisotropy_enable && !device.features.sampler_anisotropy {
+            return Err(NxError::UnsupportedFeature(format!(
+                "max_anisotropy {} requires samplerAnisotropy, which is not enabled on the device",
+                descriptor.max_anisotropy
+            )));
+        }
+        let max_anisotropy = if anisotropy_enable {
+            descriptor
+                .max_anisotropy
+                .min(device.limits.max_sampler_anisotropy)
+        } else {
+            1.0
+        };
+        let create_info = SamplerCreateInfo::builder()
+            .mag_filter(descriptor.mag_filter.into())
+            .min_filter(descriptor.min_filter.into())
+            .mipmap_mode(descriptor.mipmap_filter.into())
+            .address_mode_u(descriptor.address_mode[0].into())
+            .address_mode_v(descriptor.address_mode[1].into())
+            .address_mode_w(descriptor.address_mode[2].into())
+            .anisotropy_enable(anisotropy_enable)
+            .max_anisotropy(max_anisotropy)
+            .max_lod(ash::vk::LOD_CLAMP_NONE)
+            .build();
+        let sampler = match unsafe { device.device.create_sampler(&create_info, None) } {
+            Ok(x) => x,
+            Err(e) => {
+                return match e {
+                    ash::vk::Result::ERROR_OUT_OF_HOST_MEMORY => Err(NxError::OutOfHostMemory),
+                    ash::vk::Result::ERROR_OUT_OF_DEVICE_MEMORY => Err(NxError::OutOfDeviceMemory),
+                    _ => Err(NxError::Unknown),
+                }
+            }
+        };
+        Ok(Self { sampler })
+    }
+}
+
+impl Destroy for Sampler {
+    fn instance(&self, _: &Instance) {}
+
+    fn device(&self, device: &Device) {
+        unsafe {
+            device.device.destroy_sampler(self.sampler, None);
+        }
+    }
+}
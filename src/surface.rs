@@ -2,12 +2,20 @@ use raw_window_handle::{HasRawDisplayHandle, HasRawWindowHandle};
 
 use crate::{Instance, NxResult};
 
+/// A window that can be presented to.
+/// Each window needs a Surface of its own, and any number of them can exist at once.
+/// A Surface must outlive the Swapchain created from it.
 pub struct Surface {
     pub(crate) surface: ash::extensions::khr::Surface,
     pub(crate) khr: ash::vk::SurfaceKHR,
 }
 
 impl Surface {
+    /// Create a surface for a window.
+    /// # Arguments
+    ///
+    /// * `instance` - Instance created with `InstanceFeature::use_surface`.
+    /// * `handle` - Window to present to.
     pub fn new(
         instance: &Instance,
         handle: &(impl HasRawWindowHandle + HasRawDisplayHandle),
@@ -21,8 +29,7 @@ impl Surface {
                 handle.raw_window_handle(),
                 None,
             )
-        }
-        .unwrap();
+        }?;
         Ok(Self { surface, khr })
     }
 }
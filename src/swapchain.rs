@@ -1,23 +1,337 @@
 use ash::vk::{
-    ImageUsageFlags, PresentInfoKHR, Semaphore, SharingMode, SwapchainCreateInfoKHR, SwapchainKHR,
+    ColorSpaceKHR, Extent2D, HdrMetadataEXT, ImageUsageFlags, PresentInfoKHR, PresentModeKHR,
+    Semaphore, SharingMode, SurfaceCapabilitiesKHR, SurfaceFormatKHR, SurfaceKHR,
+    SwapchainCreateInfoKHR, SwapchainKHR, XYColorEXT,
 };
 
 use crate::{
-    Device, DeviceConnecter, Image, ImageFormat, Instance, NxError, NxResult,
-    QueuePresentDescriptor, Surface,
+    Device, DeviceConnecter, Extent2d, Image, ImageFormat, ImageView, ImageViewDescriptor,
+    Instance, NxError, NxResult, Queue, QueuePresentDescriptor, QueueSelection,
+    SemaphoreDescriptor, Surface,
 };
 
-#[derive(Clone, Copy, Debug)]
-pub enum SwapchainState {
-    Normal,
-    SubOptimal,
-    Broken,
+/// Result of `Swapchain::acquire_next_image`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AcquireResult {
+    /// The image with this index was acquired.
+    Acquired(usize),
+    /// The image with this index was acquired and can be rendered to and presented,
+    /// but the swapchain no longer matches the surface exactly and should be recreated soon.
+    Suboptimal(usize),
+    /// No image was acquired. The swapchain must be recreated before rendering.
+    OutOfDate,
 }
 
+impl AcquireResult {
+    /// Get the index of the acquired image, if one was acquired.
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            AcquireResult::Acquired(index) | AcquireResult::Suboptimal(index) => Some(*index),
+            AcquireResult::OutOfDate => None,
+        }
+    }
+}
+
+/// How presented images are synchronized with the display.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PresentMode {
+    /// Presents immediately, which may cause tearing.
+    Immediate,
+    /// Waits for the vertical blank, replacing the queued image with newer ones.
+    Mailbox,
+    /// Waits for the vertical blank, queueing the images. Always supported.
+    Fifo,
+    /// Like `Fifo`, but presents immediately if the vertical blank was missed.
+    FifoRelaxed,
+}
+
+impl From<PresentMode> for PresentModeKHR {
+    fn from(value: PresentMode) -> Self {
+        match value {
+            PresentMode::Immediate => PresentModeKHR::IMMEDIATE,
+            PresentMode::Mailbox => PresentModeKHR::MAILBOX,
+            PresentMode::Fifo => PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => PresentModeKHR::FIFO_RELAXED,
+        }
+    }
+}
+
+impl PresentMode {
+    #[doc(hidden)]
+    /// Converts a mode reported by the surface. Modes from extensions are not supported.
+    fn from_vk(value: PresentModeKHR) -> Option<Self> {
+        match value {
+            PresentModeKHR::IMMEDIATE => Some(Self::Immediate),
+            PresentModeKHR::MAILBOX => Some(Self::Mailbox),
+            PresentModeKHR::FIFO => Some(Self::Fifo),
+            PresentModeKHR::FIFO_RELAXED => Some(Self::FifoRelaxed),
+            _ => None,
+        }
+    }
+}
+
+/// How the colors of presented images are interpreted by the display.
+/// Color spaces other than `SrgbNonlinear` require `InstanceFeature::use_swapchain_colorspace`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ColorSpace {
+    /// Standard dynamic range sRGB. Always supported.
+    SrgbNonlinear,
+    /// scRGB, linear sRGB with values outside of 0.0..1.0, usually with a float format.
+    ExtendedSrgbLinear,
+    /// Display P3 with the sRGB transfer function.
+    DisplayP3Nonlinear,
+    /// HDR10, BT.2020 primaries with the ST 2084 (PQ) transfer function.
+    Hdr10St2084,
+    /// BT.2020 primaries with the hybrid log-gamma transfer function.
+    Hdr10Hlg,
+}
+
+impl From<ColorSpace> for ColorSpaceKHR {
+    fn from(value: ColorSpace) -> Self {
+        match value {
+            ColorSpace::SrgbNonlinear => ColorSpaceKHR::SRGB_NONLINEAR,
+            ColorSpace::ExtendedSrgbLinear => ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+            ColorSpace::DisplayP3Nonlinear => ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT,
+            ColorSpace::Hdr10St2084 => ColorSpaceKHR::HDR10_ST2084_EXT,
+            ColorSpace::Hdr10Hlg => ColorSpaceKHR::HDR10_HLG_EXT,
+        }
+    }
+}
+
+impl ColorSpace {
+    #[doc(hidden)]
+    /// Converts a color space reported by the surface. Other color spaces are not supported.
+    fn from_vk(value: ColorSpaceKHR) -> Option<Self> {
+        match value {
+            ColorSpaceKHR::SRGB_NONLINEAR => Some(Self::SrgbNonlinear),
+            ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => Some(Self::ExtendedSrgbLinear),
+            ColorSpaceKHR::DISPLAY_P3_NONLINEAR_EXT => Some(Self::DisplayP3Nonlinear),
+            ColorSpaceKHR::HDR10_ST2084_EXT => Some(Self::Hdr10St2084),
+            ColorSpaceKHR::HDR10_HLG_EXT => Some(Self::Hdr10Hlg),
+            _ => None,
+        }
+    }
+}
+
+/// Describes the display HDR content was mastered on, used by `Swapchain::set_hdr_metadata`.
+/// Chromaticities are CIE 1931 xy coordinates, luminances are in nits.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrMetadata {
+    primaries: [[f32; 2]; 3],
+    white_point: [f32; 2],
+    luminance: (f32, f32),
+    max_content_light_level: f32,
+    max_frame_average_light_level: f32,
+}
+
+impl HdrMetadata {
+    /// Initializes with BT.2020 primaries, a D65 white point and 0.0001 to 1000 nits.
+    #[inline]
+    pub const fn new() -> Self {
+        Self {
+            primaries: [[0.708, 0.292], [0.170, 0.797], [0.131, 0.046]],
+            white_point: [0.3127, 0.3290],
+            luminance: (0.0001, 1000.0),
+            max_content_light_level: 1000.0,
+            max_frame_average_light_level: 400.0,
+        }
+    }
+
+    /// Specifies the red, green and blue primaries and the white point of the display.
+    #[inline]
+    pub const fn primaries(
+        mut self,
+        red: [f32; 2],
+        green: [f32; 2],
+        blue: [f32; 2],
+        white_point: [f32; 2],
+    ) -> Self {
+        self.primaries = [red, green, blue];
+        self.white_point = white_point;
+        self
+    }
+
+    /// Specifies the minimum and maximum luminance of the display.
+    #[inline]
+    pub const fn luminance(mut self, min: f32, max: f32) -> Self {
+        self.luminance = (min, max);
+        self
+    }
+
+    /// Specifies the brightest pixel and the brightest frame average of the content.
+    #[inline]
+    pub const fn content_light_level(mut self, max: f32, max_frame_average: f32) -> Self {
+        self.max_content_light_level = max;
+        self.max_frame_average_light_level = max_frame_average;
+        self
+    }
+}
+
+impl Default for HdrMetadata {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<HdrMetadata> for HdrMetadataEXT {
+    fn from(value: HdrMetadata) -> Self {
+        let xy = |[x, y]: [f32; 2]| XYColorEXT { x, y };
+        HdrMetadataEXT::builder()
+            .display_primary_red(xy(value.primaries[0]))
+            .display_primary_green(xy(value.primaries[1]))
+            .display_primary_blue(xy(value.primaries[2]))
+            .white_point(xy(value.white_point))
+            .min_luminance(value.luminance.0)
+            .max_luminance(value.luminance.1)
+            .max_content_light_level(value.max_content_light_level)
+            .max_frame_average_light_level(value.max_frame_average_light_level)
+            .build()
+    }
+}
+
+/// How the swapchain trades latency against power usage.
+/// Resolved to a present mode and image count supported by the surface.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FramePolicy {
+    /// Presents as soon as possible.
+    /// Uses MAILBOX with three images if available, otherwise IMMEDIATE, otherwise FIFO.
+    LowLatency,
+    /// Synchronizes with the display using FIFO and one image more than the minimum.
+    Balanced,
+    /// Synchronizes with the display using FIFO and as few images as possible.
+    PowerSaving,
+}
+
+impl FramePolicy {
+    #[doc(hidden)]
+    /// Picks the present mode and image count for this policy.
+    fn resolve(
+        &self,
+        capabilities: &SurfaceCapabilitiesKHR,
+        present_modes: &[PresentModeKHR],
+    ) -> (PresentModeKHR, u32) {
+        // Some platforms report a minimum of 0, but a swapchain needs at least one image.
+        let min = capabilities.min_image_count.max(1);
+        let (present_mode, image_count) = match self {
+            FramePolicy::LowLatency => {
+                if present_modes.contains(&PresentModeKHR::MAILBOX) {
+                    (PresentModeKHR::MAILBOX, (min + 1).max(3))
+                } else if present_modes.contains(&PresentModeKHR::IMMEDIATE) {
+                    (PresentModeKHR::IMMEDIATE, min + 1)
+                } else {
+                    (PresentModeKHR::FIFO, min + 1)
+                }
+            }
+            FramePolicy::Balanced => (PresentModeKHR::FIFO, min + 1),
+            FramePolicy::PowerSaving => (PresentModeKHR::FIFO, min),
+        };
+        // A maximum of 0 means there is no limit.
+        let image_count = if capabilities.max_image_count == 0 {
+            image_count
+        } else {
+            image_count.min(capabilities.max_image_count)
+        };
+        (present_mode, image_count)
+    }
+}
+
+/// Stores information needed to create a Swapchain.
+pub struct SwapchainDescriptor {
+    frame_policy: FramePolicy,
+    fallback_extent: Option<(u32, u32)>,
+    present_mode_preference: Vec<PresentMode>,
+    color_space: ColorSpace,
+}
+
+impl SwapchainDescriptor {
+    /// Initializes a new descriptor with default values.
+    #[inline]
+    pub const fn empty() -> Self {
+        Self {
+            frame_policy: FramePolicy::Balanced,
+            fallback_extent: None,
+            present_mode_preference: Vec::new(),
+            color_space: ColorSpace::SrgbNonlinear,
+        }
+    }
+
+    /// Size of the swapchain images if the surface does not report its own size,
+    /// as with some window systems and offscreen surfaces. Usually the size of the window.
+    #[inline]
+    pub const fn fallback_extent(mut self, width: u32, height: u32) -> Self {
+        self.fallback_extent = Some((width, height));
+        self
+    }
+
+    /// Specifies how the present mode and image count are chosen.
+    #[inline]
+    pub const fn frame_policy(mut self, frame_policy: FramePolicy) -> Self {
+        self.frame_policy = frame_policy;
+        self
+    }
+
+    /// Specifies present modes in order of preference, e.g. `[Mailbox, Immediate, Fifo]`.
+    /// The first mode supported by the surface is used instead of the one chosen by the
+    /// frame policy, falling back to `PresentMode::Fifo`, which is always supported.
+    /// The image count still follows the frame policy.
+    #[inline]
+    pub fn present_mode_preference(mut self, present_modes: &[PresentMode]) -> Self {
+        self.present_mode_preference = present_modes.to_vec();
+        self
+    }
+
+    /// Specifies the color space of the images, for example `ColorSpace::Hdr10St2084` for HDR.
+    /// Falls back to `ColorSpace::SrgbNonlinear` if the surface does not support it,
+    /// see `Swapchain::color_space`.
+    #[inline]
+    pub fn color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+}
+
+/// Semaphores passed to the render closure of `Swapchain::with_frame`.
+pub struct FrameSemaphores {
+    image_available: crate::Semaphore,
+    render_finished: crate::Semaphore,
+}
+
+impl FrameSemaphores {
+    /// Signaled when the acquired image can be rendered to. The submission must wait on it.
+    pub fn image_available(&self) -> &crate::Semaphore {
+        &self.image_available
+    }
+
+    /// Waited on before presenting. The submission must signal it.
+    pub fn render_finished(&self) -> &crate::Semaphore {
+        &self.render_finished
+    }
+}
+
+/// Images presented to a Surface.
+/// Several swapchains, each with its own surface, can be created on one device,
+/// for example one per window.
 pub struct Swapchain {
     swapchain: ash::extensions::khr::Swapchain,
     khr: SwapchainKHR,
     format: ImageFormat,
+    image_count: u32,
+    extent: Extent2d,
+    images: Vec<Image>,
+    views: Vec<ImageView>,
+    surface: ash::extensions::khr::Surface,
+    surface_khr: SurfaceKHR,
+    connecter: DeviceConnecter,
+    frame_policy: FramePolicy,
+    present_mode_preference: Vec<PresentMode>,
+    present_mode: PresentMode,
+    color_space_preference: ColorSpace,
+    color_space: ColorSpace,
+    /// Queue families sharing the images, empty if only the graphics family uses them.
+    queue_family_indices: Vec<u32>,
+    generation: u64,
+    semaphores: Option<FrameSemaphores>,
+    device: ash::Device,
 }
 
 impl Swapchain {
@@ -26,48 +340,321 @@ impl Swapchain {
         instance: &Instance,
         device: &Device,
         connecter: DeviceConnecter,
+    ) -> NxResult<Self> {
+        Self::with_descriptor(
+            surface,
+            instance,
+            device,
+            connecter,
+            &SwapchainDescriptor::empty(),
+        )
+    }
+
+    /// Create a swapchain whose present mode and image count follow the frame policy.
+    /// # Arguments
+    ///
+    /// * `surface` - Surface to present to.
+    /// * `instance` - Appropriate Instance.
+    /// * `device` - Reference to the appropriate device.
+    /// * `connecter` - Appropriate device connecter.
+    /// * `descriptor` - Appropriate SwapchainDescriptor.
+    pub fn with_descriptor(
+        surface: &Surface,
+        instance: &Instance,
+        device: &Device,
+        connecter: DeviceConnecter,
+        descriptor: &SwapchainDescriptor,
     ) -> NxResult<Self> {
         if !connecter.is_support_swapchain(instance) {
             return Err(NxError::HardwareError);
         }
+        // Every window has its own surface, and the queue family may not present to all of them.
+        let present_queue_family_index = device.queue_family_index(QueueSelection::Present);
+        if !connecter.is_present_support(surface, present_queue_family_index) {
+            return Err(NxError::UnsupportedFeature(
+                "the present queue family of the device cannot present to the surface".to_string(),
+            ));
+        }
+        // Images rendered on the graphics queue and presented on another family are shared.
+        let queue_family_indices = if present_queue_family_index == device.queue_family_index {
+            vec![]
+        } else {
+            vec![
+                device.queue_family_index as u32,
+                present_queue_family_index as u32,
+            ]
+        };
 
-        let surface_capabilities = connecter.get_surface_capabilities(surface);
-        let surface_formats = connecter.get_surface_formats(surface);
-        let surface_present_modes = connecter.get_surface_present_modes(surface);
+        let swapchain = ash::extensions::khr::Swapchain::new(&instance.instance, &device.device);
+        let mut swapchain = Self {
+            swapchain,
+            khr: SwapchainKHR::null(),
+            format: ImageFormat::Undefined,
+            image_count: 0,
+            extent: Extent2d::new(0, 0),
+            images: vec![],
+            views: vec![],
+            surface: surface.surface.clone(),
+            surface_khr: surface.khr,
+            connecter,
+            frame_policy: descriptor.frame_policy,
+            present_mode_preference: descriptor.present_mode_preference.clone(),
+            present_mode: PresentMode::Fifo,
+            color_space_preference: descriptor.color_space,
+            color_space: ColorSpace::SrgbNonlinear,
+            queue_family_indices,
+            generation: 0,
+            semaphores: None,
+            device: device.device.clone(),
+        };
+        swapchain.create_khr(descriptor.fallback_extent)?;
+        Ok(swapchain)
+    }
 
-        let format = surface_formats[0];
-        let present_mode = surface_present_modes[0];
+    /// Creates the swapchain, replacing the current one if there is one.
+    /// `fallback_extent` is used when the surface lets the swapchain choose its extent.
+    fn create_khr(&mut self, fallback_extent: Option<(u32, u32)>) -> NxResult<()> {
+        let surface_capabilities = self
+            .connecter
+            .get_surface_capabilities(&self.surface, self.surface_khr)?;
+        let surface_formats = self
+            .connecter
+            .get_surface_formats(&self.surface, self.surface_khr)?;
+        let surface_present_modes = self
+            .connecter
+            .get_surface_present_modes(&self.surface, self.surface_khr)?;
 
+        let format = Self::choose_format(&surface_formats, self.color_space_preference.into());
+        let (mut present_mode, image_count) = self
+            .frame_policy
+            .resolve(&surface_capabilities, &surface_present_modes);
+        if !self.present_mode_preference.is_empty() {
+            present_mode = self
+                .present_mode_preference
+                .iter()
+                .map(|x| PresentModeKHR::from(*x))
+                .find(|x| surface_present_modes.contains(x))
+                .unwrap_or(PresentModeKHR::FIFO);
+        }
+        // An extent of u32::MAX means the surface size is determined by the swapchain.
+        let extent = match (surface_capabilities.current_extent.width, fallback_extent) {
+            (u32::MAX, Some((width, height))) => {
+                let min = surface_capabilities.min_image_extent;
+                let max = surface_capabilities.max_image_extent;
+                // Not `clamp`, which panics on drivers reporting a maximum below the minimum.
+                Extent2D {
+                    width: width.min(max.width).max(min.width).max(1),
+                    height: height.min(max.height).max(min.height).max(1),
+                }
+            }
+            (u32::MAX, None) => {
+                return Err(NxError::InvalidDescriptor(
+                    "the surface does not report its size and no fallback extent was given"
+                        .to_string(),
+                ))
+            }
+            _ => surface_capabilities.current_extent,
+        };
+
+        let sharing_mode = if self.queue_family_indices.is_empty() {
+            SharingMode::EXCLUSIVE
+        } else {
+            SharingMode::CONCURRENT
+        };
         let create_info = SwapchainCreateInfoKHR::builder()
-            .surface(surface.khr)
-            .min_image_count(surface_capabilities.min_image_count + 1)
+            .surface(self.surface_khr)
+            .min_image_count(image_count)
             .image_format(format.format)
             .image_color_space(format.color_space)
-            .image_extent(surface_capabilities.current_extent)
+            .image_extent(extent)
             .image_array_layers(1)
             .image_usage(ImageUsageFlags::COLOR_ATTACHMENT)
-            .image_sharing_mode(SharingMode::EXCLUSIVE)
+            .image_sharing_mode(sharing_mode)
+            .queue_family_indices(&self.queue_family_indices)
             .pre_transform(surface_capabilities.current_transform)
             .present_mode(present_mode)
             .clipped(true)
+            .old_swapchain(self.khr)
             .build();
-        let swapchain = ash::extensions::khr::Swapchain::new(&instance.instance, &device.device);
-        let khr = match unsafe { swapchain.create_swapchain(&create_info, None) } {
+        let khr = match unsafe { self.swapchain.create_swapchain(&create_info, None) } {
             Ok(x) => x,
             Err(e) => return Err(NxError::InternalError(e)),
         };
-        let format = format.format.into();
-        Ok(Self {
-            swapchain,
-            khr,
-            format,
-        })
+        self.destroy_views();
+        if self.khr != SwapchainKHR::null() {
+            unsafe {
+                self.swapchain.destroy_swapchain(self.khr, None);
+            }
+        }
+        self.khr = khr;
+        self.format = format.format.into();
+        self.color_space =
+            ColorSpace::from_vk(format.color_space).unwrap_or(ColorSpace::SrgbNonlinear);
+        self.image_count = image_count;
+        self.present_mode = PresentMode::from_vk(present_mode).unwrap_or(PresentMode::Fifo);
+        self.extent = extent.into();
+        self.create_views()
+    }
+
+    /// Picks the first format with `color_space`, otherwise the first sRGB format.
+    fn choose_format(formats: &[SurfaceFormatKHR], color_space: ColorSpaceKHR) -> SurfaceFormatKHR {
+        formats
+            .iter()
+            .find(|x| x.color_space == color_space)
+            .or_else(|| {
+                formats
+                    .iter()
+                    .find(|x| x.color_space == ColorSpaceKHR::SRGB_NONLINEAR)
+            })
+            .copied()
+            .unwrap_or(formats[0])
+    }
+
+    /// Wraps the images of the swapchain and creates a color view of each.
+    fn create_views(&mut self) -> NxResult<()> {
+        let images = unsafe { self.swapchain.get_swapchain_images(self.khr) }?;
+        let descriptor = ImageViewDescriptor::empty().format(self.format);
+        for image in images {
+            let image = Image::from_raw(image, self.format);
+            let view = ImageView::create(&self.device, &image, &descriptor)?;
+            self.images.push(image);
+            self.views.push(view);
+        }
+        Ok(())
+    }
+
+    fn destroy_views(&mut self) {
+        for view in self.views.drain(..) {
+            unsafe {
+                self.device.destroy_image_view(view.image_view, None);
+            }
+        }
+        self.images.clear();
+    }
+
+    /// Recreates the swapchain, for example after the window was resized.
+    /// Waits for the device to be idle first. The images and views returned by `images`
+    /// are rebuilt, so frame buffers created from the old views must be recreated.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `extent` - Size of the window, used if the surface does not report its own size.
+    pub fn recreate(&mut self, device: &Device, extent: (u32, u32)) -> NxResult<()> {
+        match unsafe { device.device.device_wait_idle() } {
+            Ok(_) => {}
+            Err(ash::vk::Result::ERROR_DEVICE_LOST) => return Err(NxError::DeviceLost),
+            Err(e) => return Err(NxError::InternalError(e)),
+        }
+        self.create_khr(Some(extent))?;
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Get the number of times the swapchain was recreated.
+    /// When this changes, image views and frame buffers of the old images must be rebuilt.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Acquires an image, calls `render` and presents the image.
+    /// When the swapchain is out of date or suboptimal, it is recreated with the extent
+    /// returned by `extent`. If acquiring fails because the swapchain is out of date,
+    /// the frame is retried once after recreating it.
+    /// Nothing is rendered while `extent` returns a zero size, e.g. while the window is minimized.
+    ///
+    /// The semaphores are reused every frame, so the work submitted by the previous frame
+    /// must have finished, e.g. by waiting on a fence, before `render` submits again.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `queue` - Queue to present on, usually `Device::queue(QueueSelection::Present)`.
+    /// * `extent` - Returns the current size of the window.
+    /// * `render` - Records and submits the frame for the image index.
+    ///   The submission must wait on `image_available` and signal `render_finished`.
+    pub fn with_frame<E, F>(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        mut extent: E,
+        mut render: F,
+    ) -> NxResult<()>
+    where
+        E: FnMut() -> (u32, u32),
+        F: FnMut(usize, &FrameSemaphores) -> NxResult<()>,
+    {
+        if self.semaphores.is_none() {
+            let image_available = crate::Semaphore::new(device, &SemaphoreDescriptor::empty())?;
+            let render_finished = match crate::Semaphore::new(device, &SemaphoreDescriptor::empty())
+            {
+                Ok(x) => x,
+                Err(e) => {
+                    device.destroy(&image_available);
+                    return Err(e);
+                }
+            };
+            self.semaphores = Some(FrameSemaphores {
+                image_available,
+                render_finished,
+            });
+        }
+        for _ in 0..2 {
+            let semaphores = self.semaphores.as_ref().unwrap();
+            let (index, suboptimal) = match unsafe {
+                self.swapchain.acquire_next_image(
+                    self.khr,
+                    u64::MAX,
+                    semaphores.image_available.semaphore,
+                    ash::vk::Fence::null(),
+                )
+            } {
+                Ok(x) => x,
+                Err(ash::vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    let extent = extent();
+                    if extent.0 == 0 || extent.1 == 0 {
+                        return Ok(());
+                    }
+                    self.recreate(device, extent)?;
+                    continue;
+                }
+                Err(ash::vk::Result::ERROR_DEVICE_LOST) => return Err(NxError::DeviceLost),
+                Err(e) => return Err(NxError::InternalError(e)),
+            };
+            render(index as usize, semaphores)?;
+
+            let present_info = PresentInfoKHR::builder()
+                .swapchains(&[self.khr])
+                .image_indices(&[index])
+                .wait_semaphores(&[semaphores.render_finished.semaphore])
+                .build();
+            let result = unsafe { self.swapchain.queue_present(queue.0, &present_info) };
+            return match result {
+                Ok(false) if !suboptimal => Ok(()),
+                Ok(_) | Err(ash::vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                    let extent = extent();
+                    if extent.0 == 0 || extent.1 == 0 {
+                        return Ok(());
+                    }
+                    self.recreate(device, extent)
+                }
+                Err(ash::vk::Result::ERROR_DEVICE_LOST) => Err(NxError::DeviceLost),
+                Err(e) => Err(NxError::InternalError(e)),
+            };
+        }
+        Err(NxError::InternalError(
+            ash::vk::Result::ERROR_OUT_OF_DATE_KHR,
+        ))
     }
 
+    /// Acquires the next image to render to.
+    /// Unlike `present`, an out of date swapchain is not an error, since no image was acquired
+    /// and the caller must recreate the swapchain before rendering.
+    /// # Arguments
+    ///
+    /// * `semaphore` - Signaled when the image can be rendered to.
     pub fn acquire_next_image(
         &self,
         semaphore: Option<&crate::Semaphore>,
-    ) -> NxResult<(usize, SwapchainState)> {
+    ) -> NxResult<AcquireResult> {
         let semaphore = match semaphore {
             None => Semaphore::null(),
             Some(x) => x.semaphore,
@@ -80,16 +667,10 @@ impl Swapchain {
                 ash::vk::Fence::null(),
             )
         } {
-            Ok(result) => {
-                let image = result.0 as usize;
-                let state = if result.1 {
-                    SwapchainState::Broken
-                } else {
-                    SwapchainState::Normal
-                };
-                Ok((image, state))
-            }
-
+            Ok((index, false)) => Ok(AcquireResult::Acquired(index as usize)),
+            Ok((index, true)) => Ok(AcquireResult::Suboptimal(index as usize)),
+            Err(ash::vk::Result::ERROR_OUT_OF_DATE_KHR) => Ok(AcquireResult::OutOfDate),
+            Err(ash::vk::Result::ERROR_DEVICE_LOST) => Err(NxError::DeviceLost),
             Err(e) => Err(NxError::InternalError(e)),
         }
     }
@@ -98,7 +679,87 @@ impl Swapchain {
         self.format
     }
 
-    pub fn present(&self, descriptor: &QueuePresentDescriptor, image: u32) -> NxResult<()> {
+    /// Get the present mode the swapchain was created with.
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Get the color space the swapchain was created with.
+    /// Differs from the requested one if the surface does not support it.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Get the color spaces supported by the surface.
+    pub fn supported_color_spaces(&self) -> NxResult<Vec<ColorSpace>> {
+        let mut color_spaces = vec![];
+        for format in self
+            .connecter
+            .get_surface_formats(&self.surface, self.surface_khr)?
+        {
+            match ColorSpace::from_vk(format.color_space) {
+                Some(x) if !color_spaces.contains(&x) => color_spaces.push(x),
+                _ => {}
+            }
+        }
+        Ok(color_spaces)
+    }
+
+    /// Describes the display the presented content was mastered on,
+    /// so that the display can map HDR content to its own range.
+    /// Requires the `hdr_metadata` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `metadata` - Primaries and luminance of the mastering display.
+    pub fn set_hdr_metadata(&self, device: &Device, metadata: &HdrMetadata) -> NxResult<()> {
+        let Some(hdr_metadata) = &device.hdr_metadata else {
+            return Err(NxError::UnsupportedFeature(
+                "hdr_metadata is not enabled on the device".to_string(),
+            ));
+        };
+        let metadata = HdrMetadataEXT::from(*metadata);
+        unsafe {
+            (hdr_metadata.set_hdr_metadata_ext)(device.device.handle(), 1, &self.khr, &metadata);
+        }
+        Ok(())
+    }
+
+    /// Get the present modes supported by the surface.
+    pub fn supported_present_modes(&self) -> NxResult<Vec<PresentMode>> {
+        Ok(self
+            .connecter
+            .get_surface_present_modes(&self.surface, self.surface_khr)?
+            .into_iter()
+            .filter_map(PresentMode::from_vk)
+            .collect())
+    }
+
+    /// Get the minimum number of images requested when the swapchain was created.
+    /// The implementation may create more.
+    pub fn image_count(&self) -> u32 {
+        self.image_count
+    }
+
+    /// Get the extent of the swapchain images, which the surface may have chosen
+    /// instead of the requested one. Updated by `recreate`.
+    /// Frame buffers and viewports should use this size.
+    pub fn extent(&self) -> Extent2d {
+        self.extent
+    }
+
+    /// Queues an image for presentation.
+    /// # Arguments
+    ///
+    /// * `queue` - Queue to present on, usually `Device::queue(QueueSelection::Present)`.
+    /// * `descriptor` - Appropriate QueuePresentDescriptor.
+    /// * `image` - Index of the image returned by `acquire_next_image`.
+    pub fn present(
+        &self,
+        queue: &Queue,
+        descriptor: &QueuePresentDescriptor,
+        image: u32,
+    ) -> NxResult<()> {
         let w_semaphores: Vec<Semaphore> = descriptor
             .wait_semaphores
             .iter()
@@ -110,31 +771,32 @@ impl Swapchain {
             .wait_semaphores(&w_semaphores)
             .build();
 
-        match unsafe {
-            self.swapchain
-                .queue_present(descriptor.queue.unwrap().0, &present_info)
-        } {
+        match unsafe { self.swapchain.queue_present(queue.0, &present_info) } {
             Ok(_) => Ok(()),
+            Err(ash::vk::Result::ERROR_DEVICE_LOST) => Err(NxError::DeviceLost),
             Err(e) => Err(NxError::InternalError(e)),
         }
     }
 
-    pub fn images(&self) -> NxResult<Vec<Image>> {
-        let images = unsafe { self.swapchain.get_swapchain_images(self.khr).unwrap() };
-        let images = images
-            .iter()
-            .map(|x| Image::from_raw(*x))
-            .collect::<Vec<Image>>();
-        if !images.is_empty() {
-            Ok(images)
-        } else {
-            Err(NxError::NoValue)
-        }
+    /// Get the images of the swapchain, in the order of the acquired image indices,
+    /// each with a color view owned by the swapchain.
+    /// The views are destroyed when the swapchain is recreated or dropped.
+    pub fn images(&self) -> impl Iterator<Item = (&Image, &ImageView)> {
+        self.images.iter().zip(self.views.iter())
     }
 }
 
 impl Drop for Swapchain {
     fn drop(&mut self) {
+        unsafe {
+            if let Some(semaphores) = self.semaphores.as_ref() {
+                self.device
+                    .destroy_semaphore(semaphores.image_available.semaphore, None);
+                self.device
+                    .destroy_semaphore(semaphores.render_finished.semaphore, None);
+            }
+        }
+        self.destroy_views();
         unsafe {
             self.swapchain.destroy_swapchain(self.khr, None);
         }
@@ -1,14 +1,29 @@
-use crate::{Destroy, Device, Instance, NxError, NxResult};
-use ash::vk::SemaphoreCreateInfo;
+use crate::{
+    Destroy, Device, ExternalSemaphoreHandle, ExternalSemaphoreHandleType, Instance, NxError,
+    NxResult,
+};
+use ash::vk::{ExportSemaphoreCreateInfo, SemaphoreCreateInfo};
 
 /// Stores information needed to create a Semaphore.
-pub struct SemaphoreDescriptor {}
+pub struct SemaphoreDescriptor {
+    export_handle_type: Option<ExternalSemaphoreHandleType>,
+}
 
 impl SemaphoreDescriptor {
     /// Initializes a new descriptor with default values.
     #[inline]
     pub fn empty() -> Self {
-        Self {}
+        Self {
+            export_handle_type: None,
+        }
+    }
+
+    /// Allows the semaphore to be exported as a handle of `handle_type`.
+    /// Requires the `external_semaphore` device feature.
+    #[inline]
+    pub fn export_handle_type(mut self, handle_type: ExternalSemaphoreHandleType) -> Self {
+        self.export_handle_type = Some(handle_type);
+        self
     }
 }
 
@@ -17,6 +32,7 @@ impl SemaphoreDescriptor {
 #[derive(Clone, Copy)]
 pub struct Semaphore {
     pub(crate) semaphore: ash::vk::Semaphore,
+    export_handle_type: Option<ExternalSemaphoreHandleType>,
 }
 
 impl Semaphore {
@@ -25,8 +41,18 @@ impl Semaphore {
     ///
     /// * `device` - Reference to the appropriate device.
     /// * `descriptor` - Appropriate SemaphoreDescriptor.
-    pub fn new(device: &Device, _descriptor: &SemaphoreDescriptor) -> NxResult<Self> {
-        let create_info = SemaphoreCreateInfo::builder().build();
+    pub fn new(device: &Device, descriptor: &SemaphoreDescriptor) -> NxResult<Self> {
+        let mut export_info = ExportSemaphoreCreateInfo::builder().handle_types(
+            descriptor
+                .export_handle_type
+                .map(|x| x.into())
+                .unwrap_or_default(),
+        );
+        let mut create_info = SemaphoreCreateInfo::builder();
+        if let Some(handle_type) = descriptor.export_handle_type {
+            Self::external_semaphore(device)?.validate_export(handle_type)?;
+            create_info = create_info.push_next(&mut export_info);
+        }
         let semaphore = match unsafe { device.device.create_semaphore(&create_info, None) } {
             Ok(x) => x,
             Err(e) => match e {
@@ -35,7 +61,44 @@ impl Semaphore {
                 _ => Err(NxError::Unknown),
             }?,
         };
-        Ok(Self { semaphore })
+        Ok(Self {
+            semaphore,
+            export_handle_type: descriptor.export_handle_type,
+        })
+    }
+
+    /// Export the payload of the semaphore as an OS handle.
+    /// The semaphore must be created with `export_handle_type`.
+    /// The caller owns the returned handle.
+    pub fn export(&self, device: &Device) -> NxResult<ExternalSemaphoreHandle> {
+        let handle_type = self.export_handle_type.ok_or_else(|| {
+            NxError::InvalidDescriptor("the semaphore was not created as exportable".to_string())
+        })?;
+        Self::external_semaphore(device)?.export(self.semaphore, handle_type)
+    }
+
+    /// Replace the payload of the semaphore with one exported by another API or process.
+    /// Requires the `external_semaphore` device feature.
+    /// # Arguments
+    ///
+    /// * `device` - Reference to the appropriate device.
+    /// * `handle` - Handle to import.
+    /// * `temporary` - If true, the imported payload is only used until the next wait.
+    pub fn import(
+        &self,
+        device: &Device,
+        handle: ExternalSemaphoreHandle,
+        temporary: bool,
+    ) -> NxResult<()> {
+        Self::external_semaphore(device)?.import(self.semaphore, handle, temporary)
+    }
+
+    fn external_semaphore(device: &Device) -> NxResult<&crate::external::ExternalSemaphoreLoader> {
+        device.external_semaphore.as_ref().ok_or_else(|| {
+            NxError::UnsupportedFeature(
+                "external_semaphore is not enabled on the device".to_string(),
+            )
+        })
     }
 }
 
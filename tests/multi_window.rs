@@ -0,0 +1,63 @@
+#![cfg(all(feature = "window", any(target_os = "linux", target_os = "windows")))]
+
+use nexg::{
+    DeviceFeatures, InstanceBuilder, InstanceFeature, RequestConnecterDescriptor, Surface,
+    Swapchain,
+};
+use winit::event_loop::EventLoopBuilder;
+#[cfg(target_os = "windows")]
+use winit::platform::windows::EventLoopBuilderExtWindows;
+#[cfg(target_os = "linux")]
+use winit::platform::x11::EventLoopBuilderExtX11;
+use winit::window::WindowBuilder;
+
+#[test]
+#[ignore = "requires a display and a Vulkan device"]
+fn two_surfaces_and_swapchains_on_one_device() {
+    // Tests do not run on the main thread.
+    let event_loop = EventLoopBuilder::new().with_any_thread(true).build();
+    let windows = (0..2)
+        .map(|_| {
+            WindowBuilder::new()
+                .with_visible(false)
+                .build(&event_loop)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let mut feature = InstanceFeature::empty();
+    feature.use_surface(&windows[0]).unwrap();
+    let instance = InstanceBuilder::new().feature(feature).build().unwrap();
+    let surfaces = windows
+        .iter()
+        .map(|x| Surface::new(&instance, x).unwrap())
+        .collect::<Vec<_>>();
+
+    let mut desc = RequestConnecterDescriptor::new().graphic_support(true);
+    for surface in &surfaces {
+        desc = desc.present_support(surface);
+    }
+    let connecter = instance.request_connecters(&[desc]).unwrap()[0];
+    let index = connecter.get_queue_family_index();
+    let device = connecter
+        .create_device_for_surfaces(
+            &instance,
+            index,
+            &surfaces.iter().collect::<Vec<_>>(),
+            &DeviceFeatures::empty(),
+        )
+        .unwrap();
+    let present_family = device.config().present_queue_family().unwrap_or(index);
+    for surface in &surfaces {
+        assert!(connecter.is_present_support(surface, present_family));
+    }
+
+    let swapchains = surfaces
+        .iter()
+        .map(|x| Swapchain::new(x, &instance, &device, connecter).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(swapchains.len(), 2);
+    for swapchain in &swapchains {
+        assert!(swapchain.images().count() > 0);
+    }
+}